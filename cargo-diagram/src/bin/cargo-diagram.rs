@@ -0,0 +1,3 @@
+fn main() -> Result<(), subprocess::PopenError> {
+    web3d::cli::run()
+}
@@ -0,0 +1,321 @@
+//! Post-processing of the rendered SVG: a logo or watermark, a title block, a footer & accessibility metadata.
+use minidom::{Element, Node};
+use std::io::{Error, ErrorKind};
+
+/// The namespace of SVG elements.
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+/// The logo bundled with the tool, used when `default` is given instead of the path of a logo.
+pub const DEFAULT_LOGO: &str = include_str!("logo.svg");
+/// The height of the band above the diagram holding the logo & the title block.
+const HEADER_HEIGHT: f64 = 48.0;
+/// The height of the band below the diagram holding the footer.
+const FOOTER_HEIGHT: f64 = 28.0;
+/// The space between the borders of the image & the branding.
+const INSET: f64 = 8.0;
+
+/// The branding added to a rendered diagram.
+#[derive(Debug, Clone, Default)]
+pub struct Branding {
+    /// The title of the image read out by assistive technology, e.g. the name of the contract
+    pub title: String,
+    /// The description of the image read out by assistive technology
+    pub description: String,
+    /// The SVG source of the logo drawn in the top left corner
+    pub logo: Option<String>,
+    /// The SVG source of the watermark drawn translucently over the middle of the diagram
+    pub watermark: Option<String>,
+    /// The name & version of the contract, written above the diagram
+    pub title_block: Option<(String, Option<String>)>,
+    /// The text written below the diagram, e.g. the generation time & git revision
+    pub footer: Option<String>,
+    /// The colour of the title block & footer
+    pub color: String,
+}
+
+/// Returns the four numbers of a `viewBox` attribute.
+///
+/// # Arguments
+///
+/// * `view_box` - The value of the attribute
+fn parse_view_box(view_box: &str) -> Option<(f64, f64, f64, f64)> {
+    let numbers: Vec<f64> = view_box
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [x, y, width, height] => Some((x, y, width, height)),
+        _ => None,
+    }
+}
+
+/// Returns a length attribute in pixels, `None` for relative lengths like percentages.
+///
+/// # Arguments
+///
+/// * `length` - The value of the attribute
+fn parse_length(length: &str) -> Option<f64> {
+    length.trim().trim_end_matches("px").parse().ok()
+}
+
+/// Returns the prefixed namespaces declared by the root element of an SVG, e.g. `xmlns:xlink`.
+///
+/// minidom keeps the prefixes of the attributes but drops these declarations, which are copied back by hand.
+///
+/// # Arguments
+///
+/// * `svg` - The SVG source
+fn prefix_declarations(svg: &str) -> Vec<(String, String)> {
+    let start_tag = svg
+        .find("<svg")
+        .and_then(|start| svg[start..].find('>').map(|end| &svg[start..start + end]))
+        .unwrap_or_default();
+    start_tag
+        .match_indices("xmlns:")
+        .filter_map(|(index, _)| {
+            let (name, value) = start_tag[index..].split_once('=')?;
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            Some((name.trim().to_string(), value[1..].split(quote).next()?.to_string()))
+        })
+        .collect()
+}
+
+/// Returns an image as an SVG element nested in the diagram, fitted into a box.
+///
+/// # Arguments
+///
+/// * `source` - The SVG source of the image
+/// * `bounds` - The left, top, width & height of the box
+fn nested_image(
+    source: &str,
+    bounds: (f64, f64, f64, f64),
+) -> Result<Element, Error> {
+    let mut image: Element = source
+        .parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Invalid SVG image: {}", e)))?;
+    for (name, value) in prefix_declarations(source) {
+        image.set_attr(name, value);
+    }
+    // Images without a view box are scaled from their own size
+    if image.attr("viewBox").is_none() {
+        if let (Some(width), Some(height)) = (
+            image.attr("width").and_then(parse_length),
+            image.attr("height").and_then(parse_length),
+        ) {
+            image.set_attr("viewBox", format!("0 0 {} {}", width, height));
+        }
+    }
+    let (x, y, width, height) = bounds;
+    image.set_attr("x", x.to_string());
+    image.set_attr("y", y.to_string());
+    image.set_attr("width", width.to_string());
+    image.set_attr("height", height.to_string());
+    Ok(image)
+}
+
+/// Returns a text element of the branding.
+///
+/// # Arguments
+///
+/// * `class` - The class of the element
+/// * `position` - The position of the text
+/// * `anchor` - The `text-anchor` of the text
+/// * `font_size` - The font size of the text
+/// * `color` - The colour of the text
+fn text(
+    class: &str,
+    position: (f64, f64),
+    anchor: &str,
+    font_size: f64,
+    color: &str,
+) -> Element {
+    Element::builder("text", SVG_NS)
+        .attr("class", class)
+        .attr("x", position.0.to_string())
+        .attr("y", position.1.to_string())
+        .attr("text-anchor", anchor)
+        .attr("font-size", font_size.to_string())
+        .attr("fill", color)
+        .build()
+}
+
+/// Returns the SVG with the branding added, the view box growing to make room for the title block & footer.
+///
+/// # Arguments
+///
+/// * `svg` - The rendered SVG
+/// * `branding` - The branding to add
+pub fn brand(
+    svg: &str,
+    branding: &Branding,
+) -> Result<String, Error> {
+    let root: Element = svg
+        .parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Invalid SVG: {}", e)))?;
+    let (x, y, width, height) = match root.attr("viewBox").and_then(parse_view_box) {
+        Some(view_box) => view_box,
+        None => (
+            0.0,
+            0.0,
+            root.attr("width").and_then(parse_length).unwrap_or(800.0),
+            root.attr("height").and_then(parse_length).unwrap_or(600.0),
+        ),
+    };
+    let header = if branding.logo.is_some() || branding.title_block.is_some() {
+        HEADER_HEIGHT
+    } else {
+        0.0
+    };
+    let footer = if branding.footer.is_some() { FOOTER_HEIGHT } else { 0.0 };
+
+    // Rebuild the root, replacing its accessibility metadata & growing it by the header & footer
+    let mut branded = Element::builder(root.name(), root.ns());
+    for (name, value) in root.attrs() {
+        branded = branded.attr(name, value);
+    }
+    let mut branded = branded.build();
+    for (name, value) in prefix_declarations(svg) {
+        branded.set_attr(name, value);
+    }
+    branded.set_attr("viewBox", format!("{} {} {} {}", x, y - header, width, height + header + footer));
+    if let Some(pixels) = root.attr("height").and_then(parse_length) {
+        branded.set_attr("height", (pixels * (height + header + footer) / height).to_string());
+    }
+    branded.set_attr("role", "img");
+    let mut title = Element::builder("title", SVG_NS).build();
+    title.append_text_node(branding.title.as_str());
+    branded.append_child(title);
+    let mut description = Element::builder("desc", SVG_NS).build();
+    description.append_text_node(branding.description.as_str());
+    branded.append_child(description);
+    for node in root.nodes() {
+        match node {
+            Node::Element(element) if element.is("title", SVG_NS) || element.is("desc", SVG_NS) => {}
+            // Backgrounds covering the whole image move along with the top of the view box
+            Node::Element(element)
+                if element.is("rect", SVG_NS)
+                    && element.attr("width") == Some("100%")
+                    && element.attr("height") == Some("100%") =>
+            {
+                let mut background = element.clone();
+                background.set_attr("x", x.to_string());
+                background.set_attr("y", (y - header).to_string());
+                branded.append_child(background);
+            }
+            node => branded.append_node(node.clone()),
+        }
+    }
+
+    // Push the watermark over the middle of the diagram, translucent & ignoring the pointer
+    if let Some(watermark) = &branding.watermark {
+        let side = width.min(height) / 2.0;
+        let mut watermark = nested_image(
+            watermark,
+            (x + (width - side) / 2.0, y + (height - side) / 2.0, side, side),
+        )?;
+        watermark.set_attr("class", "watermark");
+        watermark.set_attr("opacity", "0.08");
+        watermark.set_attr("pointer-events", "none");
+        branded.append_child(watermark);
+    }
+
+    // Push the logo & the title block into the header
+    let mut title_left = x + INSET;
+    if let Some(logo) = &branding.logo {
+        let side = HEADER_HEIGHT - 2.0 * INSET;
+        let mut logo = nested_image(logo, (x + INSET, y - header + INSET, side, side))?;
+        logo.set_attr("class", "logo");
+        branded.append_child(logo);
+        title_left += side + INSET;
+    }
+    if let Some((name, version)) = &branding.title_block {
+        let mut title = text(
+            "title-block",
+            (title_left, y - header / 2.0 + 7.0),
+            "start",
+            20.0,
+            &branding.color,
+        );
+        title.set_attr("font-weight", "bold");
+        title.append_text_node(name.as_str());
+        if let Some(version) = version {
+            let mut version_span = Element::builder("tspan", SVG_NS)
+                .attr("dx", "8")
+                .attr("font-size", "14")
+                .attr("font-weight", "normal")
+                .attr("opacity", "0.7")
+                .build();
+            version_span.append_text_node(format!("v{}", version));
+            title.append_child(version_span);
+        }
+        branded.append_child(title);
+    }
+
+    // Push the footer into the bottom right corner
+    if let Some(footer_text) = &branding.footer {
+        let mut footer_element = text(
+            "footer",
+            (x + width - INSET, y + height + footer - 10.0),
+            "end",
+            11.0,
+            &branding.color,
+        );
+        footer_element.set_attr("opacity", "0.7");
+        footer_element.append_text_node(footer_text.as_str());
+        branded.append_child(footer_element);
+    }
+
+    Ok(String::from(&branded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"200\" height=\"100\" viewBox=\"0 0 200 100\"><title>old</title><a xlink:href=\"https://example.com\"><rect width=\"50\" height=\"50\"/></a></svg>";
+
+    #[test]
+    fn it_only_adds_metadata_by_default() {
+        let branded = brand(
+            SVG,
+            &Branding {
+                title: "example".to_string(),
+                description: "The methods of example".to_string(),
+                ..Branding::default()
+            },
+        )
+        .unwrap();
+
+        assert!(branded.contains("viewBox=\"0 0 200 100\""));
+        assert!(branded.contains("<title>example</title><desc>The methods of example</desc>"));
+        assert!(!branded.contains("old"));
+        assert!(branded.contains("https://example.com"));
+        assert!(usvg::Tree::from_str(&branded, &usvg::Options::default().to_ref()).is_ok());
+    }
+
+    #[test]
+    fn it_makes_room_for_the_title_block_and_footer() {
+        let branded = brand(
+            SVG,
+            &Branding {
+                title: "example".to_string(),
+                logo: Some(DEFAULT_LOGO.to_string()),
+                watermark: Some(DEFAULT_LOGO.to_string()),
+                title_block: Some(("example".to_string(), Some("0.1.0".to_string()))),
+                footer: Some("Generated 2022-04-01 12:00 UTC from abc1234".to_string()),
+                color: "#333333".to_string(),
+                ..Branding::default()
+            },
+        )
+        .unwrap();
+
+        assert!(branded.contains("viewBox=\"0 -48 200 176\""));
+        assert!(branded.contains("height=\"176\""));
+        assert!(branded.contains("class=\"logo\""));
+        assert!(branded.contains("class=\"watermark\""));
+        assert!(branded.contains(">v0.1.0</tspan>"));
+        assert!(branded.contains(">Generated 2022-04-01 12:00 UTC from abc1234</text>"));
+        assert!(usvg::Tree::from_str(&branded, &usvg::Options::default().to_ref()).is_ok());
+    }
+}
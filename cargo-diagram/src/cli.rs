@@ -0,0 +1,1744 @@
+//! The command line interface shared by the `web3d` binary & the `cargo diagram` subcommand.
+//! Mermaid.js diagrams are rendered with [mermaid-cli](https://github.com/mermaid-js/mermaid-cli).
+//!
+//! Every subcommand reads the contract crate given with `--manifest-path`, or else the one the current directory is in, and
+//! writes to the path given with `--output`, by default into the `res` directory of the crate.
+//!
+//! The defaults of the options are read from the `web3d.toml` of the crate, or from the `[package.metadata.web3d]` table of
+//! its manifest, see [crate::config]. The options given on the command line override them.
+//!
+//! For more detailed info run with the `--help` flag.
+use crate::branding::{self, Branding, DEFAULT_LOGO};
+use crate::config::{self, Config, RenderConfig, CONFIG_FILE};
+use crate::markers::{self, BlockOptions};
+use crate::serve::{self, Diagram, Page, Preview};
+use crate::svg;
+use crate::watch::{Watcher, DEBOUNCE};
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::io::{self, Error, ErrorKind, Write};
+use std::net::TcpListener;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use subprocess::{Exec, Popen, PopenConfig, Redirection};
+use tempfile::TempDir;
+
+use mermaid_markdown_api::api_diff::ContractDiff;
+use mermaid_markdown_api::entrypoints::{self, EntrypointIndex, IndexEntry};
+use mermaid_markdown_api::lint::{self, Rule};
+use mermaid_markdown_api::scanner_pipeline::{
+    GroupBy, LegendPlacement, PipelineOptions, ScannerPipeline, SourceLinks,
+};
+use mermaid_markdown_api::scope::{DiagramScope, Hidden};
+use mermaid_markdown_api::structurizr::StructurizrWorkspace;
+use mermaid_markdown_api::syntax::{FlowDirection, Renderer, Syntax};
+use mermaid_markdown_api::theme::Theme;
+use scanner_syn::contract_descriptor::{ContractInfo, DefaultContractDescriptor, ScanCache, SourceFilter};
+use std::env;
+use std::fs;
+use strum::IntoEnumIterator;
+
+#[derive(Parser, Debug)]
+#[clap(name = "web3d", author, version, about, long_about = None)]
+pub struct Cli {
+    /// Path to the Cargo.toml of the contract crate. Optional. Default: the closest one above the current directory
+    #[clap(long, parse(from_os_str), value_name = "PATH", global = true)]
+    manifest_path: Option<PathBuf>,
+    /// Suppress log output
+    #[clap(short, long, global = true)]
+    quiet: bool,
+    #[clap(subcommand)]
+    command: Command,
+    /// The scanned files, re-used by the scans of the watch mode & the preview server
+    #[clap(skip)]
+    scan_cache: RefCell<ScanCache>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Scan the crate and write the source of its diagram: Mermaid, DOT, PlantUML, D2, draw.io, GraphML, Cytoscape.js or Structurizr
+    Scan(ScanArgs),
+    /// Write a diagram of every public method of the crate, with everything it calls, along with an index linking them
+    Batch(BatchArgs),
+    /// Render the diagram of the crate into an SVG, PNG or PDF image
+    Render(RenderArgs),
+    /// Export a rendered SVG into a PNG or PDF image
+    Export(ExportArgs),
+    /// Check the contract for private helpers never called, callbacks never scheduled and payable views
+    Lint(LintArgs),
+    /// Serve a live preview of the diagram, reloading the browser whenever the sources of the crate change
+    Serve(ServeArgs),
+    /// Replace the diagrams between <!-- web3d:start --> and <!-- web3d:end --> comments of Markdown files
+    Update(UpdateArgs),
+    /// Compare the public methods of two versions of the crate, two directories or two git revisions
+    Diff(DiffArgs),
+    /// Write a web3d.toml configuration with every option at its default into the crate
+    Init,
+}
+
+/// Generates an output of the watch mode, returning its path.
+type Generate<'a> = Box<dyn Fn() -> Result<PathBuf, subprocess::PopenError> + 'a>;
+
+/// The options deciding what the diagram shows & how it looks.
+#[derive(Args, Debug, Default)]
+struct DiagramArgs {
+    /// Direction of the diagram. Either TD, TB, BT, LR or BL. Optional. Default: TD
+    #[clap(short, long, value_name = "DIRECTION")]
+    direction: Option<FlowDirection>,
+    /// Group the diagram into subgraphs. Either impl, trait, file or module. Optional
+    #[clap(short, long, value_name = "KEY")]
+    group_by: Option<GroupBy>,
+    /// Label the connections by relationship, e.g. calls, callback, cross-contract, emits, transfers
+    #[clap(short, long, overrides_with = "no-edge-labels")]
+    edge_labels: bool,
+    /// Don't label the connections by relationship, even when the configuration does
+    #[clap(long, overrides_with = "edge-labels")]
+    no_edge_labels: bool,
+    /// Label the connections with the number of call sites
+    #[clap(short, long, overrides_with = "no-call-counts")]
+    call_counts: bool,
+    /// Don't label the connections with the number of call sites, even when the configuration does
+    #[clap(long, overrides_with = "call-counts")]
+    no_call_counts: bool,
+    /// Theme of the diagram. Either light, dark, high-contrast, print or the path to a TOML/JSON theme file. Optional. Default: light
+    #[clap(short, long, value_name = "THEME")]
+    theme: Option<String>,
+    /// Add a legend of the shapes, colours and connections used. Either subgraph (inside the diagram) or markdown (a separate NAME-legend.md file). Optional
+    #[clap(short, long, value_name = "PLACEMENT")]
+    legend: Option<LegendPlacement>,
+    /// Link the nodes to their source. Either a URL template like 'https://github.com/org/repo/blob/{rev}/{path}#L{line}' or 'file' for local file:// links. Optional
+    #[clap(short = 'L', long, value_name = "TEMPLATE")]
+    link_template: Option<String>,
+    /// Revision substituted for {rev} in the link template. Optional. Default: the current git commit
+    #[clap(short = 'r', long, value_name = "REV")]
+    link_rev: Option<String>,
+    /// Leave out the methods whose name matches a glob, e.g. 'internal_*'. Can be repeated. Optional
+    #[clap(long = "exclude-method", alias = "exclude", value_name = "GLOB", multiple_occurrences = true)]
+    exclude_methods: Vec<String>,
+    /// Only show this method and the functions it calls. Optional
+    #[clap(long, value_name = "METHOD")]
+    focus: Option<String>,
+    /// Levels of calls followed from the focused method, or from the public methods without one. Optional. Default: all of them
+    #[clap(long, value_name = "N")]
+    depth: Option<usize>,
+    /// Show the functions calling the focused method rather than the ones it calls
+    #[clap(long, requires = "focus")]
+    reverse: bool,
+    /// Leave out a kind of functions, along with the calls to them. Either private, process, out-of-scope or events. Can be repeated. Optional
+    #[clap(long = "hide", value_name = "KIND", multiple_occurrences = true)]
+    hidden: Vec<Hidden>,
+    /// Merge the private helpers called from a single function into their caller
+    #[clap(long, overrides_with = "no-collapse-helpers")]
+    collapse_helpers: bool,
+    /// Don't merge the private helpers into their caller, even when the configuration does
+    #[clap(long, overrides_with = "collapse-helpers")]
+    no_collapse_helpers: bool,
+}
+
+/// The branding added to rendered images.
+#[derive(Args, Debug, Clone, Default)]
+struct BrandingArgs {
+    /// SVG logo drawn in the top left corner of the image, or default for the web3d logo. Optional
+    #[clap(long, value_name = "FILE")]
+    logo: Option<String>,
+    /// SVG image drawn as a translucent watermark over the middle of the image, or default for the web3d logo. Optional
+    #[clap(long, value_name = "FILE")]
+    watermark: Option<String>,
+    /// Add a title block with the name and version of the contract crate above the image
+    #[clap(long, overrides_with = "no-title-block")]
+    title_block: bool,
+    /// Don't add a title block, even when the configuration does
+    #[clap(long, overrides_with = "title-block")]
+    no_title_block: bool,
+    /// Add a footer with the generation time and git revision below the image
+    #[clap(long, overrides_with = "no-footer")]
+    footer: bool,
+    /// Don't add a footer, even when the configuration does
+    #[clap(long, overrides_with = "footer")]
+    no_footer: bool,
+}
+
+/// The size & background of rendered images.
+#[derive(Args, Debug, Clone, Default)]
+struct SizeArgs {
+    /// Scale factor, multiplying the size of PNG and PDF output. Optional. Default: 1
+    #[clap(short, long, value_name = "SCALE")]
+    scale: Option<String>,
+    /// Height of the image. Optional. mmdc: the height of its page, default 600. PNG and PDF, from either renderer: the height the image is fitted to keeping its aspect ratio, default the diagram's own height. Not applied to the SVG of the native renderer
+    #[clap(short = 'H', long, value_name = "HEIGHT")]
+    height: Option<String>,
+    /// Width of the image. Optional. mmdc: the width of its page, default 800. PNG and PDF, from either renderer: the width the image is fitted to keeping its aspect ratio, default the diagram's own width. Not applied to the SVG of the native renderer
+    #[clap(short, long, value_name = "WIDTH")]
+    width: Option<String>,
+    /// Background color. Example: transparent, red, '#F0F0F0'. Optional. Default: white
+    #[clap(short, long, value_name = "COLOR")]
+    background_color: Option<String>,
+}
+
+#[derive(Args, Debug, Default)]
+struct ScanArgs {
+    #[clap(flatten)]
+    diagram: DiagramArgs,
+    /// Syntax of the diagram. Either mermaid, dot (Graphviz), plantuml, plantuml_sequence, d2, drawio (draw.io), graphml, cytoscape (Cytoscape.js elements JSON), structurizr (C4 model in the Structurizr DSL), svg, html (interactive report) or markdown (contract documentation). Optional. Default: mermaid
+    #[clap(short, long, value_name = "FORMAT")]
+    format: Option<Syntax>,
+    /// Another contract crate to model alongside this one, as a further container of the Structurizr workspace. Can be repeated. Optional
+    #[clap(long = "with-contract", parse(from_os_str), value_name = "DIR", multiple_occurrences = true)]
+    with_contracts: Vec<PathBuf>,
+    /// Output file, or - for the standard output. Optional. Default: ./res/CRATE.EXTENSION in the crate
+    #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Keep running, scanning the crate again whenever a .rs file or Cargo.toml of it changes
+    #[clap(long)]
+    watch: bool,
+}
+
+#[derive(Args, Debug, Default)]
+struct BatchArgs {
+    #[clap(flatten)]
+    diagram: DiagramArgs,
+    /// Syntax of the diagrams, like the format of scan. Optional. Default: mermaid
+    #[clap(short, long, value_name = "FORMAT")]
+    format: Option<Syntax>,
+    /// Output directory. Optional. Default: ./res/CRATE-entrypoints in the crate
+    #[clap(short, long, parse(from_os_str), value_name = "DIR")]
+    output: Option<PathBuf>,
+    /// Keep running, writing the diagrams again whenever a .rs file or Cargo.toml of the crate changes
+    #[clap(long)]
+    watch: bool,
+}
+
+#[derive(Args, Debug, Default)]
+struct RenderArgs {
+    #[clap(flatten)]
+    diagram: DiagramArgs,
+    #[clap(flatten)]
+    branding: BrandingArgs,
+    #[clap(flatten)]
+    size: SizeArgs,
+    /// Renderer of the diagram. Either mmdc (mermaid-cli, needs Node.js and Chromium) or native (built in). Optional. Default: mmdc
+    #[clap(short = 'R', long, value_name = "RENDERER")]
+    renderer: Option<Renderer>,
+    /// Output file. It should be either svg, png or pdf. Optional. Default: ./res/CRATE.svg in the crate
+    #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Open the image in the browser
+    #[clap(short = 'O', long, alias = "openb")]
+    open: bool,
+    /// Keep running, rendering the diagram again whenever a .rs file or Cargo.toml of the crate changes
+    #[clap(long)]
+    watch: bool,
+}
+
+#[derive(Args, Debug)]
+struct ExportArgs {
+    /// The SVG image to export
+    #[clap(parse(from_os_str), value_name = "SVG")]
+    input: PathBuf,
+    /// Output file. It should be either png or pdf
+    #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+    output: PathBuf,
+    #[clap(flatten)]
+    size: SizeArgs,
+}
+
+#[derive(Args, Debug)]
+struct UpdateArgs {
+    #[clap(flatten)]
+    diagram: DiagramArgs,
+    /// Markdown file holding web3d blocks. Can be repeated. Optional. Default: README.md in the crate
+    #[clap(parse(from_os_str), value_name = "FILE")]
+    files: Vec<PathBuf>,
+    /// Don't write anything, failing when a block isn't up to date
+    #[clap(long)]
+    check: bool,
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    #[clap(flatten)]
+    diagram: DiagramArgs,
+    /// The old version of the crate: its directory, its Cargo.toml or a git revision of the repository of the crate
+    #[clap(value_name = "OLD")]
+    old: String,
+    /// The new version of the crate, like the old one. Optional. Default: the crate as it is
+    #[clap(value_name = "NEW")]
+    new: Option<String>,
+    /// Output file, or - for the standard output. Optional. Default: ./res/CRATE-diff.md in the crate
+    #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct LintArgs {
+    /// A rule not to check. Either unused-helper, uncalled-callback or payable-view. Can be repeated. Optional
+    #[clap(long = "allow", value_name = "RULE", multiple_occurrences = true)]
+    allowed: Vec<Rule>,
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    #[clap(flatten)]
+    diagram: DiagramArgs,
+    #[clap(flatten)]
+    branding: BrandingArgs,
+    /// Renderer of the diagram. Either mmdc (Mermaid.js, rendered in the browser) or native (built in). Optional. Default: mmdc
+    #[clap(short = 'R', long, value_name = "RENDERER")]
+    renderer: Option<Renderer>,
+    /// Port of the preview server. Optional. Default: 7878
+    #[clap(short, long, value_name = "PORT", default_value = "7878")]
+    port: u16,
+    /// Don't open the preview in the browser
+    #[clap(long)]
+    no_open: bool,
+    /// Mermaid.js bundle rendering the diagram in the browser, e.g. node_modules/mermaid/dist/mermaid.min.js for another version. Optional. Default: Mermaid.js 9.1.5, shipped with web3d
+    #[clap(long, parse(from_os_str), value_name = "FILE")]
+    mermaid_js: Option<PathBuf>,
+}
+
+impl DiagramArgs {
+    /// Method to collect the options of the pipeline scanning the crate
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the scanned crate
+    /// * `syntax` - The syntax of the diagram
+    /// * `config` - The configuration of the crate, overridden by the options given
+    fn pipeline_options(
+        &self,
+        crate_dir: &Path,
+        syntax: Syntax,
+        config: &Config,
+    ) -> Result<PipelineOptions, Error> {
+        let theme = match &self.theme {
+            Some(theme) => Some(theme.clone()),
+            None => config.theme.as_deref().map(|theme| config::resolve(crate_dir, theme)),
+        };
+        let exclude_methods = if self.exclude_methods.is_empty() {
+            &config.exclude_methods
+        } else {
+            &self.exclude_methods
+        };
+        Ok(PipelineOptions {
+            group_by: self.group_by.or(config.group_by),
+            edge_labels: flag(self.edge_labels, self.no_edge_labels).unwrap_or(config.edge_labels),
+            call_counts: flag(self.call_counts, self.no_call_counts).unwrap_or(config.call_counts),
+            theme: theme.as_deref().map(Theme::load).transpose()?,
+            legend: self.legend.or(config.legend),
+            source_links: self.source_links(crate_dir, config),
+            syntax,
+            contract_name: Some(crate_name(crate_dir)),
+            excluded_methods: exclude_methods
+                .iter()
+                .map(|glob| {
+                    glob::Pattern::new(glob).map_err(|e| {
+                        Error::new(ErrorKind::InvalidInput, format!("Invalid method glob {}: {}", glob, e))
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            labels: config.labels.clone(),
+            scope: DiagramScope {
+                focus: self.focus.clone(),
+                depth: self.depth,
+                reverse: self.reverse,
+                hidden: if self.hidden.is_empty() {
+                    config.hide.clone()
+                } else {
+                    self.hidden.clone()
+                },
+                collapse_helpers: flag(self.collapse_helpers, self.no_collapse_helpers).unwrap_or(config.collapse_helpers),
+            },
+            highlights: Default::default(),
+        })
+    }
+    /// Method to return the direction of the diagram
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the crate, overridden by the direction given
+    fn direction(&self, config: &Config) -> FlowDirection {
+        self.direction.or(config.direction).unwrap_or(FlowDirection::TD)
+    }
+    /// Method to resolve the link template & revision into the links of the nodes
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the scanned crate, which the source paths are relative to
+    /// * `config` - The configuration of the crate, overridden by the template given
+    fn source_links(
+        &self,
+        crate_dir: &Path,
+        config: &Config,
+    ) -> Option<SourceLinks> {
+        let link_template = self.link_template.as_deref().or(config.link_template.as_deref())?;
+        let url_template = match link_template {
+            "file" => format!("file://{}/{{path}}", crate_dir.display()),
+            template => template.to_string(),
+        };
+        // Only ask git for the revision when the template needs one
+        let rev = match &self.link_rev {
+            Some(rev) => rev.clone(),
+            None if url_template.contains("{rev}") => {
+                git_revision(crate_dir).unwrap_or_else(|| "HEAD".to_string())
+            }
+            None => String::new(),
+        };
+        Some(SourceLinks { url_template, rev })
+    }
+}
+
+impl BrandingArgs {
+    /// Method to fill in the branding not given from the configuration of the crate
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate, which the images of the configuration are relative to
+    /// * `config` - The rendering options of the configuration
+    fn with_config(
+        &self,
+        crate_dir: &Path,
+        config: &RenderConfig,
+    ) -> BrandingArgs {
+        let resolve = |image: &String| config::resolve(crate_dir, image);
+        BrandingArgs {
+            logo: self.logo.clone().or_else(|| config.logo.as_ref().map(resolve)),
+            watermark: self.watermark.clone().or_else(|| config.watermark.as_ref().map(resolve)),
+            title_block: flag(self.title_block, self.no_title_block).unwrap_or(config.title_block),
+            no_title_block: false,
+            footer: flag(self.footer, self.no_footer).unwrap_or(config.footer),
+            no_footer: false,
+        }
+    }
+    /// Method to tell whether any branding was asked for, beyond the accessibility metadata
+    fn requested(&self) -> bool {
+        self.logo.is_some() || self.watermark.is_some() || self.title_block || self.footer
+    }
+    /// Method to collect the branding & accessibility metadata of the rendered SVG
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the scanned crate
+    /// * `theme` - The theme of the diagram, whose text colour the branding is written in
+    fn branding(
+        &self,
+        crate_dir: &Path,
+        theme: Option<&Theme>,
+    ) -> Result<Branding, Error> {
+        let name = crate_name(crate_dir);
+        let version = crate_version(crate_dir);
+        let load_image = |image: &String| -> Result<String, Error> {
+            match image.as_str() {
+                "default" => Ok(DEFAULT_LOGO.to_string()),
+                path => fs::read_to_string(path),
+            }
+        };
+        let footer = self.footer.then(|| {
+            let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M UTC");
+            match git_revision(crate_dir) {
+                Some(rev) => format!("Generated {} from {}", timestamp, &rev[..rev.len().min(7)]),
+                None => format!("Generated {}", timestamp),
+            }
+        });
+        Ok(Branding {
+            title: match &version {
+                Some(version) => format!("{} {}", name, version),
+                None => name.clone(),
+            },
+            description: format!(
+                "Diagram of the methods of the {} contract and the calls between them, generated by web3d",
+                name
+            ),
+            logo: self.logo.as_ref().map(load_image).transpose()?,
+            watermark: self.watermark.as_ref().map(load_image).transpose()?,
+            title_block: self.title_block.then(|| (name.clone(), version.clone())),
+            footer,
+            color: theme
+                .and_then(|theme| theme.variables.get("primaryTextColor").cloned())
+                .unwrap_or_else(|| "#333333".to_string()),
+        })
+    }
+}
+
+impl SizeArgs {
+    /// Method to fill in the size & background not given from the configuration of the crate
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rendering options of the configuration
+    fn with_config(&self, config: &RenderConfig) -> SizeArgs {
+        SizeArgs {
+            scale: self.scale.clone().or_else(|| config.scale.map(|scale| scale.to_string())),
+            height: self.height.clone().or_else(|| config.height.map(|height| height.to_string())),
+            width: self.width.clone().or_else(|| config.width.map(|width| width.to_string())),
+            background_color: self.background_color.clone().or_else(|| config.background_color.clone()),
+        }
+    }
+    /// Method to parse the size & background of the PNG and PDF output
+    fn export_options(&self) -> Result<svg::ExportOptions, Error> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &Option<String>) -> Result<Option<T>, Error> {
+            value
+                .as_deref()
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Invalid {}: {}", name, value)))
+                })
+                .transpose()
+        }
+        let defaults = svg::ExportOptions::default();
+        Ok(svg::ExportOptions {
+            scale: parse("scale", &self.scale)?.unwrap_or(defaults.scale),
+            width: parse("width", &self.width)?,
+            height: parse("height", &self.height)?,
+            background: self.background_color.clone().or(defaults.background),
+        })
+    }
+}
+
+impl Cli {
+    /// Method to return the directory of the contract crate
+    fn crate_directory(&self) -> Result<PathBuf, Error> {
+        crate_directory(self.manifest_path.as_deref())
+    }
+    /// Method to scan the crate & write the source of its diagram
+    ///
+    /// Returns the written file path, `-` for the standard output
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The options of the scan
+    fn scan(&self, args: &ScanArgs) -> Result<PathBuf, subprocess::PopenError> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let format = args.format.or(config.scan.format).unwrap_or_default();
+        let options = args.diagram.pipeline_options(&crate_dir, format, &config)?;
+        let output = args
+            .output
+            .clone()
+            .or_else(|| config.scan.output.as_ref().map(|output| crate_dir.join(output)))
+            .unwrap_or_else(|| default_output(&crate_dir, format.extension()));
+        let with_contracts: Vec<PathBuf> = if args.with_contracts.is_empty() {
+            config.scan.with_contracts.iter().map(|contract_dir| crate_dir.join(contract_dir)).collect()
+        } else {
+            args.with_contracts.clone()
+        };
+        let markdown = scan_diagram(
+            &crate_dir,
+            &config,
+            args.diagram.direction(&config),
+            &options,
+            &with_contracts,
+            &mut self.scan_cache.borrow_mut(),
+        )?;
+
+        if output == Path::new("-") {
+            let mut stdout = io::stdout().lock();
+            let mut written = writeln!(stdout, "{}", markdown.content);
+            if let Some(legend) = markdown.legend {
+                written = written.and_then(|_| writeln!(stdout, "\n{}", legend));
+            }
+            // A reader closing the pipe early, like `head`, has all it wants
+            match written {
+                Err(error) if error.kind() != ErrorKind::BrokenPipe => return Err(error.into()),
+                _ => return Ok(output),
+            }
+        }
+        create_parent_dir(&output)?;
+        fs::write(&output, file_content(&output, format, markdown.content))?;
+        if let Some(legend) = markdown.legend {
+            let file_stem = output.file_stem().unwrap().to_string_lossy();
+            fs::write(output.with_file_name(format!("{}-legend.md", file_stem)), legend)?;
+        }
+        Ok(output)
+    }
+    /// Method to write the diagram of every public entrypoint of the crate, along with an index.md & index.html linking them
+    ///
+    /// Returns the written index.md path
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The options of the diagrams
+    fn batch(&self, args: &BatchArgs) -> Result<PathBuf, subprocess::PopenError> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let format = args.format.or(config.batch.format).unwrap_or_default();
+        let mut options = args.diagram.pipeline_options(&crate_dir, format, &config)?;
+        // Every diagram is focused on its entrypoint already
+        options.scope.focus = None;
+        options.scope.reverse = false;
+        let direction = args.diagram.direction(&config);
+        let output_dir = args
+            .output
+            .clone()
+            .or_else(|| config.batch.output.as_ref().map(|output| crate_dir.join(output)))
+            .unwrap_or_else(|| crate_dir.join("res").join(format!("{}-entrypoints", crate_name(&crate_dir))));
+        fs::create_dir_all(&output_dir)?;
+        let contract_info = scan_contract(&crate_dir, &config, &mut self.scan_cache.borrow_mut())?;
+
+        let mut index = EntrypointIndex {
+            title: crate_name(&crate_dir),
+            entries: Vec::new(),
+        };
+        for entrypoint in entrypoints::entrypoints(&contract_info) {
+            if options.excluded_methods.iter().any(|pattern| pattern.matches(&entrypoint.name)) {
+                continue;
+            }
+            let reachable = entrypoints::reachable_from(&contract_info, &entrypoint.name).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Could not find the method {} in the contract", entrypoint.name),
+                )
+            })?;
+            let markdown = ScannerPipeline::from_with_options(reachable, direction, &options);
+            let file_name = format!("{}.{}", entrypoint.name, format.extension());
+            let output = output_dir.join(&file_name);
+            fs::write(&output, file_content(&output, format, markdown.content))?;
+            if let Some(legend) = markdown.legend {
+                fs::write(output_dir.join(format!("{}-legend.md", entrypoint.name)), legend)?;
+            }
+            index.entries.push(IndexEntry {
+                entrypoint,
+                path: file_name,
+            });
+        }
+
+        let index_path = output_dir.join("index.md");
+        fs::write(&index_path, index.to_markdown())?;
+        fs::write(output_dir.join("index.html"), index.to_html())?;
+        if !self.quiet {
+            println!("Wrote the diagrams of {} entrypoints into {}", index.entries.len(), output_dir.display());
+        }
+        Ok(index_path)
+    }
+    /// Method to render the diagram of the crate, with mermaid-cli or the native renderer
+    ///
+    /// Returns created file path
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The options of the rendering
+    fn render(&self, args: &RenderArgs) -> Result<PathBuf, subprocess::PopenError> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let renderer = args.renderer.or(config.render.renderer).unwrap_or_default();
+        let size = args.size.with_config(&config.render);
+        // The native renderer draws the flowchart itself rather than handing it to mermaid-cli
+        let syntax = match renderer {
+            Renderer::Mmdc => Syntax::Mermaid,
+            Renderer::Native => Syntax::Svg,
+        };
+        let options = args.diagram.pipeline_options(&crate_dir, syntax, &config)?;
+        let output_path = args
+            .output
+            .clone()
+            .or_else(|| config.render.output.as_ref().map(|output| crate_dir.join(output)))
+            .unwrap_or_else(|| default_output(&crate_dir, "svg"));
+        create_parent_dir(&output_path)?;
+        // PNG and PDF files are exported from an SVG rendered next to the other temporary files
+        let exported = svg::is_exported(&output_path);
+        let svg_path = if exported {
+            temporary_file("svg")
+        } else {
+            output_path.clone()
+        };
+        let markdown = scan_diagram(
+            &crate_dir,
+            &config,
+            args.diagram.direction(&config),
+            &options,
+            &[],
+            &mut self.scan_cache.borrow_mut(),
+        )?;
+        if let Some(legend) = &markdown.legend {
+            let file_stem = output_path.file_stem().unwrap().to_string_lossy();
+            fs::write(output_path.with_file_name(format!("{}-legend.md", file_stem)), legend)?;
+        }
+
+        match renderer {
+            Renderer::Native => fs::write(&svg_path, markdown.content)?,
+            Renderer::Mmdc => {
+                let input_path = temporary_file("mmd");
+                fs::write(&input_path, markdown.content)?;
+                let rendered = self.pass_to_mmdc(&input_path, &svg_path, &size);
+                fs::remove_file(&input_path)?;
+                rendered?;
+            }
+        }
+        let branding = args.branding.with_config(&crate_dir, &config.render);
+        self.post_process(&crate_dir, &svg_path, &branding, options.theme.as_ref())?;
+        if exported {
+            if !self.quiet {
+                println!("Exporting {}", output_path.display());
+            }
+            let result = svg::export(&svg_path, &output_path, &size.export_options()?);
+            fs::remove_file(&svg_path)?;
+            result?;
+        }
+        Ok(output_path)
+    }
+    /// Method to pass arguments to the mermaid-cli, rendering a Mermaid.js file into an SVG
+    ///
+    /// # Arguments
+    ///
+    /// * `input_path` - The Mermaid.js file
+    /// * `svg_path` - The SVG file to render
+    /// * `size` - The size & background of the page
+    fn pass_to_mmdc(
+        &self,
+        input_path: &Path,
+        svg_path: &Path,
+        size: &SizeArgs,
+    ) -> Result<(), subprocess::PopenError> {
+        let mut command = vec!["mmdc", "-i", input_path.to_str().unwrap()];
+        // Set the output file
+        command.push("-o");
+        command.push(svg_path.to_str().unwrap());
+
+        // Check if the scale is provided
+        if let Some(scale) = &size.scale {
+            if !self.quiet {
+                println!("Set the scale: {}", scale);
+            };
+            command.push("-s");
+            command.push(scale);
+        };
+        // Check if the height is provided
+        if let Some(height) = &size.height {
+            if !self.quiet {
+                println!("Set the height: {}", height);
+            };
+            command.push("-h");
+            command.push(height);
+        };
+        // Check if the width is provided
+        if let Some(width) = &size.width {
+            if !self.quiet {
+                println!("Set the width: {}", width);
+            };
+            command.push("-w");
+            command.push(width);
+        };
+        // Check if the background color is provided
+        if let Some(background_color) = &size.background_color {
+            if !self.quiet {
+                println!("Set the background color: {}", background_color);
+            };
+            command.push("-b");
+            command.push(background_color);
+        };
+        if self.quiet {
+            command.push("-q");
+        }
+
+        // Call mermaid-cli with the constracted command, create svg file
+        let mut mmdc = Popen::create(
+            &command,
+            PopenConfig {
+                stdout: Redirection::Pipe,
+                ..PopenConfig::default()
+            },
+        )?;
+        if !mmdc.wait()?.success() {
+            return Err(Error::other("mermaid-cli couldn't render the diagram").into());
+        }
+        Ok(())
+    }
+    /// Method to export a rendered SVG into a PNG or PDF image
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The options of the export
+    fn export(&self, args: &ExportArgs) -> Result<PathBuf, Error> {
+        if !svg::is_exported(&args.output) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Can only export to PNG or PDF, not {}", args.output.display()),
+            ));
+        }
+        create_parent_dir(&args.output)?;
+        svg::export(&args.input, &args.output, &args.size.export_options()?)?;
+        Ok(args.output.clone())
+    }
+    /// Method to check the contract against the lint rules, printing the problems found
+    ///
+    /// Returns the number of problems found
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The options of the check
+    fn lint(&self, args: &LintArgs) -> Result<usize, Error> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let contract_info = scan_contract(&crate_dir, &config, &mut self.scan_cache.borrow_mut())?;
+        let rules: Vec<Rule> = Rule::iter().filter(|rule| !args.allowed.contains(rule)).collect();
+        let findings = lint::lint(&contract_info, &rules);
+        for finding in &findings {
+            println!("{}", finding);
+        }
+        if !self.quiet {
+            println!("{} problem(s) found in {}", findings.len(), crate_name(&crate_dir));
+        }
+        Ok(findings.len())
+    }
+    /// Method to replace the diagrams of the web3d blocks of Markdown files, or to check they're up to date
+    ///
+    /// Returns the files whose blocks were stale, left as they are when checking
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The files & the options of their diagrams, which the options of each block override
+    fn update(&self, args: &UpdateArgs) -> Result<Vec<PathBuf>, Error> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let files: Vec<PathBuf> = if !args.files.is_empty() {
+            args.files.clone()
+        } else if !config.update.files.is_empty() {
+            config.update.files.iter().map(|file| crate_dir.join(file)).collect()
+        } else {
+            vec![crate_dir.join("README.md")]
+        };
+
+        let generate = |block: &BlockOptions| -> Result<String, Error> {
+            let format = block.format.unwrap_or(Syntax::Mermaid);
+            let mut options = args.diagram.pipeline_options(&crate_dir, format, &config)?;
+            if block.focus.is_some() {
+                options.scope.focus = block.focus.clone();
+                options.scope.reverse = block.reverse;
+            }
+            options.scope.depth = block.depth.or(options.scope.depth);
+            if !block.hidden.is_empty() {
+                options.scope.hidden = block.hidden.clone();
+            }
+            options.scope.collapse_helpers |= block.collapse_helpers;
+            if !block.exclude_methods.is_empty() {
+                options.excluded_methods = block
+                    .exclude_methods
+                    .iter()
+                    .map(|glob| {
+                        glob::Pattern::new(glob).map_err(|e| {
+                            Error::new(ErrorKind::InvalidInput, format!("Invalid method glob {}: {}", glob, e))
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            let direction = block.direction.unwrap_or_else(|| args.diagram.direction(&config));
+            let markdown = scan_diagram(&crate_dir, &config, direction, &options, &[], &mut self.scan_cache.borrow_mut())?;
+            let mut content = block_content(format, markdown.content);
+            if let Some(legend) = markdown.legend {
+                content.push_str(&format!("\n{}", legend));
+            }
+            Ok(content)
+        };
+
+        let mut stale: Vec<PathBuf> = Vec::new();
+        for file in files {
+            let text = fs::read_to_string(&file)
+                .map_err(|e| Error::new(e.kind(), format!("Could not read {}: {}", file.display(), e)))?;
+            let updated = markers::update(&text, &generate)
+                .map_err(|e| Error::new(e.kind(), format!("{}: {}", file.display(), e)))?;
+            if updated == text {
+                continue;
+            }
+            if !args.check {
+                fs::write(&file, updated)?;
+            }
+            stale.push(file);
+        }
+        Ok(stale)
+    }
+    /// Method to write the changes to the public methods between two versions of the crate, as a Markdown report along
+    /// with a diagram of them
+    ///
+    /// Returns the written file path, `-` for the standard output
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The versions compared & the options of the diagram
+    fn diff(&self, args: &DiffArgs) -> Result<PathBuf, Error> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let scan_version = |source: Option<&str>| -> Result<ContractInfo, Error> {
+            let version = match source {
+                Some(source) => Version::resolve(&crate_dir, source)?,
+                None => Version::Directory(crate_dir.clone()),
+            };
+            // Each version is scanned with its own configuration
+            let contract_info = scan_contract(version.dir(), &Config::load(version.dir())?, &mut self.scan_cache.borrow_mut());
+            version.remove()?;
+            contract_info
+        };
+        let old = scan_version(Some(&args.old))?;
+        let new = scan_version(args.new.as_deref())?;
+
+        let contract_diff = ContractDiff::between(&old, &new);
+        let diagram = if contract_diff.is_empty() {
+            None
+        } else {
+            let mut options = args.diagram.pipeline_options(&crate_dir, Syntax::Mermaid, &config)?;
+            options.highlights = contract_diff.highlights();
+            let merged = contract_diff.merged_contract(&old, &new);
+            Some(ScannerPipeline::from_with_options(merged, args.diagram.direction(&config), &options).content)
+        };
+        let report = contract_diff.to_markdown(&crate_name(&crate_dir), diagram.as_deref());
+
+        let output = args.output.clone().unwrap_or_else(|| crate_dir.join("res").join(format!("{}-diff.md", crate_name(&crate_dir))));
+        if output == Path::new("-") {
+            print!("{}", report);
+        } else {
+            create_parent_dir(&output)?;
+            fs::write(&output, report)?;
+        }
+        Ok(output)
+    }
+    /// Method to write a configuration with every option at its default into the crate
+    ///
+    /// Returns the written file path
+    fn init(&self) -> Result<PathBuf, Error> {
+        let config_file = self.crate_directory()?.join(CONFIG_FILE);
+        if config_file.exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", config_file.display()),
+            ));
+        }
+        fs::write(&config_file, config::TEMPLATE)?;
+        Ok(config_file)
+    }
+    /// Method to add the branding & accessibility metadata to a rendered SVG, rewriting the file
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the scanned crate
+    /// * `svg_path` - The path of the SVG file
+    /// * `branding_args` - The branding asked for
+    /// * `theme` - The theme of the diagram, whose text colour the branding is written in
+    fn post_process(
+        &self,
+        crate_dir: &Path,
+        svg_path: &Path,
+        branding_args: &BrandingArgs,
+        theme: Option<&Theme>,
+    ) -> Result<(), Error> {
+        let branding = branding_args.branding(crate_dir, theme)?;
+
+        let svg = fs::read_to_string(svg_path)?;
+        match branding::brand(&svg, &branding) {
+            Ok(branded) => fs::write(svg_path, branded),
+            // Only the accessibility metadata is missed when no branding was asked for
+            Err(error) if !branding_args.requested() => {
+                if !self.quiet {
+                    println!("Could not add the accessibility metadata to {}: {}", svg_path.display(), error);
+                }
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+    /// Method to serve a live preview of the diagram until the process is stopped
+    ///
+    /// The page renders a Mermaid.js flowchart with the Mermaid.js bundle shipped with web3d, or the one given, unless the
+    /// native renderer is chosen, which shows its SVG.
+    ///
+    /// # Arguments
+    ///
+    /// * `serve_args` - The options of the preview server
+    fn serve(&self, serve_args: &ServeArgs) -> Result<(), Error> {
+        let crate_dir = self.crate_directory()?;
+        let config = Config::load(&crate_dir)?;
+        let syntax = match serve_args.renderer.or(config.render.renderer).unwrap_or_default() {
+            Renderer::Mmdc => Syntax::Mermaid,
+            Renderer::Native => Syntax::Svg,
+        };
+        let mermaid_js = serve_args.mermaid_js.as_ref().map(fs::read_to_string).transpose()?;
+        let options = serve_args.diagram.pipeline_options(&crate_dir, syntax, &config)?;
+        let direction = serve_args.diagram.direction(&config);
+        let branding = serve_args
+            .branding
+            .with_config(&crate_dir, &config.render)
+            .branding(&crate_dir, options.theme.as_ref())?;
+        let title = crate_name(&crate_dir);
+
+        // A crate failing to scan keeps the last diagram on show, under the error
+        let render = |previous: Option<Page>| -> Page {
+            let scanned = panic::catch_unwind(AssertUnwindSafe(|| {
+                scan_diagram(&crate_dir, &config, direction, &options, &[], &mut self.scan_cache.borrow_mut())
+            }));
+            match scanned {
+                Ok(Ok(markdown)) => Page {
+                    title: title.clone(),
+                    diagram: match syntax {
+                        Syntax::Mermaid => Diagram::Mermaid(markdown.content),
+                        _ => Diagram::Svg(branding::brand(&markdown.content, &branding).unwrap_or(markdown.content)),
+                    },
+                    error: None,
+                },
+                Ok(Err(error)) => Page {
+                    error: Some(error.to_string()),
+                    ..previous.unwrap_or(Page {
+                        title: title.clone(),
+                        diagram: Diagram::Svg(String::new()),
+                        error: None,
+                    })
+                },
+                Err(_) => Page {
+                    error: Some("The crate could not be scanned, see the terminal for the details".to_string()),
+                    ..previous.unwrap_or(Page {
+                        title: title.clone(),
+                        diagram: Diagram::Svg(String::new()),
+                        error: None,
+                    })
+                },
+            }
+        };
+
+        let listener = TcpListener::bind(("127.0.0.1", serve_args.port))?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let preview = Arc::new(Preview::new(render(None), mermaid_js));
+        let server_preview = preview.clone();
+        thread::spawn(move || serve::serve(listener, server_preview));
+        println!("Serving the diagram of {} at {}", title, url);
+        if !serve_args.no_open {
+            open_in_browser(&url);
+        }
+
+        // Re-scan the sources once they changed
+        let mut watcher = self.watcher(&crate_dir);
+        loop {
+            let changes = watcher.wait();
+            self.log_changes(&crate_dir, &changes);
+            preview.publish(render(Some(preview.page())));
+        }
+    }
+    /// Method returns the generators of the outputs of the watch mode: the output of the running subcommand, then the
+    /// ones of the other subcommands whose output is set in the configuration, generated with its options
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The running subcommand
+    fn watched_outputs<'a>(
+        &'a self,
+        command: &'a Command,
+    ) -> Result<Vec<Generate<'a>>, Error> {
+        let config = Config::load(&self.crate_directory()?)?;
+        let mut outputs: Vec<Generate<'a>> = Vec::new();
+        match command {
+            Command::Scan(args) => outputs.push(Box::new(move || self.scan(args))),
+            Command::Batch(args) => outputs.push(Box::new(move || self.batch(args))),
+            Command::Render(args) => outputs.push(Box::new(move || self.render(args))),
+            _ => {}
+        }
+        if config.scan.output.is_some() && !matches!(command, Command::Scan(_)) {
+            outputs.push(Box::new(move || self.scan(&ScanArgs::default())));
+        }
+        if config.batch.output.is_some() && !matches!(command, Command::Batch(_)) {
+            outputs.push(Box::new(move || self.batch(&BatchArgs::default())));
+        }
+        if config.render.output.is_some() && !matches!(command, Command::Render(_)) {
+            outputs.push(Box::new(move || self.render(&RenderArgs::default())));
+        }
+        Ok(outputs)
+    }
+    /// Method starts watching the sources of the crate, telling when filesystem notifications aren't available
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate
+    fn watcher(
+        &self,
+        crate_dir: &Path,
+    ) -> Watcher {
+        let watcher = Watcher::new(crate_dir, DEBOUNCE);
+        if watcher.is_polling() && !self.quiet {
+            println!("Filesystem notifications aren't available, polling the files of {} instead", crate_dir.display());
+        }
+        watcher
+    }
+    /// Method regenerates the outputs of the watch mode whenever the sources of the crate change, until the process is
+    /// stopped
+    ///
+    /// Only the changed files are parsed again, the others are re-used from the previous scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The running subcommand, whose output is generated first
+    /// * `open` - Whether to open the first output in the browser
+    fn watch(
+        &self,
+        command: &Command,
+        open: bool,
+    ) -> Result<(), subprocess::PopenError> {
+        let crate_dir = self.crate_directory()?;
+        let mut watcher = self.watcher(&crate_dir);
+        let mut opened = false;
+        loop {
+            // The configuration is read again, as it's watched too
+            match self.watched_outputs(command) {
+                Ok(outputs) => {
+                    for generate in outputs {
+                        // A crate failing to scan halfway through an edit is reported & scanned again on the next change
+                        match panic::catch_unwind(AssertUnwindSafe(generate)) {
+                            Ok(Ok(output_path)) => {
+                                if !self.quiet {
+                                    let cache = self.scan_cache.borrow();
+                                    println!(
+                                        "Generated {} (parsed {} files, re-used {})",
+                                        output_path.display(),
+                                        cache.scanned,
+                                        cache.reused
+                                    );
+                                }
+                                if open && !opened {
+                                    open_in_browser(output_path.to_str().unwrap());
+                                    opened = true;
+                                }
+                            }
+                            Ok(Err(error)) => println!("Could not generate the diagram: {}", error),
+                            Err(_) => {
+                                println!("Could not scan the crate, waiting for the next change");
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(error) => println!("Could not read the configuration: {}", error),
+            }
+            if !self.quiet {
+                println!("Watching {} for changes", crate_dir.display());
+            }
+            let changes = watcher.wait();
+            self.log_changes(&crate_dir, &changes);
+        }
+    }
+    /// Method lists the changed files, relative to the crate
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate
+    /// * `changes` - The changed files
+    fn log_changes(
+        &self,
+        crate_dir: &Path,
+        changes: &[PathBuf],
+    ) {
+        if self.quiet {
+            return;
+        }
+        for change in changes {
+            println!("Changed: {}", change.strip_prefix(crate_dir).unwrap_or(change).display());
+        }
+    }
+}
+
+/// Function parses the command line, skipping the name of the subcommand when run by Cargo
+///
+/// `cargo diagram ARGS` runs `cargo-diagram diagram ARGS`.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, starting with the name of the binary
+fn parse_from(args: impl IntoIterator<Item = OsString>) -> Result<Cli, clap::Error> {
+    let mut args: Vec<OsString> = args.into_iter().collect();
+    let mut command = Cli::command();
+    if args.get(1).map(|arg| arg == "diagram").unwrap_or(false) {
+        args.remove(1);
+        command = command.bin_name("cargo diagram");
+    }
+    Cli::from_arg_matches(&command.try_get_matches_from(args)?)
+}
+
+/// Function runs the command line interface with the arguments of the process
+pub fn run() -> Result<(), subprocess::PopenError> {
+    let cli = parse_from(env::args_os()).unwrap_or_else(|error| error.exit());
+
+    match &cli.command {
+        Command::Scan(args) if args.watch => cli.watch(&cli.command, false),
+        Command::Scan(args) => {
+            let output_path = cli.scan(args)?;
+            if !cli.quiet && output_path != Path::new("-") {
+                println!("Wrote {}", output_path.display());
+            }
+            Ok(())
+        }
+        Command::Batch(args) if args.watch => cli.watch(&cli.command, false),
+        Command::Batch(args) => {
+            cli.batch(args)?;
+            Ok(())
+        }
+        Command::Render(args) if args.watch => cli.watch(&cli.command, args.open),
+        Command::Render(args) => {
+            let output_path = cli.render(args)?;
+            if args.open {
+                open_in_browser(output_path.to_str().unwrap());
+            }
+            Ok(())
+        }
+        Command::Export(args) => {
+            cli.export(args)?;
+            Ok(())
+        }
+        Command::Lint(args) => {
+            // Problems fail the run, like a failing test
+            if cli.lint(args)? > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Serve(args) => {
+            cli.serve(args)?;
+            Ok(())
+        }
+        Command::Update(args) => {
+            let stale = cli.update(args)?;
+            for file in &stale {
+                if args.check {
+                    println!("{} is out of date, run web3d update", file.display());
+                } else if !cli.quiet {
+                    println!("Updated {}", file.display());
+                }
+            }
+            // Stale diagrams fail the check, so CI catches the docs left behind the contract
+            if args.check && !stale.is_empty() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Diff(args) => {
+            let output_path = cli.diff(args)?;
+            if !cli.quiet && output_path != Path::new("-") {
+                println!("Wrote {}", output_path.display());
+            }
+            Ok(())
+        }
+        Command::Init => {
+            let config_file = cli.init()?;
+            if !cli.quiet {
+                println!("Wrote {}", config_file.display());
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Function opens a file or URL in the web browser
+///
+/// The browser is taken from `$BROWSER`, a colon separated list of commands where `%s` stands for the target, falling back
+/// to the opener of the platform: `open` on macOS, `start` on Windows and `xdg-open` elsewhere.
+///
+/// # Arguments
+///
+/// * `target` - The path or URL to open
+fn open_in_browser(target: &str) {
+    let browsers: Vec<Vec<String>> = match env::var("BROWSER") {
+        Ok(browsers) if !browsers.trim().is_empty() => browsers
+            .split(':')
+            .map(|browser| {
+                let mut command: Vec<String> = browser.split_whitespace().map(|part| part.replace("%s", target)).collect();
+                if !browser.contains("%s") {
+                    command.push(target.to_string());
+                }
+                command
+            })
+            .collect(),
+        _ if cfg!(target_os = "macos") => vec![vec!["open".to_string(), target.to_string()]],
+        _ if cfg!(target_os = "windows") => vec![vec!["cmd".to_string(), "/C".to_string(), "start".to_string(), String::new(), target.to_string()]],
+        _ => vec![vec!["xdg-open".to_string(), target.to_string()]],
+    };
+
+    // The first browser starting is left running on its own
+    let opened = browsers.iter().filter(|command| !command.is_empty()).any(|command| {
+        Popen::create(
+            command,
+            PopenConfig {
+                stdout: Redirection::Pipe,
+                stderr: Redirection::Pipe,
+                detached: true,
+                ..PopenConfig::default()
+            },
+        )
+        .map(|mut browser| browser.detach())
+        .is_ok()
+    });
+    if !opened {
+        println!("Could not open a browser, open {} manually", target);
+    }
+}
+
+/// Function returns the default output path of a crate, `./res/CRATE.EXTENSION` in the crate
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+/// * `extension` - The extension of the output
+fn default_output(
+    crate_dir: &Path,
+    extension: &str,
+) -> PathBuf {
+    crate_dir
+        .join("res")
+        .join(format!("{}.{}", crate_name(crate_dir), extension))
+}
+
+/// Function returns the path of a temporary file of the process, in the temporary directory of the system
+///
+/// # Arguments
+///
+/// * `extension` - The extension of the file
+fn temporary_file(extension: &str) -> PathBuf {
+    env::temp_dir().join(format!("web3d-{}.{}", std::process::id(), extension))
+}
+
+/// Function creates the directory an output file is written into
+///
+/// # Arguments
+///
+/// * `output` - The output file
+fn create_parent_dir(output: &Path) -> Result<(), Error> {
+    match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Function scans the source files of a contract crate included by its configuration
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+/// * `config` - The configuration of the crate
+/// * `cache` - The files scanned earlier, re-used while they're unchanged
+fn scan_contract(
+    crate_dir: &Path,
+    config: &Config,
+    cache: &mut ScanCache,
+) -> Result<ContractInfo, Error> {
+    let source_filter = SourceFilter::new(&config.include, &config.exclude)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid source glob: {}", e)))?;
+    Ok(DefaultContractDescriptor::with_source_filter(source_filter).get_contract_info_for_crate_cached(crate_dir.to_str(), cache))
+}
+
+/// Function scans a contract crate into a diagram
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+/// * `config` - The configuration of the crate
+/// * `direction` - The direction of the diagram
+/// * `options` - Options controlling the layout of the diagram
+/// * `with_contracts` - The directories of the other contract crates modelled in a Structurizr workspace
+/// * `cache` - The files scanned earlier, re-used while they're unchanged
+fn scan_diagram(
+    crate_dir: &Path,
+    config: &Config,
+    direction: FlowDirection,
+    options: &PipelineOptions,
+    with_contracts: &[PathBuf],
+    cache: &mut ScanCache,
+) -> Result<ScannerPipeline, Error> {
+    let contract_info = scan_contract(crate_dir, config, cache)?;
+    if let Some(focus) = &options.scope.focus {
+        if !contract_info.contract_metadata.iter().flat_map(|m| &m.fns).any(|function| &function.name == focus) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("There is no method named {} in {}", focus, crate_dir.display()),
+            ));
+        }
+    }
+    if options.syntax == Syntax::Structurizr && !with_contracts.is_empty() {
+        // Every contract is a container of the same system, named after the crate scanned
+        let name = options.contract_name.clone().unwrap_or_default();
+        let mut workspace = StructurizrWorkspace::new(&name, direction, options.theme.clone());
+        workspace.add_contract(&name, &contract_info);
+        for contract_dir in with_contracts {
+            // The other crates are scanned with their own configuration
+            let contract_info = scan_contract(contract_dir, &Config::load(contract_dir)?, cache)?;
+            workspace.add_contract(&crate_name(contract_dir), &contract_info);
+        }
+        Ok(ScannerPipeline {
+            content: workspace.to_dsl(),
+            legend: None,
+        })
+    } else {
+        Ok(ScannerPipeline::from_with_options(contract_info, direction, options))
+    }
+}
+
+/// Function returns the content of a diagram file, a Mermaid.js flowchart written into a Markdown file being wrapped into a
+/// fenced block, the only way it's shown as a diagram
+///
+/// # Arguments
+///
+/// * `output` - The file written
+/// * `format` - The syntax of the diagram
+/// * `content` - The diagram
+fn file_content(
+    output: &Path,
+    format: Syntax,
+    content: String,
+) -> String {
+    if format == Syntax::Mermaid && output.extension().is_some_and(|extension| extension == "md") {
+        format!("```mermaid\n{}\n```\n", content.trim_end())
+    } else {
+        content
+    }
+}
+
+/// A version of the crate compared by `web3d diff`.
+enum Version {
+    /// A directory holding the crate
+    Directory(PathBuf),
+    /// A git revision of the crate, extracted into a temporary directory: the crate in it & the temporary directory, removed
+    /// once the version is dropped
+    Revision(PathBuf, TempDir),
+}
+
+impl Version {
+    /// Method to find a version of the crate: a directory or manifest if one exists at the path, or else a revision of the
+    /// git repository of the crate, which is extracted
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate
+    /// * `source` - The path or revision
+    fn resolve(
+        crate_dir: &Path,
+        source: &str,
+    ) -> Result<Version, Error> {
+        if Path::new(source).exists() {
+            return Ok(Version::Directory(crate_directory(Some(Path::new(source)))?));
+        }
+        let git = |args: &[&str]| -> Result<String, Error> {
+            let capture = Exec::cmd("git")
+                .args(args)
+                .cwd(crate_dir)
+                .stdout(Redirection::Pipe)
+                .stderr(Redirection::Pipe)
+                .capture()
+                .map_err(|e| Error::other(format!("Could not run git: {}", e)))?;
+            if !capture.success() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("{} is neither a directory nor a git revision: {}", source, capture.stderr_str().trim()),
+                ));
+            }
+            Ok(capture.stdout_str().trim().to_string())
+        };
+        let commit = git(&["rev-parse", "--verify", &format!("{}^{{commit}}", source)])?;
+        let root = git(&["rev-parse", "--show-toplevel"])?;
+        // The path of the crate inside of the repository, e.g. `contracts/vault/`
+        let prefix = git(&["rev-parse", "--show-prefix"])?;
+
+        let temporary_dir = tempfile::Builder::new()
+            .prefix(&format!("web3d-{}-", &commit[..12.min(commit.len())]))
+            .tempdir()?;
+        let mut archive = vec!["archive", "--format=tar", &commit];
+        if !prefix.is_empty() {
+            archive.extend(["--", &prefix]);
+        }
+        let extracted = (Exec::cmd("git").args(&archive).cwd(&root)
+            | Exec::cmd("tar").arg("-x").arg("-C").arg(temporary_dir.path()))
+        .capture()
+        .map_err(|e| Error::other(format!("Could not extract {}: {}", source, e)))?;
+        if !extracted.success() {
+            return Err(Error::other(format!("Could not extract {}", source)));
+        }
+        Ok(Version::Revision(temporary_dir.path().join(prefix), temporary_dir))
+    }
+    /// Method to return the directory of the crate
+    fn dir(&self) -> &Path {
+        match self {
+            Version::Directory(dir) | Version::Revision(dir, _) => dir,
+        }
+    }
+    /// Method to remove the directory a revision was extracted into, failing if it can't be. Dropping the version removes it
+    /// too, ignoring failures
+    fn remove(self) -> Result<(), Error> {
+        match self {
+            Version::Directory(_) => Ok(()),
+            Version::Revision(_, temporary_dir) => temporary_dir.close(),
+        }
+    }
+}
+
+/// Function returns the content of a web3d block of a Markdown file: the diagram in a fenced block, a Markdown document as is
+///
+/// # Arguments
+///
+/// * `format` - The syntax of the diagram
+/// * `content` - The diagram
+fn block_content(
+    format: Syntax,
+    content: String,
+) -> String {
+    let language = match format {
+        Syntax::Markdown => return content,
+        Syntax::Mermaid => "mermaid",
+        Syntax::PlantUml | Syntax::PlantUmlSequence => "plantuml",
+        _ => format.extension(),
+    };
+    format!("```{}\n{}\n```\n", language, content.trim_end())
+}
+
+/// Function finds the directory of the contract crate: the one of the manifest given, or else the closest one above the
+/// current directory with a `[package]`, like Cargo does
+///
+/// # Arguments
+///
+/// * `manifest_path` - The `Cargo.toml` of the crate, or its directory, if given
+fn crate_directory(manifest_path: Option<&Path>) -> Result<PathBuf, Error> {
+    let crate_dir = match manifest_path {
+        Some(manifest_path) => {
+            let manifest_path = if manifest_path.is_dir() {
+                manifest_path.join("Cargo.toml")
+            } else {
+                manifest_path.to_path_buf()
+            };
+            if !manifest_path.is_file() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("The manifest {} doesn't exist", manifest_path.display()),
+                ));
+            }
+            manifest_path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf()
+        }
+        None => {
+            let current_dir = env::current_dir()?;
+            current_dir
+                .ancestors()
+                .find(|dir| package_field(dir, "name").is_some())
+                .map(Path::to_path_buf)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "Could not find the Cargo.toml of a package in {} or any parent directory, pass --manifest-path",
+                            current_dir.display()
+                        ),
+                    )
+                })?
+        }
+    };
+    crate_dir.canonicalize()
+}
+
+/// Function returns the value of a flag given along with its `--no-` counterpart, `None` if neither was given so that the
+/// configuration decides
+///
+/// # Arguments
+///
+/// * `set` - Whether the flag was given
+/// * `unset` - Whether its `--no-` counterpart was given
+fn flag(set: bool, unset: bool) -> Option<bool> {
+    match (set, unset) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Function returns the name of the package in the manifest of a crate, or the name of its directory if it has none
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+fn crate_name(crate_dir: &Path) -> String {
+    package_field(crate_dir, "name")
+        .unwrap_or_else(|| {
+            crate_dir
+                .canonicalize()
+                .ok()
+                .and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "Contract".to_string())
+        })
+}
+
+/// Function returns the version of the package in the manifest of a crate, if any
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+fn crate_version(crate_dir: &Path) -> Option<String> {
+    package_field(crate_dir, "version")
+}
+
+/// Function returns a string field of the `[package]` table in the manifest of a crate, if any
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+/// * `field` - The name of the field
+fn package_field(
+    crate_dir: &Path,
+    field: &str,
+) -> Option<String> {
+    fs::read_to_string(crate_dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|manifest| manifest.parse::<toml::Value>().ok())
+        .and_then(|manifest| {
+            manifest
+                .get("package")?
+                .get(field)?
+                .as_str()
+                .map(str::to_string)
+        })
+}
+
+/// Function returns the commit checked out in the repository containing `dir`, if any
+///
+/// # Arguments
+///
+/// * `dir` - A directory inside of the repository
+fn git_revision(dir: &Path) -> Option<String> {
+    let capture = Exec::cmd("git")
+        .args(&["rev-parse", "HEAD"])
+        .cwd(dir)
+        .stdout(Redirection::Pipe)
+        .stderr(Redirection::Pipe)
+        .capture()
+        .ok()?;
+    if !capture.success() {
+        return None;
+    }
+    Some(capture.stdout_str().trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_crate_of_a_manifest_and_skips_the_cargo_subcommand_name() {
+        Cli::command().debug_assert();
+        let crate_dir = crate_directory(Some(&Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))).unwrap();
+        assert_eq!(crate_name(&crate_dir), "web3d");
+        assert!(crate_directory(Some(Path::new("does-not-exist/Cargo.toml"))).is_err());
+
+        let args = ["cargo-diagram", "diagram", "scan", "-f", "dot", "-o", "-", "--manifest-path", "Cargo.toml"];
+        let cli = parse_from(args.iter().map(OsString::from)).unwrap();
+        match cli.command {
+            Command::Scan(args) => {
+                assert_eq!(args.format, Some(Syntax::Dot));
+                assert_eq!(args.output, Some(PathBuf::from("-")));
+            }
+            command => panic!("Expected the scan subcommand, got {:?}", command),
+        }
+        assert_eq!(cli.manifest_path, Some(PathBuf::from("Cargo.toml")));
+    }
+
+    #[test]
+    fn it_parses_every_subcommand() {
+        let parse = |args: &[&str]| parse_from(args.iter().map(OsString::from)).unwrap().command;
+
+        match parse(&["web3d", "render", "-R", "native", "-o", "a.png", "--title-block", "-w", "1200"]) {
+            Command::Render(args) => {
+                assert_eq!(args.renderer, Some(Renderer::Native));
+                assert_eq!(args.output, Some(PathBuf::from("a.png")));
+                assert!(args.branding.title_block);
+                assert_eq!(args.size.width.as_deref(), Some("1200"));
+            }
+            command => panic!("Expected the render subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "export", "a.svg", "-o", "a.pdf", "-s", "2"]) {
+            Command::Export(args) => {
+                assert_eq!(args.input, PathBuf::from("a.svg"));
+                assert_eq!(args.output, PathBuf::from("a.pdf"));
+                assert_eq!(args.size.scale.as_deref(), Some("2"));
+            }
+            command => panic!("Expected the export subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "lint", "--allow", "unused-helper", "--allow", "payable-view"]) {
+            Command::Lint(args) => assert_eq!(args.allowed, vec![Rule::UnusedHelper, Rule::PayableView]),
+            command => panic!("Expected the lint subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "serve", "-p", "8080", "--no-open"]) {
+            Command::Serve(args) => {
+                assert_eq!(args.port, 8080);
+                assert!(args.no_open);
+                assert_eq!(args.mermaid_js, None);
+            }
+            command => panic!("Expected the serve subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "batch", "-f", "svg", "-o", "docs"]) {
+            Command::Batch(args) => {
+                assert_eq!(args.format, Some(Syntax::Svg));
+                assert_eq!(args.output, Some(PathBuf::from("docs")));
+            }
+            command => panic!("Expected the batch subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "diff", "v1.0.0", "-o", "-"]) {
+            Command::Diff(args) => {
+                assert_eq!(args.old, "v1.0.0");
+                assert_eq!(args.new, None);
+                assert_eq!(args.output, Some(PathBuf::from("-")));
+            }
+            command => panic!("Expected the diff subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "update", "README.md", "docs/contract.md", "--check"]) {
+            Command::Update(args) => {
+                assert_eq!(args.files, vec![PathBuf::from("README.md"), PathBuf::from("docs/contract.md")]);
+                assert!(args.check);
+            }
+            command => panic!("Expected the update subcommand, got {:?}", command),
+        }
+        assert!(matches!(parse(&["web3d", "init"]), Command::Init));
+        assert!(parse_from(["web3d", "unknown"].iter().map(OsString::from)).is_err());
+    }
+
+    #[test]
+    fn it_only_skips_diagram_as_the_first_argument() {
+        let parse = |args: &[&str]| parse_from(args.iter().map(OsString::from)).unwrap().command;
+
+        // A git revision or a file named `diagram` is kept
+        match parse(&["cargo-diagram", "diagram", "diff", "diagram"]) {
+            Command::Diff(args) => assert_eq!(args.old, "diagram"),
+            command => panic!("Expected the diff subcommand, got {:?}", command),
+        }
+        match parse(&["web3d", "update", "diagram"]) {
+            Command::Update(args) => assert_eq!(args.files, vec![PathBuf::from("diagram")]),
+            command => panic!("Expected the update subcommand, got {:?}", command),
+        }
+        assert!(parse_from(["web3d", "init", "diagram"].iter().map(OsString::from)).is_err());
+    }
+
+    #[test]
+    fn it_lets_the_flags_given_override_the_configuration() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = Config {
+            edge_labels: true,
+            call_counts: true,
+            render: RenderConfig {
+                title_block: true,
+                footer: true,
+                ..RenderConfig::default()
+            },
+            ..Config::default()
+        };
+        let args = ["web3d", "render", "--no-edge-labels", "--no-title-block"];
+        let args = match parse_from(args.iter().map(OsString::from)).unwrap().command {
+            Command::Render(args) => args,
+            command => panic!("Expected the render subcommand, got {:?}", command),
+        };
+
+        let options = args.diagram.pipeline_options(crate_dir, Syntax::Mermaid, &config).unwrap();
+        assert!(!options.edge_labels);
+        assert!(options.call_counts);
+        let branding = args.branding.with_config(crate_dir, &config.render);
+        assert!(!branding.title_block);
+        assert!(branding.footer);
+
+        // The last of a flag & its `--no-` counterpart wins
+        let args = ["web3d", "scan", "--no-edge-labels", "--edge-labels"];
+        let args = match parse_from(args.iter().map(OsString::from)).unwrap().command {
+            Command::Scan(args) => args,
+            command => panic!("Expected the scan subcommand, got {:?}", command),
+        };
+        assert!(args.diagram.pipeline_options(crate_dir, Syntax::Mermaid, &Config::default()).unwrap().edge_labels);
+    }
+}
@@ -0,0 +1,295 @@
+//! The project configuration, read from `web3d.toml` next to the manifest or from the `[package.metadata.web3d]` table of
+//! `Cargo.toml`. The options given on the command line override it.
+use mermaid_markdown_api::scanner_pipeline::{GroupBy, LegendPlacement};
+use mermaid_markdown_api::scope::Hidden;
+use mermaid_markdown_api::syntax::{FlowDirection, Renderer, Syntax};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The name of the configuration file.
+pub const CONFIG_FILE: &str = "web3d.toml";
+
+/// The configuration written by `web3d init`, every option left at its default.
+pub const TEMPLATE: &str = r#"# Configuration of web3d, the options given on the command line override these.
+
+# Globs of the source files scanned, relative to the crate. Every .rs file when empty
+include = ["src/**/*.rs"]
+exclude = []
+
+# Direction of the diagram: TD, TB, BT, LR or BL
+direction = "TD"
+# light, dark, high-contrast, print or the path to a TOML/JSON theme file
+theme = "light"
+# group-by = "impl"
+# legend = "markdown"
+edge-labels = false
+call-counts = false
+# link-template = "https://github.com/org/repo/blob/{rev}/{path}#L{line}"
+
+# Globs of the methods left out of the diagram, e.g. "internal_*"
+exclude-methods = []
+# Kinds of functions left out of the diagram: private, process, out-of-scope or events
+hide = []
+# Merge the private helpers called from a single function into their caller
+collapse-helpers = false
+
+# Text shown in place of a method's name
+[labels]
+# ft_transfer = "Transfer tokens"
+
+[scan]
+# mermaid, dot, plantuml, plantuml_sequence, d2, drawio, graphml, cytoscape, structurizr, svg, html or markdown
+format = "mermaid"
+# output = "res/contract.md"
+
+[batch]
+# The syntax of the diagram of every entrypoint, like the format of scan
+format = "mermaid"
+# output = "res/entrypoints"
+
+[update]
+# Markdown files holding <!-- web3d:start --> & <!-- web3d:end --> blocks, relative to the crate
+files = ["README.md"]
+
+[render]
+# mmdc or native
+renderer = "mmdc"
+# output = "res/contract.svg"
+# scale = 1
+# width = 800
+# height = 600
+# background-color = "white"
+# logo = "default"
+# watermark = "default"
+title-block = false
+footer = false
+"#;
+
+/// Deserializes an optional value from its name, the way the command line parses it.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer
+fn from_name<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|name| {
+            name.parse()
+                .map_err(|e| serde::de::Error::custom(format!("invalid value `{}`: {}", name, e)))
+        })
+        .transpose()
+}
+
+/// Deserializes a list of values from their names, the way the command line parses them.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer
+fn from_names<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|name| {
+            name.parse()
+                .map_err(|e| serde::de::Error::custom(format!("invalid value `{}`: {}", name, e)))
+        })
+        .collect()
+}
+
+/// The options of the project, every one of them optional.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Globs of the source files scanned, relative to the crate. Every `.rs` file when empty
+    pub include: Vec<String>,
+    /// Globs of the source files skipped, relative to the crate
+    pub exclude: Vec<String>,
+    /// The direction of the diagram
+    #[serde(deserialize_with = "from_name")]
+    pub direction: Option<FlowDirection>,
+    /// A built-in theme or the path to a theme file, relative to the crate
+    pub theme: Option<String>,
+    /// The key grouping the nodes into subgraphs
+    #[serde(deserialize_with = "from_name")]
+    pub group_by: Option<GroupBy>,
+    /// Label the connections by relationship
+    pub edge_labels: bool,
+    /// Label the connections with the number of call sites
+    pub call_counts: bool,
+    /// Where the legend is placed
+    #[serde(deserialize_with = "from_name")]
+    pub legend: Option<LegendPlacement>,
+    /// The URL template linking the nodes to their source
+    pub link_template: Option<String>,
+    /// Globs of the names of the methods left out of the diagram
+    pub exclude_methods: Vec<String>,
+    /// The kinds of functions left out of the diagram
+    #[serde(deserialize_with = "from_names")]
+    pub hide: Vec<Hidden>,
+    /// Merge the private helpers called from a single function into their caller
+    pub collapse_helpers: bool,
+    /// The text shown in place of a method's name, by name
+    pub labels: BTreeMap<String, String>,
+    /// The options of `web3d scan`
+    pub scan: ScanConfig,
+    /// The options of `web3d batch`
+    pub batch: BatchConfig,
+    /// The options of `web3d update`
+    pub update: UpdateConfig,
+    /// The options of `web3d render`
+    pub render: RenderConfig,
+}
+
+/// The options of `web3d scan`.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScanConfig {
+    /// The syntax of the diagram
+    #[serde(deserialize_with = "from_name")]
+    pub format: Option<Syntax>,
+    /// The output file, relative to the crate
+    pub output: Option<PathBuf>,
+    /// The other contract crates modelled in a Structurizr workspace, relative to the crate
+    pub with_contracts: Vec<PathBuf>,
+}
+
+/// The options of `web3d batch`.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct BatchConfig {
+    /// The syntax of the diagrams
+    #[serde(deserialize_with = "from_name")]
+    pub format: Option<Syntax>,
+    /// The output directory, relative to the crate
+    pub output: Option<PathBuf>,
+}
+
+/// The options of `web3d update`.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UpdateConfig {
+    /// The Markdown files holding the blocks, relative to the crate
+    pub files: Vec<PathBuf>,
+}
+
+/// The options of `web3d render`.
+#[derive(Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RenderConfig {
+    /// The renderer of the diagram
+    #[serde(deserialize_with = "from_name")]
+    pub renderer: Option<Renderer>,
+    /// The output file, relative to the crate
+    pub output: Option<PathBuf>,
+    /// The scale factor of PNG and PDF output
+    pub scale: Option<f32>,
+    /// The width of the page
+    pub width: Option<u32>,
+    /// The height of the page
+    pub height: Option<u32>,
+    /// The background color
+    pub background_color: Option<String>,
+    /// The SVG logo, relative to the crate, or `default`
+    pub logo: Option<String>,
+    /// The SVG watermark, relative to the crate, or `default`
+    pub watermark: Option<String>,
+    /// Add a title block above the image
+    pub title_block: bool,
+    /// Add a footer below the image
+    pub footer: bool,
+}
+
+impl Config {
+    /// Reads the configuration of a crate: its `web3d.toml`, or else the `[package.metadata.web3d]` table of its manifest,
+    /// or else the defaults.
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate
+    pub fn load(crate_dir: &Path) -> Result<Config, Error> {
+        let invalid = |file: &Path, e: toml::de::Error| {
+            Error::new(ErrorKind::InvalidData, format!("Invalid configuration in {}: {}", file.display(), e))
+        };
+        let config_file = crate_dir.join(CONFIG_FILE);
+        if config_file.is_file() {
+            return toml::from_str(&fs::read_to_string(&config_file)?).map_err(|e| invalid(&config_file, e));
+        }
+
+        let manifest_path = crate_dir.join("Cargo.toml");
+        let metadata = fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|manifest| manifest.parse::<toml::Value>().ok())
+            .and_then(|manifest| manifest.get("package")?.get("metadata")?.get("web3d").cloned());
+        match metadata {
+            Some(metadata) => metadata.try_into().map_err(|e| invalid(&manifest_path, e)),
+            None => Ok(Config::default()),
+        }
+    }
+}
+
+/// Returns a path of the configuration resolved against the crate, unless it's a name like a built-in theme.
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+/// * `value` - The path or name
+pub fn resolve(
+    crate_dir: &Path,
+    value: &str,
+) -> String {
+    let path = crate_dir.join(value);
+    if path.exists() {
+        path.display().to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_template_and_the_manifest_metadata() {
+        let template: Config = toml::from_str(TEMPLATE).unwrap();
+        assert_eq!(template.include, vec!["src/**/*.rs".to_string()]);
+        assert_eq!(template.direction, Some(FlowDirection::TD));
+        assert_eq!(template.scan.format, Some(Syntax::Mermaid));
+        assert_eq!(template.update.files, vec![PathBuf::from("README.md")]);
+        assert_eq!(template.render.renderer, Some(Renderer::Mmdc));
+
+        let directory = std::env::temp_dir().join(format!("web3d-config-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("Cargo.toml"),
+            "[package]\nname = \"example\"\n\n[package.metadata.web3d]\ndirection = \"lr\"\nexclude-methods = [\"internal_*\"]\nhide = [\"events\", \"out-of-scope\"]\n\n[package.metadata.web3d.labels]\nft_transfer = \"Transfer tokens\"\n\n[package.metadata.web3d.render]\nscale = 2\n",
+        )
+        .unwrap();
+        let config = Config::load(&directory).unwrap();
+        assert_eq!(config.direction, Some(FlowDirection::LR));
+        assert_eq!(config.exclude_methods, vec!["internal_*".to_string()]);
+        assert_eq!(config.hide, vec![Hidden::Events, Hidden::OutOfScope]);
+        assert_eq!(config.labels.get("ft_transfer").map(String::as_str), Some("Transfer tokens"));
+        assert_eq!(config.render.scale, Some(2.0));
+
+        // The configuration file wins over the manifest
+        fs::write(directory.join(CONFIG_FILE), "direction = \"BT\"\n").unwrap();
+        assert_eq!(Config::load(&directory).unwrap().direction, Some(FlowDirection::BT));
+        fs::write(directory.join(CONFIG_FILE), "directoin = \"BT\"\n").unwrap();
+        assert!(Config::load(&directory).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,8 @@
+//! The command line tool which creates diagrams of NEAR smart contracts, run as `web3d` or `cargo diagram`.
+pub mod branding;
+pub mod cli;
+pub mod config;
+pub mod markers;
+pub mod serve;
+pub mod svg;
+pub mod watch;
@@ -0,0 +1,215 @@
+//! The diagrams kept up to date inside Markdown files, between a `<!-- web3d:start -->` & a `<!-- web3d:end -->` comment.
+//! The start marker may carry the options of its diagram, e.g. `<!-- web3d:start focus=withdraw depth=2 -->`.
+use mermaid_markdown_api::scope::Hidden;
+use mermaid_markdown_api::syntax::{FlowDirection, Syntax};
+use std::io::{Error, ErrorKind};
+
+/// The beginning of the comment starting a block.
+pub const START: &str = "<!-- web3d:start";
+/// The comment ending a block.
+pub const END: &str = "<!-- web3d:end -->";
+
+/// The options of the diagram of a block, overriding the ones of the command line & of the configuration.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct BlockOptions {
+    /// `format=plantuml`, the syntax of the diagram
+    pub format: Option<Syntax>,
+    /// `direction=LR`, the direction of the diagram
+    pub direction: Option<FlowDirection>,
+    /// `focus=withdraw`, the method the diagram is focused on
+    pub focus: Option<String>,
+    /// `depth=2`, the levels of calls followed
+    pub depth: Option<usize>,
+    /// `reverse`, shows the callers of the focused method
+    pub reverse: bool,
+    /// `hide=private,events`, the kinds of functions left out
+    pub hidden: Vec<Hidden>,
+    /// `collapse-helpers`, merges the private helpers into their caller
+    pub collapse_helpers: bool,
+    /// `exclude-method=internal_*,log_*`, globs of the methods left out
+    pub exclude_methods: Vec<String>,
+}
+
+impl BlockOptions {
+    /// Parses the options of a start marker, space separated `key=value` pairs & flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text between `web3d:start` & the end of the comment
+    pub fn parse(text: &str) -> Result<BlockOptions, Error> {
+        let invalid = |option: &str, message: String| {
+            Error::new(ErrorKind::InvalidInput, format!("Invalid web3d block option {}: {}", option, message))
+        };
+        let mut options = BlockOptions::default();
+        for option in text.split_whitespace() {
+            let (key, value) = match option.split_once('=') {
+                Some((key, value)) => (key, Some(value.trim_matches('"'))),
+                None => (option, None),
+            };
+            match (key, value) {
+                ("format", Some(value)) => {
+                    options.format = Some(value.parse().map_err(|e| invalid(option, format!("{}", e)))?)
+                }
+                ("direction", Some(value)) => {
+                    options.direction = Some(value.parse().map_err(|e| invalid(option, format!("{}", e)))?)
+                }
+                ("focus", Some(value)) => options.focus = Some(value.to_string()),
+                ("depth", Some(value)) => {
+                    options.depth = Some(value.parse().map_err(|e| invalid(option, format!("{}", e)))?)
+                }
+                ("reverse", None) => options.reverse = true,
+                ("hide", Some(value)) => {
+                    options.hidden = value
+                        .split(',')
+                        .map(|kind| kind.parse().map_err(|e| invalid(option, format!("{}", e))))
+                        .collect::<Result<_, _>>()?
+                }
+                ("collapse-helpers", None) => options.collapse_helpers = true,
+                ("exclude-method", Some(value)) => {
+                    options.exclude_methods = value.split(',').map(str::to_string).collect()
+                }
+                _ => return Err(invalid(option, "unknown option".to_string())),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Returns the fence a line opens or closes a fenced code block with, its character & its length, e.g. `('`', 3)`.
+///
+/// # Arguments
+///
+/// * `line` - The line, trimmed
+fn fence(line: &str) -> Option<(char, usize)> {
+    let character = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = line.chars().take_while(|c| *c == character).count();
+    (length >= 3).then_some((character, length))
+}
+
+/// Replaces the content of every block of a Markdown text with the one generated from its options.
+///
+/// Returns the updated text, the text around the blocks & the markers themselves left as they are. The markers inside
+/// fenced code blocks are examples, left as they are too.
+///
+/// # Arguments
+///
+/// * `text` - The Markdown text
+/// * `generate` - Generates the content of a block from its options
+pub fn update<F>(
+    text: &str,
+    mut generate: F,
+) -> Result<String, Error>
+where
+    F: FnMut(&BlockOptions) -> Result<String, Error>,
+{
+    let unbalanced = |line: usize, message: &str| {
+        Error::new(ErrorKind::InvalidData, format!("Line {}: {}", line, message))
+    };
+    let mut updated = String::with_capacity(text.len());
+    // The line the block being replaced starts at
+    let mut open_block: Option<usize> = None;
+    // The fence of the code block the line is in
+    let mut open_fence: Option<(char, usize)> = None;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let marker = line.trim();
+        if open_block.is_none() {
+            match (open_fence, fence(marker)) {
+                (None, Some(opening)) => {
+                    open_fence = Some(opening);
+                    updated.push_str(line);
+                    continue;
+                }
+                (Some((character, length)), Some((closing, closing_length)))
+                    if closing == character
+                        && closing_length >= length
+                        && marker.trim_start_matches(character).is_empty() =>
+                {
+                    open_fence = None;
+                    updated.push_str(line);
+                    continue;
+                }
+                (Some(_), _) => {
+                    updated.push_str(line);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if let Some(options) = marker.strip_prefix(START) {
+            if let Some(start) = open_block {
+                return Err(unbalanced(index + 1, &format!("the web3d block started at line {} isn't ended", start)));
+            }
+            let options = options
+                .strip_suffix("-->")
+                .ok_or_else(|| unbalanced(index + 1, "the web3d:start comment isn't closed on its line"))?;
+            updated.push_str(line);
+            let content = generate(&BlockOptions::parse(options)?)?;
+            updated.push_str(&content);
+            if !content.is_empty() && !content.ends_with('\n') {
+                updated.push('\n');
+            }
+            open_block = Some(index + 1);
+        } else if marker == END {
+            if open_block.take().is_none() {
+                return Err(unbalanced(index + 1, "web3d:end without a web3d:start"));
+            }
+            updated.push_str(line);
+        } else if open_block.is_none() {
+            updated.push_str(line);
+        }
+    }
+    match open_block {
+        Some(start) => Err(unbalanced(start, "the web3d block isn't ended")),
+        None => Ok(updated),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_replaces_the_content_of_the_blocks() {
+        let text = "# Vault\n\n<!-- web3d:start -->\nstale\n<!-- web3d:end -->\n\nText\n<!-- web3d:start focus=withdraw depth=2 reverse hide=private,events format=dot -->\n<!-- web3d:end -->\n";
+        let mut blocks: Vec<BlockOptions> = Vec::new();
+        let updated = update(text, |options| {
+            blocks.push(options.clone());
+            Ok(format!("diagram {}", blocks.len()))
+        })
+        .unwrap();
+
+        assert_eq!(
+            updated,
+            "# Vault\n\n<!-- web3d:start -->\ndiagram 1\n<!-- web3d:end -->\n\nText\n<!-- web3d:start focus=withdraw depth=2 reverse hide=private,events format=dot -->\ndiagram 2\n<!-- web3d:end -->\n"
+        );
+        assert_eq!(blocks[0], BlockOptions::default());
+        assert_eq!(
+            blocks[1],
+            BlockOptions {
+                format: Some(Syntax::Dot),
+                focus: Some("withdraw".to_string()),
+                depth: Some(2),
+                reverse: true,
+                hidden: vec![Hidden::Private, Hidden::Events],
+                ..Default::default()
+            }
+        );
+
+        assert!(update("<!-- web3d:start -->\n", |_| Ok(String::new())).is_err());
+        assert!(update("<!-- web3d:end -->\n", |_| Ok(String::new())).is_err());
+        assert!(update("<!-- web3d:start colour=red -->\n<!-- web3d:end -->\n", |_| Ok(String::new())).is_err());
+    }
+
+    #[test]
+    fn it_leaves_the_fenced_examples_unchanged() {
+        let example = "Add the markers:\n\n````markdown\n<!-- web3d:start -->\n```\n<!-- web3d:end -->\n````\n\n~~~\n<!-- web3d:end -->\n~~~\n";
+        assert_eq!(update(example, |_| unreachable!()).unwrap(), example);
+
+        let text = format!("{}\n<!-- web3d:start -->\n```mermaid\nstale\n```\n<!-- web3d:end -->\n", example);
+        let updated = update(&text, |_| Ok("```mermaid\nflowchart TD\n```\n".to_string())).unwrap();
+        assert_eq!(
+            updated,
+            format!("{}\n<!-- web3d:start -->\n```mermaid\nflowchart TD\n```\n<!-- web3d:end -->\n", example)
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! A local preview server, showing the diagram in the browser & reloading it over server-sent events whenever it changes.
+use mermaid_markdown_api::syntax::escape_xml;
+use std::io::{BufRead, BufReader, Error, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long an idle event stream waits before sending a keep-alive comment.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// The Mermaid.js bundle shipped with web3d, Mermaid 9.1.5 under the MIT license of `mermaid.min.js.LICENSE.txt`.
+const MERMAID_JS: &str = include_str!("mermaid.min.js");
+
+/// The diagram shown by the preview.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagram {
+    /// An SVG image, inlined into the page
+    Svg(String),
+    /// A Mermaid.js flowchart, rendered in the browser
+    Mermaid(String),
+}
+
+/// The contents of the preview page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// The title of the page, e.g. the name of the contract
+    pub title: String,
+    /// The diagram
+    pub diagram: Diagram,
+    /// The error of the last scan, shown above the last diagram scanned successfully
+    pub error: Option<String>,
+}
+
+impl Page {
+    /// Returns the HTML of the page, which reloads itself once the generation of the preview moves past its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation` - The generation of the preview the page shows
+    pub fn to_html(
+        &self,
+        generation: u64,
+    ) -> String {
+        let (diagram, scripts) = match &self.diagram {
+            Diagram::Svg(svg) => (svg.clone(), String::new()),
+            Diagram::Mermaid(source) => (
+                format!("<pre class=\"mermaid\">{}</pre>", escape_xml(source)),
+                "<script src=\"/mermaid.min.js\"></script>\n<script>mermaid.initialize({ startOnLoad: true, securityLevel: \"loose\" });</script>\n".to_string(),
+            ),
+        };
+        let error = self
+            .error
+            .as_ref()
+            .map(|error| format!("<div class=\"error\">{}</div>\n", escape_xml(error)))
+            .unwrap_or_default();
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title} - web3d</title>
+<style>
+body {{ margin: 0; font-family: "trebuchet ms", verdana, arial, sans-serif; background: #f6f6f6; }}
+header {{ display: flex; justify-content: space-between; align-items: center; padding: 8px 16px; background: #24292f; color: #ffffff; }}
+header .status {{ font-size: 12px; opacity: 0.7; }}
+.error {{ margin: 16px; padding: 8px 16px; border: 1px solid #cf222e; background: #ffebe9; color: #82071e; white-space: pre-wrap; }}
+main {{ padding: 16px; overflow: auto; }}
+main svg {{ max-width: none; height: auto; }}
+</style>
+</head>
+<body>
+<header><strong>{title}</strong><span class="status" id="status">Watching for changes</span></header>
+{error}<main>
+{diagram}
+</main>
+{scripts}<script>
+const events = new EventSource("/events?since={generation}");
+events.addEventListener("reload", () => location.reload());
+events.onerror = () => {{ document.getElementById("status").textContent = "Disconnected, retrying"; }};
+events.onopen = () => {{ document.getElementById("status").textContent = "Watching for changes"; }};
+</script>
+</body>
+</html>
+"#,
+            title = escape_xml(&self.title),
+            error = error,
+            diagram = diagram,
+            scripts = scripts,
+            generation = generation,
+        )
+    }
+}
+
+/// The state of the preview shared by the connections: the page & its generation, bumped on every change.
+pub struct Preview {
+    /// The generation of the page & the page
+    state: Mutex<(u64, Page)>,
+    /// Notifies the event streams of a new generation
+    changed: Condvar,
+    /// The Mermaid.js bundle served to the pages showing a Mermaid.js flowchart, the one shipped with web3d if `None`
+    mermaid_js: Option<String>,
+}
+
+impl Preview {
+    /// Creates the preview of a page.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The first page
+    /// * `mermaid_js` - The Mermaid.js bundle served at `/mermaid.min.js` in place of the one shipped with web3d, if any
+    pub fn new(
+        page: Page,
+        mermaid_js: Option<String>,
+    ) -> Preview {
+        Preview {
+            state: Mutex::new((0, page)),
+            changed: Condvar::new(),
+            mermaid_js,
+        }
+    }
+
+    /// Replaces the page, reloading every browser showing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The new page
+    pub fn publish(
+        &self,
+        page: Page,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        *state = (state.0 + 1, page);
+        self.changed.notify_all();
+    }
+
+    /// Returns the current page.
+    pub fn page(&self) -> Page {
+        self.state.lock().unwrap().1.clone()
+    }
+
+    /// Returns the current generation, once it moved past a generation or the timeout elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `generation` - The generation the caller knows of
+    /// * `timeout` - The longest time to wait
+    fn wait_past(
+        &self,
+        generation: u64,
+        timeout: Duration,
+    ) -> u64 {
+        let state = self.state.lock().unwrap();
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.0 <= generation)
+            .unwrap();
+        state.0
+    }
+}
+
+/// Writes a complete HTTP response.
+///
+/// # Arguments
+///
+/// * `stream` - The connection
+/// * `status` - The status line, e.g. `200 OK`
+/// * `content_type` - The type of the body
+/// * `body` - The body
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<(), Error> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// Streams a `reload` event to the browser every time the generation of the preview moves past the one of its page.
+///
+/// # Arguments
+///
+/// * `stream` - The connection
+/// * `preview` - The preview
+/// * `generation` - The generation of the page listening
+fn stream_events(
+    stream: &mut TcpStream,
+    preview: &Preview,
+    mut generation: u64,
+) -> Result<(), Error> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\nretry: 1000\n\n"
+    )?;
+    stream.flush()?;
+    // The stream ends with the first write failing, once the browser left the page
+    loop {
+        let current = preview.wait_past(generation, KEEP_ALIVE);
+        if current > generation {
+            write!(stream, "event: reload\ndata: {}\n\n", current)?;
+            generation = current;
+        } else {
+            write!(stream, ": keep-alive\n\n")?;
+        }
+        stream.flush()?;
+    }
+}
+
+/// Answers a single request of the browser.
+///
+/// # Arguments
+///
+/// * `stream` - The connection
+/// * `preview` - The preview
+fn handle_connection(
+    mut stream: TcpStream,
+    preview: &Preview,
+) -> Result<(), Error> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, the body of a GET request being empty
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"Method not allowed");
+    }
+    match path {
+        "/" | "/index.html" => {
+            let (generation, page) = {
+                let state = preview.state.lock().unwrap();
+                (state.0, state.1.clone())
+            };
+            respond(&mut stream, "200 OK", "text/html; charset=utf-8", page.to_html(generation).as_bytes())
+        }
+        "/events" => {
+            let since = query
+                .split('&')
+                .find_map(|parameter| parameter.strip_prefix("since="))
+                .and_then(|since| since.parse().ok())
+                .unwrap_or(0);
+            stream_events(&mut stream, preview, since)
+        }
+        "/mermaid.min.js" => respond(
+            &mut stream,
+            "200 OK",
+            "text/javascript; charset=utf-8",
+            preview.mermaid_js.as_deref().unwrap_or(MERMAID_JS).as_bytes(),
+        ),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"Not found"),
+    }
+}
+
+/// Answers the browsers connecting to the listener, each connection on its own thread, until the process ends.
+///
+/// # Arguments
+///
+/// * `listener` - The bound listener
+/// * `preview` - The preview shown to the browsers
+pub fn serve(
+    listener: TcpListener,
+    preview: Arc<Preview>,
+) {
+    for stream in listener.incoming().flatten() {
+        let preview = preview.clone();
+        thread::spawn(move || {
+            // A browser closing the connection early isn't worth reporting
+            let _ = handle_connection(stream, &preview);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn page(svg: &str) -> Page {
+        Page {
+            title: "example".to_string(),
+            diagram: Diagram::Svg(svg.to_string()),
+            error: None,
+        }
+    }
+
+    fn get(
+        address: std::net::SocketAddr,
+        target: &str,
+    ) -> TcpStream {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+        stream
+    }
+
+    #[test]
+    fn it_escapes_the_mermaid_source() {
+        let html = Page {
+            title: "a & b".to_string(),
+            diagram: Diagram::Mermaid("flowchart TD\na-->b[\"<b>\"]".to_string()),
+            error: Some("expected `;`".to_string()),
+        }
+        .to_html(3);
+
+        assert!(html.contains("<title>a &amp; b - web3d</title>"));
+        assert!(html.contains("<pre class=\"mermaid\">flowchart TD\na--&gt;b[&quot;&lt;b&gt;&quot;]</pre>"));
+        assert!(html.contains("<script src=\"/mermaid.min.js\"></script>"));
+        assert!(html.contains("<div class=\"error\">expected `;`</div>"));
+        assert!(html.contains("new EventSource(\"/events?since=3\")"));
+    }
+
+    #[test]
+    fn it_serves_the_page_and_pushes_reloads() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let preview = Arc::new(Preview::new(page("<svg id=\"first\"/>"), None));
+        let server_preview = preview.clone();
+        thread::spawn(move || serve(listener, server_preview));
+
+        let mut response = String::new();
+        get(address, "/").read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("<svg id=\"first\"/>"));
+
+        let mut response = String::new();
+        get(address, "/mermaid.min.js").read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(MERMAID_JS));
+
+        let mut events = BufReader::new(get(address, "/events?since=0"));
+        let mut line = String::new();
+        while line != "retry: 1000\n" {
+            line.clear();
+            events.read_line(&mut line).unwrap();
+        }
+        preview.publish(page("<svg id=\"second\"/>"));
+        while !line.starts_with("event:") {
+            line.clear();
+            events.read_line(&mut line).unwrap();
+        }
+        assert_eq!(line, "event: reload\n");
+        assert_eq!(preview.page(), page("<svg id=\"second\"/>"));
+    }
+}
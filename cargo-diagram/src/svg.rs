@@ -1,6 +1,12 @@
+//! Loading of SVG images & their export to PNG & PDF, without any external tool.
+use mermaid_markdown_api::syntax::escape_xml;
+use mermaid_markdown_api::syntax::svg::load_system_fonts;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
 fn with_svg_options<T>(callback: impl FnOnce(usvg::OptionsRef<'_>) -> T) -> T {
     let mut options = usvg::Options::default();
-    options.fontdb.load_system_fonts();
+    load_system_fonts(&mut options.fontdb);
     let options_ref = options.to_ref();
 
     callback(options_ref)
@@ -20,5 +26,245 @@ pub fn load_from_data(slice: &[u8]) -> Result<usvg::Tree, usvg::Error> {
     with_svg_options(|options| usvg::Tree::from_data(slice, &options))
 }
 
+/// The size & background of the images exported from an SVG.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    /// The factor the size of the image is multiplied by
+    pub scale: f32,
+    /// The width the image is fitted to, keeping its aspect ratio
+    pub width: Option<u32>,
+    /// The height the image is fitted to, keeping its aspect ratio
+    pub height: Option<u32>,
+    /// The colour painted behind the image, `None` to leave it transparent
+    pub background: Option<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            scale: 1.0,
+            width: None,
+            height: None,
+            background: Some("white".to_string()),
+        }
+    }
+}
 
+/// Returns `true` if the file is exported from an SVG, i.e. it's a PNG or PDF file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file
+pub fn is_exported(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("png") | Some("pdf")
+    )
+}
+
+/// Returns the SVG with a rectangle of the background colour covering its view box, behind every other element.
+///
+/// # Arguments
+///
+/// * `svg` - The SVG image
+/// * `view_box` - The view box of the image
+/// * `color` - The background colour
+fn with_background(
+    svg: &str,
+    view_box: usvg::Rect,
+    color: &str,
+) -> String {
+    // The rectangle is inserted right after the start tag of the root element
+    let start_tag_end = svg
+        .find("<svg")
+        .and_then(|start| svg[start..].find('>').map(|end| start + end + 1));
+    match start_tag_end {
+        Some(index) if !svg[..index].ends_with("/>") => format!(
+            "{}<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>{}",
+            &svg[..index],
+            view_box.x(),
+            view_box.y(),
+            view_box.width(),
+            view_box.height(),
+            escape_xml(color),
+            &svg[index..]
+        ),
+        _ => svg.to_string(),
+    }
+}
+
+/// Returns the size of the exported image, in pixels or PDF points.
+///
+/// # Arguments
+///
+/// * `tree` - The SVG image
+/// * `options` - The size of the exported image
+fn export_size(
+    tree: &usvg::Tree,
+    options: &ExportOptions,
+) -> Result<usvg::ScreenSize, Error> {
+    let fit_to = match (options.width, options.height) {
+        (Some(width), Some(height)) => usvg::FitTo::Size(width, height),
+        (Some(width), None) => usvg::FitTo::Width(width),
+        (None, Some(height)) => usvg::FitTo::Height(height),
+        (None, None) => usvg::FitTo::Original,
+    };
+    let size = fit_to
+        .fit_to(tree.svg_node().size.to_screen_size())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "The image can't be fitted to the requested size"))?;
+    let scaled = |length: u32| ((length as f32 * options.scale).round() as u32).max(1);
+    usvg::ScreenSize::new(scaled(size.width()), scaled(size.height()))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "The requested size of the image is empty"))
+}
+
+/// Rasterizes an SVG image into a PNG image.
+///
+/// # Arguments
+///
+/// * `tree` - The SVG image
+/// * `options` - The size of the PNG image
+pub fn export_png(
+    tree: &usvg::Tree,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, Error> {
+    let size = export_size(tree, options)?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "The requested size of the image is empty"))?;
+    resvg::render(
+        tree,
+        usvg::FitTo::Size(size.width(), size.height()),
+        tiny_skia::Transform::default(),
+        pixmap.as_mut(),
+    )
+    .ok_or_else(|| Error::other("The image couldn't be rendered"))?;
+    pixmap
+        .encode_png()
+        .map_err(Error::other)
+}
+
+/// Converts an SVG image into a single page PDF document.
+///
+/// # Arguments
+///
+/// * `tree` - The SVG image
+/// * `options` - The size of the page, in points
+pub fn export_pdf(
+    tree: &usvg::Tree,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, Error> {
+    let size = export_size(tree, options)?;
+    Ok(svg2pdf::convert_tree(
+        tree,
+        svg2pdf::Options {
+            viewport: Some((size.width() as f64, size.height() as f64)),
+            ..svg2pdf::Options::default()
+        },
+    ))
+}
 
+/// Exports an SVG file to a PNG or PDF file, chosen by the extension of the output file.
+///
+/// # Arguments
+///
+/// * `svg_path` - The path of the SVG file
+/// * `output_path` - The path of the PNG or PDF file
+/// * `options` - The size & background of the exported image
+pub fn export(
+    svg_path: &Path,
+    output_path: &Path,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    let svg = std::fs::read_to_string(svg_path)?;
+    let mut tree = load_from_data(svg.as_bytes()).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    // Transparent is the default of both formats, any other colour is drawn as part of the image
+    if let Some(color) = options.background.as_deref().filter(|color| *color != "transparent") {
+        let svg = with_background(&svg, tree.svg_node().view_box.rect, color);
+        tree = load_from_data(svg.as_bytes()).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    }
+
+    let data = match output_path.extension().and_then(|extension| extension.to_str()) {
+        Some("png") => export_png(&tree, options)?,
+        Some("pdf") => export_pdf(&tree, options)?,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Can't export {} from an SVG, expected a png or pdf file", output_path.display()),
+            ))
+        }
+    };
+    std::fs::write(output_path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200\" height=\"100\" viewBox=\"-10 -10 200 100\"><rect x=\"0\" y=\"0\" width=\"50\" height=\"50\" fill=\"red\"/></svg>";
+
+    #[test]
+    fn it_fits_the_image_to_the_requested_size() {
+        let tree = load_from_data(SVG.as_bytes()).unwrap();
+        let size = |options: ExportOptions| {
+            let size = export_size(&tree, &options).unwrap();
+            (size.width(), size.height())
+        };
+
+        assert_eq!(size(ExportOptions::default()), (200, 100));
+        assert_eq!(
+            size(ExportOptions {
+                scale: 2.0,
+                ..ExportOptions::default()
+            }),
+            (400, 200)
+        );
+        assert_eq!(
+            size(ExportOptions {
+                width: Some(100),
+                ..ExportOptions::default()
+            }),
+            (100, 50)
+        );
+        assert_eq!(
+            size(ExportOptions {
+                width: Some(400),
+                height: Some(100),
+                scale: 0.5,
+                ..ExportOptions::default()
+            }),
+            (100, 50)
+        );
+    }
+
+    #[test]
+    fn it_exports_png_and_pdf_with_a_background() {
+        let directory = std::env::temp_dir().join(format!("web3d-export-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let svg_path = directory.join("diagram.svg");
+        std::fs::write(&svg_path, SVG).unwrap();
+
+        let png_path = directory.join("diagram.png");
+        export(
+            &svg_path,
+            &png_path,
+            &ExportOptions {
+                background: Some("#0000ff".to_string()),
+                ..ExportOptions::default()
+            },
+        )
+        .unwrap();
+        let pixmap = tiny_skia::Pixmap::load_png(&png_path).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (200, 100));
+        // The background covers the view box, which starts left of & above the origin
+        let color = |x: u32, y: u32| pixmap.pixel(x, y).unwrap();
+        assert_eq!((color(0, 0).red(), color(0, 0).blue()), (0, 255));
+        assert_eq!((color(20, 20).red(), color(20, 20).blue()), (255, 0));
+
+        let pdf_path = directory.join("diagram.pdf");
+        export(&svg_path, &pdf_path, &ExportOptions::default()).unwrap();
+        assert!(std::fs::read(&pdf_path).unwrap().starts_with(b"%PDF"));
+
+        assert!(export(&svg_path, &directory.join("diagram.gif"), &ExportOptions::default()).is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
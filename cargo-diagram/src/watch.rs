@@ -0,0 +1,293 @@
+//! Watching of the sources of a crate, through the filesystem notifications of the platform.
+//!
+//! Where notifications aren't available, e.g. on some network filesystems or once the watches of the system ran out, the
+//! modification times of the files are polled instead.
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// How often the files are polled, when notifications aren't available.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// How long the files must stay unchanged before a burst of changes, like a save touching several files, is reported.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The modification time of every watched file of a crate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The modification time of every file, by path
+    pub files: BTreeMap<PathBuf, SystemTime>,
+}
+
+/// Returns `true` if an entry of the crate is skipped by the watch, i.e. it's hidden or the build output.
+///
+/// # Arguments
+///
+/// * `name` - The name of the entry
+/// * `is_dir` - Whether the entry is a directory
+fn is_skipped(
+    name: &str,
+    is_dir: bool,
+) -> bool {
+    name.starts_with('.') || (is_dir && name == "target")
+}
+
+/// Returns `true` if the file affects the diagram of the crate, i.e. it's a Rust source, a manifest or the configuration.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file
+pub fn is_watched(path: &Path) -> bool {
+    path.extension().map(|extension| extension == "rs").unwrap_or(false)
+        || path
+            .file_name()
+            .map(|name| name == "Cargo.toml" || name == crate::config::CONFIG_FILE)
+            .unwrap_or(false)
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the watched files of a crate, skipping the build output & hidden directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate
+    pub fn take(crate_dir: &Path) -> Snapshot {
+        let files = WalkDir::new(crate_dir)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !is_skipped(&entry.file_name().to_string_lossy(), entry.file_type().is_dir())
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && is_watched(entry.path()))
+            .filter_map(|entry| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((entry.into_path(), modified))
+            })
+            .collect();
+        Snapshot { files }
+    }
+
+    /// Returns the files added, modified or removed since an earlier snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `earlier` - The earlier snapshot
+    pub fn changes_since(
+        &self,
+        earlier: &Snapshot,
+    ) -> Vec<PathBuf> {
+        let mut changes: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, modified)| earlier.files.get(*path) != Some(*modified))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changes.extend(
+            earlier
+                .files
+                .keys()
+                .filter(|path| !self.files.contains_key(*path))
+                .cloned(),
+        );
+        changes
+    }
+}
+
+/// Where the changes of the files come from.
+enum Source {
+    /// The notifications of the platform, debounced
+    Notifications {
+        /// Keeps the notifications coming while it's alive
+        _debouncer: Debouncer<RecommendedWatcher>,
+        /// The debounced bursts of changes
+        events: Receiver<DebounceEventResult>,
+    },
+    /// The modification times of the files, polled
+    Polling(Snapshot),
+}
+
+/// Watches the sources of a crate, reporting their changes once a burst of them settled.
+pub struct Watcher {
+    /// The directory of the crate
+    crate_dir: PathBuf,
+    /// How long the files must stay unchanged before their changes are reported
+    debounce: Duration,
+    /// Where the changes come from
+    source: Source,
+}
+
+impl Watcher {
+    /// Starts watching a crate through filesystem notifications, or by polling its files when they aren't available.
+    ///
+    /// # Arguments
+    ///
+    /// * `crate_dir` - The directory of the crate
+    /// * `debounce` - How long the files must stay unchanged before their changes are reported
+    pub fn new(
+        crate_dir: &Path,
+        debounce: Duration,
+    ) -> Watcher {
+        let (sender, events) = mpsc::channel();
+        let source = new_debouncer(debounce, sender)
+            .and_then(|mut debouncer| {
+                debouncer.watcher().watch(crate_dir, RecursiveMode::Recursive)?;
+                Ok(Source::Notifications {
+                    _debouncer: debouncer,
+                    events,
+                })
+            })
+            .unwrap_or_else(|_| Source::Polling(Snapshot::take(crate_dir)));
+        Watcher {
+            crate_dir: crate_dir.to_path_buf(),
+            debounce,
+            source,
+        }
+    }
+
+    /// Returns `true` if the files are polled, filesystem notifications not being available.
+    pub fn is_polling(&self) -> bool {
+        matches!(self.source, Source::Polling(_))
+    }
+
+    /// Returns `true` if a changed path is one of the watched files of the crate.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The changed path
+    fn watches(
+        &self,
+        path: &Path,
+    ) -> bool {
+        let relative = path.strip_prefix(&self.crate_dir).unwrap_or(path);
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            let is_dir = components.peek().is_some();
+            if is_skipped(&component.as_os_str().to_string_lossy(), is_dir) {
+                return false;
+            }
+        }
+        is_watched(path)
+    }
+
+    /// Blocks until the watched files change & then stay unchanged for the debounce time, returning the changed files.
+    ///
+    /// Notifications failing, e.g. once their queue overflowed, the files are polled from then on.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        loop {
+            let received = match &mut self.source {
+                Source::Notifications { events, .. } => events.recv(),
+                Source::Polling(snapshot) => return wait_for_changes(&self.crate_dir, snapshot, self.debounce),
+            };
+            match received {
+                Ok(Ok(events)) => {
+                    let mut changes: Vec<PathBuf> = events
+                        .into_iter()
+                        .map(|event| event.path)
+                        .filter(|path| self.watches(path))
+                        .collect();
+                    changes.sort();
+                    changes.dedup();
+                    if !changes.is_empty() {
+                        return changes;
+                    }
+                }
+                Ok(Err(_)) | Err(_) => self.source = Source::Polling(Snapshot::take(&self.crate_dir)),
+            }
+        }
+    }
+}
+
+/// Blocks until the watched files of a crate change & then stay unchanged for the debounce time, returning the changed
+/// files & moving the snapshot to the settled state.
+///
+/// # Arguments
+///
+/// * `crate_dir` - The directory of the crate
+/// * `snapshot` - The snapshot of the files the caller knows of
+/// * `debounce` - How long the files must stay unchanged
+fn wait_for_changes(
+    crate_dir: &Path,
+    snapshot: &mut Snapshot,
+    debounce: Duration,
+) -> Vec<PathBuf> {
+    let mut latest = loop {
+        thread::sleep(POLL_INTERVAL);
+        let next = Snapshot::take(crate_dir);
+        if next != *snapshot {
+            break next;
+        }
+    };
+    // Wait for the burst to settle, the files changing again restarting the wait
+    let mut settled_for = Duration::ZERO;
+    while settled_for < debounce {
+        thread::sleep(POLL_INTERVAL.min(debounce));
+        let next = Snapshot::take(crate_dir);
+        if next == latest {
+            settled_for += POLL_INTERVAL.min(debounce);
+        } else {
+            latest = next;
+            settled_for = Duration::ZERO;
+        }
+    }
+    let changes = latest.changes_since(snapshot);
+    *snapshot = latest;
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn it_reports_the_changed_sources() {
+        let directory = std::env::temp_dir().join(format!("web3d-watch-{}", std::process::id()));
+        fs::create_dir_all(directory.join("src")).unwrap();
+        fs::create_dir_all(directory.join("target")).unwrap();
+        fs::write(directory.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(directory.join("src/lib.rs"), "").unwrap();
+        fs::write(directory.join("src/notes.md"), "").unwrap();
+        fs::write(directory.join("target/build.rs"), "").unwrap();
+
+        let earlier = Snapshot::take(&directory);
+        assert_eq!(earlier.files.len(), 2);
+
+        let source = fs::File::options().write(true).open(directory.join("src/lib.rs")).unwrap();
+        source.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        fs::write(directory.join("src/main.rs"), "").unwrap();
+        fs::remove_file(directory.join("Cargo.toml")).unwrap();
+        let mut changes = Snapshot::take(&directory).changes_since(&earlier);
+        changes.sort();
+
+        assert_eq!(
+            changes,
+            vec![
+                directory.join("Cargo.toml"),
+                directory.join("src/lib.rs"),
+                directory.join("src/main.rs"),
+            ]
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn it_is_notified_of_the_changed_sources() {
+        let directory = std::env::temp_dir().join(format!("web3d-notify-{}", std::process::id()));
+        fs::create_dir_all(directory.join("src")).unwrap();
+        fs::create_dir_all(directory.join("target")).unwrap();
+        fs::write(directory.join("src/lib.rs"), "").unwrap();
+
+        let mut watcher = Watcher::new(&directory, Duration::from_millis(100));
+        fs::write(directory.join("src/notes.md"), "").unwrap();
+        fs::write(directory.join("target/build.rs"), "").unwrap();
+        fs::write(directory.join("src/lib.rs"), "pub struct Contract;").unwrap();
+
+        assert_eq!(watcher.wait(), vec![directory.join("src/lib.rs")]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
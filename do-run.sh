@@ -1,4 +1,4 @@
 set -e
 cargo build --workspace --release 
 cd ./example
-../target/release/web3d -O --input diagram.md
\ No newline at end of file
+../target/release/web3d render -O
\ No newline at end of file
@@ -0,0 +1,445 @@
+use crate::documentation::{collect_effects, is_public_method, method_kind};
+use crate::theme::Style;
+use scanner_syn::contract_descriptor::{ArgumentInfo, ContractDescriptorMeta, ContractInfo, FunctionInfo};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How a public method changed from one version of a contract to the next.
+#[derive(AsRefStr, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChangeKind {
+    /// The method is new
+    Added,
+    /// The method is gone
+    Removed,
+    /// The method is called differently, takes or returns something else, or calls other contracts & emits other events
+    Changed,
+}
+
+impl ChangeKind {
+    /// Returns the style of the nodes of the methods changed this way in the diagram of the diff: green, red or amber.
+    pub fn style(&self) -> Style {
+        let (fill, stroke) = match self {
+            ChangeKind::Added => ("#2DA44E", "#116329"),
+            ChangeKind::Removed => ("#CF222E", "#82071E"),
+            ChangeKind::Changed => ("#D4A72C", "#7D4E00"),
+        };
+        Style {
+            fill: Some(fill.to_string()),
+            stroke: Some(stroke.to_string()),
+            stroke_width: Some("3px".to_string()),
+            stroke_dasharray: None,
+            color: Some("#FFFFFF".to_string()),
+        }
+    }
+}
+
+/// A public method added, removed or changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodDiff {
+    /// The name of the method
+    pub name: String,
+    /// How the method changed
+    pub change: ChangeKind,
+    /// What changed, e.g. ``became payable``, empty for added & removed methods
+    pub details: Vec<String>,
+}
+
+/// The differences between the public API of two versions of a contract.
+pub struct ContractDiff {
+    /// The methods added, removed or changed, the ones of the new version first, in source order
+    pub methods: Vec<MethodDiff>,
+}
+
+/// Returns the public methods of a contract by name, the first one of a name winning.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+fn public_methods(contract: &ContractInfo) -> BTreeMap<&str, &FunctionInfo> {
+    let mut methods: BTreeMap<&str, &FunctionInfo> = BTreeMap::new();
+    for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+        if is_public_method(function) {
+            methods.entry(function.name.as_str()).or_insert(function);
+        }
+    }
+    methods
+}
+
+/// Returns the arguments of a method as they read in a signature, e.g. `amount: U128, msg: String (borsh)`.
+///
+/// # Arguments
+///
+/// * `arguments` - The arguments
+fn arguments_text(arguments: &[ArgumentInfo]) -> String {
+    if arguments.is_empty() {
+        return "none".to_string();
+    }
+    arguments
+        .iter()
+        .map(|argument| match argument.serializer.as_str() {
+            "borsh" => format!("{}: {} (borsh)", argument.name, argument.ty),
+            _ => format!("{}: {}", argument.name, argument.ty),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the events emitted & the cross-contract calls made by a method, itself or through the functions it calls.
+///
+/// # Arguments
+///
+/// * `function` - The method
+/// * `contract` - The contract of the method
+fn effects(
+    function: &FunctionInfo,
+    contract: &ContractInfo,
+) -> (Vec<String>, Vec<String>) {
+    let functions: Vec<&FunctionInfo> = contract.contract_metadata.iter().flat_map(|m| &m.fns).collect();
+    let mut events: Vec<String> = Vec::new();
+    let mut external_calls: Vec<String> = Vec::new();
+    collect_effects(function, &functions, &mut BTreeSet::new(), &mut events, &mut external_calls);
+    (events, external_calls)
+}
+
+/// Describes the items added to & removed from a list, e.g. the cross-contract calls of a method.
+///
+/// # Arguments
+///
+/// * `old` - The items of the old version
+/// * `new` - The items of the new version
+/// * `added` - The verb describing an added item, e.g. `now calls`
+/// * `removed` - The verb describing a removed item, e.g. `no longer calls`
+/// * `details` - The details collected so far
+fn list_changes(
+    old: &[String],
+    new: &[String],
+    added: &str,
+    removed: &str,
+    details: &mut Vec<String>,
+) {
+    for item in new.iter().filter(|item| !old.contains(item)) {
+        details.push(format!("{} `{}`", added, item));
+    }
+    for item in old.iter().filter(|item| !new.contains(item)) {
+        details.push(format!("{} `{}`", removed, item));
+    }
+}
+
+impl ContractDiff {
+    /// Compares the public methods of two versions of a contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The old version of the contract
+    /// * `new` - The new version of the contract
+    pub fn between(
+        old: &ContractInfo,
+        new: &ContractInfo,
+    ) -> ContractDiff {
+        let old_methods = public_methods(old);
+        let new_methods = public_methods(new);
+        let mut methods: Vec<MethodDiff> = Vec::new();
+
+        let in_source_order = |contract: &'_ ContractInfo| -> Vec<String> {
+            let mut names: Vec<String> = Vec::new();
+            for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+                if is_public_method(function) && !names.contains(&function.name) {
+                    names.push(function.name.clone());
+                }
+            }
+            names
+        };
+        for name in in_source_order(new) {
+            let new_method = new_methods[name.as_str()];
+            let old_method = match old_methods.get(name.as_str()) {
+                Some(old_method) => old_method,
+                None => {
+                    methods.push(MethodDiff {
+                        name,
+                        change: ChangeKind::Added,
+                        details: Vec::new(),
+                    });
+                    continue;
+                }
+            };
+
+            let mut details: Vec<String> = Vec::new();
+            let (old_kind, new_kind) = (method_kind(old_method), method_kind(new_method));
+            if old_kind != new_kind {
+                details.push(format!("changed from {} to {}", old_kind, new_kind));
+            }
+            match (old_method.is_payable, new_method.is_payable) {
+                (false, true) => details.push("became payable".to_string()),
+                (true, false) => details.push("is no longer payable".to_string()),
+                _ => {}
+            }
+            match (old_method.is_private_cccalls, new_method.is_private_cccalls) {
+                (false, true) => details.push("became `#[private]`".to_string()),
+                (true, false) => details.push("lost `#[private]`, anyone may call it".to_string()),
+                _ => {}
+            }
+            if old_method.arguments != new_method.arguments {
+                details.push(format!(
+                    "arguments changed from `{}` to `{}`",
+                    arguments_text(&old_method.arguments),
+                    arguments_text(&new_method.arguments)
+                ));
+            }
+            match (&old_method.return_type, &new_method.return_type) {
+                (Some(old_type), Some(new_type)) if old_type != new_type => {
+                    details.push(format!("returns `{}` rather than `{}`", new_type, old_type))
+                }
+                (None, Some(new_type)) => details.push(format!("now returns `{}`", new_type)),
+                (Some(_), None) => details.push("no longer returns a value".to_string()),
+                _ => {}
+            }
+            if let (Some(old_serializer), Some(new_serializer)) =
+                (&old_method.result_serializer, &new_method.result_serializer)
+            {
+                if old_serializer != new_serializer {
+                    details.push(format!(
+                        "serializes its result with {} rather than {}",
+                        new_serializer, old_serializer
+                    ));
+                }
+            }
+            let (old_events, old_calls) = effects(old_method, old);
+            let (new_events, new_calls) = effects(new_method, new);
+            list_changes(&old_calls, &new_calls, "now calls", "no longer calls", &mut details);
+            list_changes(&old_events, &new_events, "now emits", "no longer emits", &mut details);
+
+            if !details.is_empty() {
+                methods.push(MethodDiff {
+                    name,
+                    change: ChangeKind::Changed,
+                    details,
+                });
+            }
+        }
+        for name in in_source_order(old) {
+            if !new_methods.contains_key(name.as_str()) {
+                methods.push(MethodDiff {
+                    name,
+                    change: ChangeKind::Removed,
+                    details: Vec::new(),
+                });
+            }
+        }
+
+        ContractDiff { methods }
+    }
+
+    /// Returns whether the public API of the contract is unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+
+    /// Returns the new version of the contract along with the methods removed from the old one, so a diagram can show
+    /// both.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The old version of the contract
+    /// * `new` - The new version of the contract
+    pub fn merged_contract(
+        &self,
+        old: &ContractInfo,
+        new: &ContractInfo,
+    ) -> ContractInfo {
+        let removed: Vec<FunctionInfo> = old
+            .contract_metadata
+            .iter()
+            .flat_map(|m| &m.fns)
+            .filter(|function| {
+                self.methods
+                    .iter()
+                    .any(|method| method.change == ChangeKind::Removed && method.name == function.name)
+            })
+            .cloned()
+            .collect();
+        let mut contract_metadata = new.contract_metadata.clone();
+        contract_metadata.push(ContractDescriptorMeta {
+            fns: removed,
+            ext_contract_fns: Vec::new(),
+            connections: None,
+            tokens: None,
+        });
+        ContractInfo { contract_metadata }
+    }
+
+    /// Returns the style of the node of every method added, removed or changed, by name.
+    pub fn highlights(&self) -> BTreeMap<String, Style> {
+        self.methods
+            .iter()
+            .map(|method| (method.name.clone(), method.change.style()))
+            .collect()
+    }
+
+    /// Returns the diff as a Markdown report.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the report, usually the name of the contract
+    /// * `diagram` - The diagram of the diff, a Mermaid.js flowchart shown in a fenced block, if any
+    pub fn to_markdown(
+        &self,
+        title: &str,
+        diagram: Option<&str>,
+    ) -> String {
+        let mut markdown = format!("# API changes of {}\n\n", title);
+        if self.is_empty() {
+            markdown.push_str("The public methods are unchanged.\n");
+            return markdown;
+        }
+        let count = |change: ChangeKind| self.methods.iter().filter(|method| method.change == change).count();
+        markdown.push_str(&format!(
+            "{} added, {} removed and {} changed public method(s).\n",
+            count(ChangeKind::Added),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Changed)
+        ));
+        if let Some(diagram) = diagram {
+            markdown.push_str(&format!("\n```mermaid\n{}\n```\n", diagram.trim_end()));
+        }
+
+        for (change, heading) in [
+            (ChangeKind::Added, "Added"),
+            (ChangeKind::Removed, "Removed"),
+            (ChangeKind::Changed, "Changed"),
+        ] {
+            let methods: Vec<&MethodDiff> = self.methods.iter().filter(|method| method.change == change).collect();
+            if methods.is_empty() {
+                continue;
+            }
+            markdown.push_str(&format!("\n## {}\n\n", heading));
+            for method in methods {
+                if method.details.is_empty() {
+                    markdown.push_str(&format!("- `{}`\n", method.name));
+                } else {
+                    markdown.push_str(&format!("- `{}`: {}\n", method.name, method.details.join(", ")));
+                }
+            }
+        }
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(fns: Vec<FunctionInfo>) -> ContractInfo {
+        ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns,
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        }
+    }
+
+    fn method(name: &str) -> FunctionInfo {
+        FunctionInfo {
+            name: name.to_string(),
+            is_public: true,
+            is_mutable: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_lists_the_added_removed_and_changed_methods() {
+        let ft_transfer = FunctionInfo {
+            name: "ft_transfer".to_string(),
+            is_public: true,
+            is_out_of_contract_scope: true,
+            external_contract: Some("ext_ft".to_string()),
+            ..Default::default()
+        };
+        let amount = ArgumentInfo {
+            name: "amount".to_string(),
+            ty: "U128".to_string(),
+            serializer: "json".to_string(),
+        };
+        let old = contract(vec![
+            FunctionInfo {
+                arguments: vec![amount.clone()],
+                ..method("withdraw")
+            },
+            FunctionInfo {
+                is_view: true,
+                is_mutable: false,
+                return_type: Some("U128".to_string()),
+                result_serializer: Some("json".to_string()),
+                ..method("balance")
+            },
+            method("legacy"),
+        ]);
+        let new = contract(vec![
+            FunctionInfo {
+                is_payable: true,
+                arguments: vec![
+                    amount,
+                    ArgumentInfo {
+                        name: "memo".to_string(),
+                        ty: "String".to_string(),
+                        serializer: "json".to_string(),
+                    },
+                ],
+                inner_calls: Some(vec![ft_transfer]),
+                ..method("withdraw")
+            },
+            FunctionInfo {
+                is_view: true,
+                is_mutable: false,
+                return_type: Some("U128".to_string()),
+                result_serializer: Some("json".to_string()),
+                ..method("balance")
+            },
+            method("deposit"),
+        ]);
+
+        let diff = ContractDiff::between(&old, &new);
+        assert_eq!(
+            diff.methods,
+            vec![
+                MethodDiff {
+                    name: "withdraw".to_string(),
+                    change: ChangeKind::Changed,
+                    details: vec![
+                        "became payable".to_string(),
+                        "arguments changed from `amount: U128` to `amount: U128, memo: String`".to_string(),
+                        "now calls `ext_ft::ft_transfer`".to_string(),
+                    ],
+                },
+                MethodDiff {
+                    name: "deposit".to_string(),
+                    change: ChangeKind::Added,
+                    details: vec![],
+                },
+                MethodDiff {
+                    name: "legacy".to_string(),
+                    change: ChangeKind::Removed,
+                    details: vec![],
+                },
+            ]
+        );
+
+        let merged = diff.merged_contract(&old, &new);
+        let names: Vec<&str> = merged
+            .contract_metadata
+            .iter()
+            .flat_map(|m| &m.fns)
+            .map(|function| function.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["withdraw", "balance", "deposit", "legacy"]);
+        assert_eq!(diff.highlights()["legacy"].fill.as_deref(), Some("#CF222E"));
+
+        let markdown = diff.to_markdown("vault", Some("flowchart TD\n"));
+        assert!(markdown.starts_with("# API changes of vault\n\n1 added, 1 removed and 1 changed public method(s).\n\n```mermaid\nflowchart TD\n```\n"));
+        assert!(markdown.contains("\n## Added\n\n- `deposit`\n"));
+        assert!(markdown.contains("\n## Changed\n\n- `withdraw`: became payable, "));
+        assert!(ContractDiff::between(&old, &old).is_empty());
+    }
+}
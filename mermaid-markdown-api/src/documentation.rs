@@ -0,0 +1,289 @@
+use scanner_syn::contract_descriptor::{ContractInfo, FunctionInfo};
+use std::collections::BTreeSet;
+
+/// A public method of a contract, as documented.
+#[derive(Debug, Clone)]
+pub struct DocumentedMethod {
+    /// The scanned method
+    pub function: FunctionInfo,
+    /// The events the method emits, itself or through the functions it calls
+    pub events: Vec<String>,
+    /// The cross-contract calls the method makes, itself or through the functions it calls, e.g. `ext_ft::ft_transfer`
+    pub external_calls: Vec<String>,
+}
+
+impl DocumentedMethod {
+    /// Returns how the method is called: `init`, `view` or `call`.
+    pub fn kind(&self) -> &'static str {
+        method_kind(&self.function)
+    }
+}
+
+/// Returns how a public method is called: `init`, `view` or `call`.
+///
+/// # Arguments
+///
+/// * `function` - The method
+pub(crate) fn method_kind(function: &FunctionInfo) -> &'static str {
+    if function.is_init {
+        "init"
+    } else if function.is_view {
+        "view"
+    } else {
+        "call"
+    }
+}
+
+/// The documentation of a contract as a Markdown document: its diagram, a table of its public methods & a section per
+/// method.
+pub struct ContractDocumentation {
+    /// The title of the document, usually the name of the contract
+    pub title: String,
+    /// The diagram, a Mermaid.js flowchart shown in a fenced block
+    pub diagram: String,
+    /// The public methods, callbacks included, in source order
+    pub methods: Vec<DocumentedMethod>,
+}
+
+/// Escapes the characters that would end a cell of a Markdown table.
+///
+/// # Arguments
+///
+/// * `text` - The text of the cell
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Returns whether a function is a public method of the contract, callbacks included.
+///
+/// # Arguments
+///
+/// * `function` - The function
+pub(crate) fn is_public_method(function: &FunctionInfo) -> bool {
+    function.is_public
+        && !function.is_out_of_contract_scope
+        && !function.is_event
+        && !function.is_test
+        && function.external_contract.is_none()
+}
+
+/// Collects the events emitted & the cross-contract calls made by a function, following its calls to the other
+/// functions of the contract.
+///
+/// # Arguments
+///
+/// * `function` - The function
+/// * `functions` - The functions of the contract, along with their inner calls
+/// * `visited` - The names of the functions followed already
+/// * `events` - The events collected so far
+/// * `external_calls` - The cross-contract calls collected so far
+pub(crate) fn collect_effects(
+    function: &FunctionInfo,
+    functions: &[&FunctionInfo],
+    visited: &mut BTreeSet<String>,
+    events: &mut Vec<String>,
+    external_calls: &mut Vec<String>,
+) {
+    if !visited.insert(function.name.clone()) {
+        return;
+    }
+    for inner_call in function.inner_calls.iter().flatten() {
+        if inner_call.is_event {
+            if !events.contains(&inner_call.name) {
+                events.push(inner_call.name.clone());
+            }
+        } else if let Some(contract) = &inner_call.external_contract {
+            let call = format!("{}::{}", contract, inner_call.name);
+            if !external_calls.contains(&call) {
+                external_calls.push(call);
+            }
+        } else if inner_call.is_transfer {
+            if !external_calls.contains(&inner_call.name) {
+                external_calls.push(inner_call.name.clone());
+            }
+        } else if let Some(callee) = functions.iter().find(|callee| callee.name == inner_call.name) {
+            collect_effects(callee, functions, visited, events, external_calls);
+        }
+    }
+}
+
+impl ContractDocumentation {
+    /// Documents the public methods of a contract, under its diagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the document
+    /// * `diagram` - The diagram of the contract, rendered as [crate::syntax::Syntax::Mermaid]
+    /// * `contract` - The scanned contract
+    pub fn from_contract(
+        title: &str,
+        diagram: String,
+        contract: &ContractInfo,
+    ) -> ContractDocumentation {
+        let functions: Vec<&FunctionInfo> = contract.contract_metadata.iter().flat_map(|m| &m.fns).collect();
+        let mut methods: Vec<DocumentedMethod> = Vec::new();
+        for function in &functions {
+            if !is_public_method(function) || methods.iter().any(|method| method.function.name == function.name) {
+                continue;
+            }
+            let mut events: Vec<String> = Vec::new();
+            let mut external_calls: Vec<String> = Vec::new();
+            collect_effects(function, &functions, &mut BTreeSet::new(), &mut events, &mut external_calls);
+            methods.push(DocumentedMethod {
+                function: FunctionInfo {
+                    inner_calls: None,
+                    ..FunctionInfo::clone(function)
+                },
+                events,
+                external_calls,
+            });
+        }
+
+        ContractDocumentation {
+            title: title.to_string(),
+            diagram,
+            methods,
+        }
+    }
+
+    /// Returns the documentation as a Markdown document, which GitHub renders along with its diagram.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# {}\n\n```mermaid\n{}\n```\n\n## Methods\n\n",
+            self.title,
+            self.diagram.trim_end()
+        );
+        if self.methods.is_empty() {
+            markdown.push_str("The contract has no public methods.\n");
+            return markdown;
+        }
+
+        markdown.push_str("| Method | Kind | Payable | Private | Arguments | Returns |\n");
+        markdown.push_str("| --- | --- | --- | --- | --- | --- |\n");
+        for method in &self.methods {
+            let function = &method.function;
+            let arguments = function
+                .arguments
+                .iter()
+                .map(|argument| format!("`{}: {}`", argument.name, escape_cell(&argument.ty)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            markdown.push_str(&format!(
+                "| [`{0}`](#{0}) | {1} | {2} | {3} | {4} | {5} |\n",
+                function.name,
+                method.kind(),
+                if function.is_payable { "yes" } else { "no" },
+                if function.is_private_cccalls { "yes" } else { "no" },
+                arguments,
+                function
+                    .return_type
+                    .as_ref()
+                    .map(|return_type| format!("`{}`", escape_cell(return_type)))
+                    .unwrap_or_default()
+            ));
+        }
+
+        for method in &self.methods {
+            let function = &method.function;
+            markdown.push_str(&format!("\n### {}\n\n", function.name));
+            if let Some(signature) = &function.signature {
+                markdown.push_str(&format!("```rust\n{}\n```\n\n", signature));
+            }
+            if let Some(docs) = &function.docs {
+                markdown.push_str(&format!("{}\n\n", docs));
+            }
+            let mut attributes = vec![format!("**Kind:** {}", method.kind())];
+            if function.is_payable {
+                attributes.push("payable".to_string());
+            }
+            if function.is_private_cccalls {
+                attributes.push("private, only callable by the contract itself".to_string());
+            }
+            markdown.push_str(&format!("{}\n", attributes.join(", ")));
+            if !method.events.is_empty() {
+                let events: Vec<String> = method.events.iter().map(|event| format!("`{}`", event)).collect();
+                markdown.push_str(&format!("\n**Emits:** {}\n", events.join(", ")));
+            }
+            if !method.external_calls.is_empty() {
+                let calls: Vec<String> = method.external_calls.iter().map(|call| format!("`{}`", call)).collect();
+                markdown.push_str(&format!("\n**Calls:** {}\n", calls.join(", ")));
+            }
+        }
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scanner_syn::contract_descriptor::{ArgumentInfo, ContractDescriptorMeta};
+
+    #[test]
+    fn it_documents_the_public_methods() {
+        let log_withdraw = FunctionInfo {
+            name: "log_withdraw".to_string(),
+            is_public: true,
+            is_event: true,
+            ..Default::default()
+        };
+        let ft_transfer = FunctionInfo {
+            name: "ft_transfer".to_string(),
+            is_public: true,
+            is_out_of_contract_scope: true,
+            external_contract: Some("ext_ft".to_string()),
+            ..Default::default()
+        };
+        let internal_withdraw = FunctionInfo {
+            name: "internal_withdraw".to_string(),
+            inner_calls: Some(vec![log_withdraw.clone(), ft_transfer.clone()]),
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_payable: true,
+            is_mutable: true,
+            signature: Some("fn withdraw(&mut self, amount: U128) -> Promise".to_string()),
+            docs: Some("Withdraws the amount.".to_string()),
+            arguments: vec![ArgumentInfo {
+                name: "amount".to_string(),
+                ty: "U128".to_string(),
+                serializer: "json".to_string(),
+            }],
+            return_type: Some("Promise".to_string()),
+            inner_calls: Some(vec![FunctionInfo {
+                inner_calls: None,
+                ..internal_withdraw.clone()
+            }]),
+            ..Default::default()
+        };
+        let on_withdraw = FunctionInfo {
+            name: "on_withdraw".to_string(),
+            is_public: true,
+            is_private_cccalls: true,
+            ..Default::default()
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![withdraw, internal_withdraw, on_withdraw, log_withdraw],
+                ext_contract_fns: vec![ft_transfer],
+                connections: None,
+                tokens: None,
+            }],
+        };
+
+        let documentation = ContractDocumentation::from_contract("vault", "flowchart TD\n".to_string(), &contract);
+        assert_eq!(documentation.methods.len(), 2);
+        assert_eq!(documentation.methods[0].events, vec!["log_withdraw"]);
+        assert_eq!(documentation.methods[0].external_calls, vec!["ext_ft::ft_transfer"]);
+
+        let markdown = documentation.to_markdown();
+        assert!(markdown.starts_with("# vault\n\n```mermaid\nflowchart TD\n```\n\n## Methods\n\n"));
+        assert!(markdown.contains("| [`withdraw`](#withdraw) | call | yes | no | `amount: U128` | `Promise` |\n"));
+        assert!(markdown.contains("| [`on_withdraw`](#on_withdraw) | call | no | yes |  |  |\n"));
+        assert!(markdown.contains(
+            "\n### withdraw\n\n```rust\nfn withdraw(&mut self, amount: U128) -> Promise\n```\n\nWithdraws the amount.\n\n**Kind:** call, payable\n\n**Emits:** `log_withdraw`\n\n**Calls:** `ext_ft::ft_transfer`\n"
+        ));
+        assert!(markdown.contains("**Kind:** call, private, only callable by the contract itself\n"));
+    }
+}
@@ -0,0 +1,409 @@
+use crate::syntax::escape_xml;
+use scanner_syn::contract_descriptor::{ContractDescriptorMeta, ContractInfo, FunctionInfo};
+use std::collections::BTreeSet;
+use strum::IntoEnumIterator;
+
+/// How an entrypoint of a contract is called, in the order the index lists them.
+#[derive(AsRefStr, EnumIter, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[strum(serialize_all = "snake_case")]
+pub enum EntrypointKind {
+    /// A method reading the state, called without a transaction
+    View,
+    /// A method changing the state, called with a transaction
+    Call,
+    /// A method initializing the state of the contract
+    Init,
+    /// A method accepting a deposit of $NEAR
+    Payable,
+}
+
+impl EntrypointKind {
+    /// Returns the kind of a function, `None` if it isn't a public entrypoint of the contract.
+    ///
+    /// Callbacks are left out, since only the contract itself can call them.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The function
+    pub fn of(function: &FunctionInfo) -> Option<EntrypointKind> {
+        let entrypoint = function.is_public
+            && !function.is_out_of_contract_scope
+            && !function.is_private_cccalls
+            && !function.is_event
+            && !function.is_test
+            && function.external_contract.is_none();
+        if !entrypoint {
+            None
+        } else if function.is_init {
+            Some(EntrypointKind::Init)
+        } else if function.is_payable {
+            Some(EntrypointKind::Payable)
+        } else if function.is_view {
+            Some(EntrypointKind::View)
+        } else {
+            Some(EntrypointKind::Call)
+        }
+    }
+
+    /// Returns the title of the section of the index listing the entrypoints of this kind.
+    pub fn title(&self) -> &'static str {
+        match self {
+            EntrypointKind::View => "View methods",
+            EntrypointKind::Call => "Call methods",
+            EntrypointKind::Init => "Initializers",
+            EntrypointKind::Payable => "Payable methods",
+        }
+    }
+}
+
+/// A public method of a contract, which a diagram of its own can be drawn for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entrypoint {
+    /// The name of the method
+    pub name: String,
+    /// How the method is called
+    pub kind: EntrypointKind,
+    /// The signature of the method, e.g. `fn withdraw(&mut self, amount: U128) -> Promise`
+    pub signature: Option<String>,
+}
+
+/// Returns the public entrypoints of a contract, in source order, each name listed once.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+pub fn entrypoints(contract: &ContractInfo) -> Vec<Entrypoint> {
+    let mut entrypoints: Vec<Entrypoint> = Vec::new();
+    for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+        if entrypoints.iter().any(|entrypoint| entrypoint.name == function.name) {
+            continue;
+        }
+        if let Some(kind) = EntrypointKind::of(function) {
+            entrypoints.push(Entrypoint {
+                name: function.name.clone(),
+                kind,
+                signature: function.signature.clone(),
+            });
+        }
+    }
+    entrypoints
+}
+
+/// Replaces the inner calls of a function by the functions of the contract they resolve to, recursively, so everything
+/// reachable from it hangs off of it. A function reached more than once is only expanded the first time, which keeps
+/// every call in the tree once & stops at recursive calls.
+///
+/// # Arguments
+///
+/// * `function` - The function to expand
+/// * `functions` - The functions of the contract
+/// * `expanded` - The names of the functions expanded so far
+/// * `depth` - How many levels of calls are followed, all of them if `None`
+/// * `keep` - Whether a call to a function is followed, given its name
+pub(crate) fn expand<F: Fn(&str) -> bool>(
+    function: FunctionInfo,
+    functions: &[&FunctionInfo],
+    expanded: &mut BTreeSet<String>,
+    depth: Option<usize>,
+    keep: &F,
+) -> FunctionInfo {
+    if depth == Some(0) || !expanded.insert(function.name.clone()) {
+        return FunctionInfo {
+            inner_calls: None,
+            ..function
+        };
+    }
+    let inner_calls = function.inner_calls.map(|inner_calls| {
+        inner_calls
+            .into_iter()
+            .filter(|inner_call| keep(&inner_call.name))
+            .map(|inner_call| {
+                // The inner calls only carry the calls they make themselves once they're looked up in the contract
+                let resolved = functions
+                    .iter()
+                    .find(|function| function.name == inner_call.name && inner_call.external_contract.is_none())
+                    .map(|function| FunctionInfo {
+                        call_count: inner_call.call_count,
+                        ..(*function).clone()
+                    })
+                    .unwrap_or(inner_call);
+                expand(resolved, functions, expanded, depth.map(|depth| depth - 1), keep)
+            })
+            .collect::<Vec<_>>()
+    });
+    FunctionInfo {
+        inner_calls: inner_calls.filter(|inner_calls| !inner_calls.is_empty()),
+        ..function
+    }
+}
+
+/// Returns the part of a contract reachable from one of its methods: the method with every function it calls, directly
+/// or through other functions, nested in its inner calls. `None` if the contract has no function of this name.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+/// * `name` - The name of the method
+pub fn reachable_from(
+    contract: &ContractInfo,
+    name: &str,
+) -> Option<ContractInfo> {
+    reachable_within(contract, name, None)
+}
+
+/// Returns the part of a contract reachable from one of its methods in at most `depth` calls, like [reachable_from].
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+/// * `name` - The name of the method
+/// * `depth` - How many levels of calls are followed, all of them if `None`
+pub fn reachable_within(
+    contract: &ContractInfo,
+    name: &str,
+    depth: Option<usize>,
+) -> Option<ContractInfo> {
+    let functions: Vec<&FunctionInfo> = contract.contract_metadata.iter().flat_map(|m| &m.fns).collect();
+    let function = functions.iter().find(|function| function.name == name)?;
+    let mut expanded = BTreeSet::new();
+    let function = expand((*function).clone(), &functions, &mut expanded, depth, &|_| true);
+    Some(nested_contract(contract, vec![function]))
+}
+
+/// Returns a contract made of functions whose calls are nested in their inner calls, along with the external methods
+/// they reach.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract the functions come from
+/// * `fns` - The functions, expanded by [expand]
+pub(crate) fn nested_contract(
+    contract: &ContractInfo,
+    fns: Vec<FunctionInfo>,
+) -> ContractInfo {
+    let mut reached = BTreeSet::new();
+    for function in &fns {
+        collect_names(function, &mut reached);
+    }
+    let ext_contract_fns = contract
+        .contract_metadata
+        .iter()
+        .flat_map(|m| &m.ext_contract_fns)
+        .filter(|function| reached.contains(&function.name))
+        .cloned()
+        .collect();
+    ContractInfo {
+        contract_metadata: vec![ContractDescriptorMeta {
+            fns,
+            ext_contract_fns,
+            connections: None,
+            tokens: None,
+        }],
+    }
+}
+
+/// Collects the names of a function & of every function nested in its inner calls.
+///
+/// # Arguments
+///
+/// * `function` - The function
+/// * `names` - The names collected so far
+fn collect_names(
+    function: &FunctionInfo,
+    names: &mut BTreeSet<String>,
+) {
+    names.insert(function.name.clone());
+    for inner_call in function.inner_calls.iter().flatten() {
+        collect_names(inner_call, names);
+    }
+}
+
+/// The diagram written for an entrypoint, as listed in an [EntrypointIndex].
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    /// The entrypoint drawn
+    pub entrypoint: Entrypoint,
+    /// The path of its diagram, relative to the index
+    pub path: String,
+}
+
+/// The index page linking the diagrams of the entrypoints of a contract, grouped by [EntrypointKind].
+#[derive(Debug, Clone)]
+pub struct EntrypointIndex {
+    /// The name of the contract
+    pub title: String,
+    /// The diagrams of the entrypoints
+    pub entries: Vec<IndexEntry>,
+}
+
+impl EntrypointIndex {
+    /// Returns the entries of every kind that has any, in the order of [EntrypointKind].
+    fn sections(&self) -> Vec<(EntrypointKind, Vec<&IndexEntry>)> {
+        EntrypointKind::iter()
+            .map(|kind| {
+                let entries: Vec<&IndexEntry> = self.entries.iter().filter(|entry| entry.entrypoint.kind == kind).collect();
+                (kind, entries)
+            })
+            .filter(|(_, entries)| !entries.is_empty())
+            .collect()
+    }
+
+    /// Returns the index as a Markdown document, a list of links per kind of entrypoint.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\nThe diagram of every public method of the contract, with everything it calls.\n", self.title);
+        for (kind, entries) in self.sections() {
+            markdown.push_str(&format!("\n## {}\n\n", kind.title()));
+            for entry in entries {
+                markdown.push_str(&format!("- [`{}`]({})", entry.entrypoint.name, entry.path.replace(' ', "%20")));
+                if let Some(signature) = &entry.entrypoint.signature {
+                    markdown.push_str(&format!(" — `{}`", signature));
+                }
+                markdown.push('\n');
+            }
+        }
+        markdown
+    }
+
+    /// Returns the index as an HTML page, a list of links per kind of entrypoint. SVG diagrams are shown in the page too.
+    pub fn to_html(&self) -> String {
+        let title = escape_xml(&self.title);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2em; }}\n\
+             code {{ background: #f4f4f4; padding: 0 .2em; }}\n\
+             li {{ margin-bottom: 1em; }}\n\
+             img {{ display: block; max-width: 100%; margin-top: .5em; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = title
+        );
+        for (kind, entries) in self.sections() {
+            html.push_str(&format!("<h2>{}</h2>\n<ul>\n", kind.title()));
+            for entry in entries {
+                let path = escape_xml(&entry.path);
+                html.push_str(&format!("<li><a href=\"{}\"><code>{}</code></a>", path, escape_xml(&entry.entrypoint.name)));
+                if let Some(signature) = &entry.entrypoint.signature {
+                    html.push_str(&format!(" <code>{}</code>", escape_xml(signature)));
+                }
+                if entry.path.ends_with(".svg") {
+                    html.push_str(&format!("<img src=\"{}\" alt=\"Diagram of {}\" loading=\"lazy\">", path, escape_xml(&entry.entrypoint.name)));
+                }
+                html.push_str("</li>\n");
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract() -> ContractInfo {
+        let log = FunctionInfo {
+            name: "log".to_string(),
+            ..Default::default()
+        };
+        let internal_transfer = FunctionInfo {
+            name: "internal_transfer".to_string(),
+            inner_calls: Some(vec![FunctionInfo {
+                call_count: 1,
+                ..log.clone()
+            }]),
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_mutable: true,
+            signature: Some("fn withdraw(&mut self, amount: U128)".to_string()),
+            inner_calls: Some(vec![FunctionInfo {
+                call_count: 2,
+                // As resolved by the scanner, without the calls it makes itself
+                inner_calls: None,
+                ..internal_transfer.clone()
+            }]),
+            ..Default::default()
+        };
+        let balance = FunctionInfo {
+            name: "balance".to_string(),
+            is_public: true,
+            is_view: true,
+            ..Default::default()
+        };
+        let on_withdraw = FunctionInfo {
+            name: "on_withdraw".to_string(),
+            is_public: true,
+            is_private_cccalls: true,
+            ..Default::default()
+        };
+        let new = FunctionInfo {
+            name: "new".to_string(),
+            is_public: true,
+            is_init: true,
+            ..Default::default()
+        };
+        ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![new, withdraw, balance, on_withdraw, internal_transfer, log],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn it_lists_the_public_entrypoints_by_kind() {
+        let found: Vec<(String, EntrypointKind)> = entrypoints(&contract())
+            .into_iter()
+            .map(|entrypoint| (entrypoint.name, entrypoint.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("new".to_string(), EntrypointKind::Init),
+                ("withdraw".to_string(), EntrypointKind::Call),
+                ("balance".to_string(), EntrypointKind::View),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_nests_everything_reachable_from_an_entrypoint() {
+        let reachable = reachable_from(&contract(), "withdraw").unwrap();
+        let fns = &reachable.contract_metadata[0].fns;
+        assert_eq!(fns.len(), 1);
+        let internal_transfer = &fns[0].inner_calls.as_ref().unwrap()[0];
+        assert_eq!(internal_transfer.name, "internal_transfer");
+        assert_eq!(internal_transfer.call_count, 2);
+        assert_eq!(internal_transfer.inner_calls.as_ref().unwrap()[0].name, "log");
+        assert!(reachable_from(&contract(), "missing").is_none());
+    }
+
+    #[test]
+    fn it_writes_an_index_grouped_by_kind() {
+        let index = EntrypointIndex {
+            title: "vault".to_string(),
+            entries: entrypoints(&contract())
+                .into_iter()
+                .map(|entrypoint| IndexEntry {
+                    path: format!("{}.svg", entrypoint.name),
+                    entrypoint,
+                })
+                .collect(),
+        };
+        let markdown = index.to_markdown();
+        assert!(markdown.starts_with("# vault\n"));
+        let view = markdown.find("## View methods").unwrap();
+        let call = markdown.find("## Call methods").unwrap();
+        let init = markdown.find("## Initializers").unwrap();
+        assert!(view < call && call < init);
+        assert!(!markdown.contains("## Payable methods"));
+        assert!(markdown.contains("- [`withdraw`](withdraw.svg) — `fn withdraw(&mut self, amount: U128)`\n"));
+
+        let html = index.to_html();
+        assert!(html.contains("<a href=\"withdraw.svg\"><code>withdraw</code></a> <code>fn withdraw(&amp;mut self, amount: U128)</code><img src=\"withdraw.svg\""));
+    }
+}
@@ -0,0 +1,397 @@
+use crate::objects::connection::ConnectionType;
+use crate::objects::node::{ActionType, ScopeType};
+use crate::syntax::escape_xml;
+use scanner_syn::contract_descriptor::{ContractInfo, FunctionInfo};
+use serde_json::{json, Map, Value};
+use strum::EnumProperty;
+
+/// The data keys of the nodes, along with their GraphML type, in the order they are declared.
+const NODE_KEYS: [(&str, &str); 24] = [
+    ("name", "string"),
+    ("class", "string"),
+    ("scope", "string"),
+    ("action", "string"),
+    ("signature", "string"),
+    ("file", "string"),
+    ("line_start", "int"),
+    ("line_end", "int"),
+    ("module_path", "string"),
+    ("impl_type", "string"),
+    ("trait_name", "string"),
+    ("external_contract", "string"),
+    ("is_public", "boolean"),
+    ("is_trait_impl", "boolean"),
+    ("is_init", "boolean"),
+    ("is_test", "boolean"),
+    ("is_payable", "boolean"),
+    ("is_view", "boolean"),
+    ("is_mutable", "boolean"),
+    ("is_process", "boolean"),
+    ("is_private_cccalls", "boolean"),
+    ("is_out_of_contract_scope", "boolean"),
+    ("is_event", "boolean"),
+    ("is_transfer", "boolean"),
+];
+
+/// The data keys of the edges, along with their GraphML type, in the order they are declared.
+const EDGE_KEYS: [(&str, &str); 3] = [
+    ("connection_type", "string"),
+    ("label", "string"),
+    ("call_count", "int"),
+];
+
+/// A call from one function to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdge {
+    /// The name of the calling function
+    pub source: String,
+    /// The name of the called function
+    pub target: String,
+    /// The kind of call
+    pub connection_type: ConnectionType,
+    /// The number of call sites
+    pub call_count: usize,
+}
+
+/// The resolved call graph of a contract, exported for graph tools rather than rendered as a diagram.
+///
+/// Every function is a node carrying all of its [FunctionInfo] flags, its signature & its source location, & every call is an
+/// edge carrying its [ConnectionType]. Functions are identified by name, like the nodes of the diagrams.
+pub struct ContractGraph {
+    /// The functions, including the external methods & transfers that are called
+    pub nodes: Vec<FunctionInfo>,
+    /// The calls between the functions
+    pub edges: Vec<GraphEdge>,
+}
+
+impl ContractGraph {
+    /// Collects the functions & the calls between them from a scanned contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `contract` - The scanned contract
+    pub fn from_contract(contract: &ContractInfo) -> ContractGraph {
+        let mut nodes: Vec<FunctionInfo> = Vec::new();
+        let mut edges: Vec<GraphEdge> = Vec::new();
+
+        let functions = contract.contract_metadata.iter().flat_map(|m| &m.fns);
+        for function in functions.clone() {
+            if !nodes.iter().any(|node| node.name == function.name) {
+                nodes.push(FunctionInfo {
+                    inner_calls: None,
+                    ..function.clone()
+                });
+            }
+        }
+
+        for function in functions {
+            ContractGraph::add_calls(function, &mut nodes, &mut edges);
+        }
+
+        ContractGraph { nodes, edges }
+    }
+
+    /// Adds the calls a function makes, along with the calls nested in its inner calls, e.g. the ones of a contract
+    /// narrowed down to an entrypoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The calling function
+    /// * `nodes` - The nodes collected so far
+    /// * `edges` - The edges collected so far
+    fn add_calls(
+        function: &FunctionInfo,
+        nodes: &mut Vec<FunctionInfo>,
+        edges: &mut Vec<GraphEdge>,
+    ) {
+        for inner_call in function.inner_calls.iter().flatten() {
+            // External methods & transfers are only known through the calls to them
+            if !nodes.iter().any(|node| node.name == inner_call.name) {
+                nodes.push(FunctionInfo {
+                    inner_calls: None,
+                    call_count: 0,
+                    ..inner_call.clone()
+                });
+            }
+            let duplicate = edges
+                .iter()
+                .any(|edge| edge.source == function.name && edge.target == inner_call.name);
+            if !duplicate {
+                edges.push(GraphEdge {
+                    source: function.name.clone(),
+                    target: inner_call.name.clone(),
+                    connection_type: inner_call.clone().into(),
+                    call_count: inner_call.call_count,
+                });
+            }
+            ContractGraph::add_calls(inner_call, nodes, edges);
+        }
+    }
+
+    /// Returns the data of a node keyed as in [NODE_KEYS], unknown values are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The function represented by the node
+    fn node_data(function: &FunctionInfo) -> Map<String, Value> {
+        let scope: ScopeType = function.clone().into();
+        let action: ActionType = function.clone().into();
+        let data = json!({
+            "name": function.name,
+            "class": format!("{}-{}", scope.as_ref(), action.as_ref()),
+            "scope": scope.as_ref(),
+            "action": action.as_ref(),
+            "signature": function.signature,
+            "file": function.file_path.as_ref().map(|path| {
+                path.iter()
+                    .map(|component| component.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            }),
+            "line_start": function.line_span.map(|(start, _)| start),
+            "line_end": function.line_span.map(|(_, end)| end),
+            "module_path": if function.module_path.is_empty() {
+                None
+            } else {
+                Some(function.module_path.join("::"))
+            },
+            "impl_type": function.impl_type,
+            "trait_name": function.trait_name,
+            "external_contract": function.external_contract,
+            "is_public": function.is_public,
+            "is_trait_impl": function.is_trait_impl,
+            "is_init": function.is_init,
+            "is_test": function.is_test,
+            "is_payable": function.is_payable,
+            "is_view": function.is_view,
+            "is_mutable": function.is_mutable,
+            "is_process": function.is_process,
+            "is_private_cccalls": function.is_private_cccalls,
+            "is_out_of_contract_scope": function.is_out_of_contract_scope,
+            "is_event": function.is_event,
+            "is_transfer": function.is_transfer,
+        });
+
+        let mut data = match data {
+            Value::Object(data) => data,
+            _ => unreachable!(),
+        };
+        data.retain(|_, value| !value.is_null());
+        data
+    }
+
+    /// Returns the data of an edge keyed as in [EDGE_KEYS].
+    ///
+    /// # Arguments
+    ///
+    /// * `edge` - The call represented by the edge
+    fn edge_data(edge: &GraphEdge) -> Map<String, Value> {
+        let mut data = Map::new();
+        data.insert(
+            "connection_type".to_string(),
+            edge.connection_type.as_ref().into(),
+        );
+        data.insert(
+            "label".to_string(),
+            edge.connection_type.get_str("Label").unwrap().into(),
+        );
+        data.insert("call_count".to_string(), edge.call_count.into());
+        data
+    }
+
+    /// Returns the graph as a [GraphML](http://graphml.graphdrawing.org) document, e.g. for yEd or Gephi.
+    pub fn to_graphml(&self) -> String {
+        let mut graphml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        );
+
+        // Declare every data key up front, as GraphML requires
+        for (keys, domain) in [(&NODE_KEYS[..], "node"), (&EDGE_KEYS[..], "edge")] {
+            for (key, key_type) in keys {
+                graphml.push_str(&format!(
+                    "\t<key id=\"{domain}_{key}\" for=\"{domain}\" attr.name=\"{key}\" attr.type=\"{key_type}\"/>\n",
+                    domain = domain,
+                    key = key,
+                    key_type = key_type
+                ));
+            }
+        }
+
+        graphml.push_str("\t<graph id=\"contract\" edgedefault=\"directed\">\n");
+        for node in &self.nodes {
+            graphml.push_str(&format!("\t\t<node id=\"{}\">\n", escape_xml(&node.name)));
+            push_graphml_data(&mut graphml, "node", &ContractGraph::node_data(node));
+            graphml.push_str("\t\t</node>\n");
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            graphml.push_str(&format!(
+                "\t\t<edge id=\"e{}\" source=\"{}\" target=\"{}\">\n",
+                index,
+                escape_xml(&edge.source),
+                escape_xml(&edge.target)
+            ));
+            push_graphml_data(&mut graphml, "edge", &ContractGraph::edge_data(edge));
+            graphml.push_str("\t\t</edge>\n");
+        }
+        graphml.push_str("\t</graph>\n</graphml>\n");
+
+        graphml
+    }
+
+    /// Returns the graph as [Cytoscape.js elements](https://js.cytoscape.org/#notation/elements-json), i.e. an object of
+    /// `nodes` & `edges` whose `data` holds the same keys as the GraphML export. The nodes are also given their class (e.g.
+    /// `Public-View`) so they can be styled like the diagrams.
+    pub fn to_cytoscape(&self) -> String {
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut data = ContractGraph::node_data(node);
+                let class = data.get("class").cloned().unwrap_or_default();
+                data.insert("id".to_string(), node.name.clone().into());
+                json!({ "data": data, "classes": class })
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                let mut data = ContractGraph::edge_data(edge);
+                data.insert(
+                    "id".to_string(),
+                    format!("{}->{}", edge.source, edge.target).into(),
+                );
+                data.insert("source".to_string(), edge.source.clone().into());
+                data.insert("target".to_string(), edge.target.clone().into());
+                json!({ "data": data })
+            })
+            .collect();
+
+        let mut cytoscape =
+            serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges })).unwrap();
+        cytoscape.push('\n');
+        cytoscape
+    }
+}
+
+/// Appends a `<data>` element for every value.
+///
+/// # Arguments
+///
+/// * `graphml` - The document being written
+/// * `domain` - Either `node` or `edge`, the prefix of the keys
+/// * `data` - The values keyed by data key
+fn push_graphml_data(
+    graphml: &mut String,
+    domain: &str,
+    data: &Map<String, Value>,
+) {
+    for (key, value) in data {
+        let value = match value {
+            Value::String(value) => escape_xml(value),
+            value => value.to_string(),
+        };
+        graphml.push_str(&format!(
+            "\t\t\t<data key=\"{}_{}\">{}</data>\n",
+            domain, key, value
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scanner_syn::contract_descriptor::ContractDescriptorMeta;
+    use std::path::PathBuf;
+
+    fn contract() -> ContractInfo {
+        let ft_transfer = FunctionInfo {
+            name: "ft_transfer".to_string(),
+            is_public: true,
+            is_mutable: true,
+            external_contract: Some("ext_ft".to_string()),
+            signature: Some("fn ft_transfer(&mut self, receiver_id: AccountId)".to_string()),
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_mutable: true,
+            signature: Some("fn withdraw(&mut self, amount: U128) -> Promise".to_string()),
+            file_path: Some(PathBuf::from("src").join("lib.rs")),
+            line_span: Some((10, 20)),
+            impl_type: Some("Contract".to_string()),
+            inner_calls: Some(vec![FunctionInfo {
+                call_count: 2,
+                ..ft_transfer.clone()
+            }]),
+            ..Default::default()
+        };
+
+        ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![withdraw],
+                ext_contract_fns: vec![ft_transfer],
+                connections: None,
+                tokens: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn it_collects_the_called_functions_and_the_calls() {
+        let graph = ContractGraph::from_contract(&contract());
+
+        let names: Vec<&str> = graph.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, vec!["withdraw", "ft_transfer"]);
+        assert_eq!(
+            graph.edges,
+            vec![GraphEdge {
+                source: "withdraw".to_string(),
+                target: "ft_transfer".to_string(),
+                connection_type: ConnectionType::CrossContractConnection,
+                call_count: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn it_exports_graphml_with_node_and_edge_data() {
+        let graphml = ContractGraph::from_contract(&contract()).to_graphml();
+
+        assert!(graphml.contains(
+            "<key id=\"node_is_payable\" for=\"node\" attr.name=\"is_payable\" attr.type=\"boolean\"/>"
+        ));
+        assert!(graphml.contains("<node id=\"withdraw\">"));
+        assert!(graphml.contains(
+            "<data key=\"node_signature\">fn withdraw(&amp;mut self, amount: U128) -&gt; Promise</data>"
+        ));
+        assert!(graphml.contains("<data key=\"node_file\">src/lib.rs</data>"));
+        assert!(graphml.contains("<data key=\"node_line_start\">10</data>"));
+        assert!(graphml.contains("<data key=\"node_class\">External-Mutation</data>"));
+        assert!(graphml.contains("<edge id=\"e0\" source=\"withdraw\" target=\"ft_transfer\">"));
+        assert!(graphml.contains(
+            "<data key=\"edge_connection_type\">CrossContractConnection</data>"
+        ));
+        assert!(graphml.contains("<data key=\"edge_call_count\">2</data>"));
+    }
+
+    #[test]
+    fn it_exports_cytoscape_elements() {
+        let cytoscape: Value =
+            serde_json::from_str(&ContractGraph::from_contract(&contract()).to_cytoscape()).unwrap();
+
+        let withdraw = &cytoscape["nodes"][0];
+        assert_eq!(withdraw["classes"], "Public-Mutation");
+        assert_eq!(withdraw["data"]["id"], "withdraw");
+        assert_eq!(withdraw["data"]["is_mutable"], true);
+        assert_eq!(withdraw["data"]["line_end"], 20);
+        assert_eq!(withdraw["data"]["impl_type"], "Contract");
+        assert!(withdraw["data"].get("trait_name").is_none());
+
+        let edge = &cytoscape["edges"][0];
+        assert_eq!(edge["data"]["source"], "withdraw");
+        assert_eq!(edge["data"]["target"], "ft_transfer");
+        assert_eq!(edge["data"]["label"], "cross-contract");
+    }
+}
@@ -0,0 +1,295 @@
+use crate::entrypoints::EntrypointKind;
+use crate::graph_export::ContractGraph;
+use crate::objects::node::{ActionType, ScopeType};
+use crate::syntax::escape_xml;
+use scanner_syn::contract_descriptor::{ContractInfo, FunctionInfo};
+
+/// The styles of the report, the diagram on the left & the panel of methods on the right.
+const STYLE: &str = "\
+body { margin: 0; font-family: sans-serif; display: flex; height: 100vh; }
+main { flex: 1; overflow: auto; padding: 1em; }
+main svg { max-width: 100%; height: auto; }
+aside { width: 28em; overflow: auto; border-left: 1px solid #ddd; padding: 1em; box-sizing: border-box; }
+h1 { font-size: 1.3em; margin-top: 0; }
+input[type=search] { width: 100%; padding: .4em; box-sizing: border-box; margin-bottom: 1em; }
+ul.methods { list-style: none; padding: 0; margin: 0; }
+ul.methods > li { border: 1px solid #ddd; border-radius: 4px; padding: .5em; margin-bottom: .5em; cursor: pointer; }
+ul.methods > li.selected { border-color: #d9534f; background: #fff5f5; }
+.kind { font-size: .8em; background: #eee; border-radius: 3px; padding: 0 .3em; margin-left: .3em; }
+pre { white-space: pre-wrap; background: #f4f4f4; padding: .3em; margin: .3em 0; }
+.docs { white-space: pre-wrap; margin: .3em 0; }
+dl { margin: .3em 0 0; font-size: .9em; }
+dt { font-weight: bold; }
+dd { margin-left: 1em; }
+dd a { margin-right: .5em; }
+svg .dimmed { opacity: .15; }
+svg g.node { cursor: pointer; }
+svg g.node.selected > :first-child { stroke: #d9534f; stroke-width: 3; }
+";
+
+/// Narrows the panel down to the methods matching the search & highlights the neighbourhood of the selected method.
+const SCRIPT: &str = "\
+const methods = Array.from(document.querySelectorAll('ul.methods > li'));
+const byName = new Map(methods.map(method => [method.dataset.name, method]));
+const neighbours = name => {
+  const method = byName.get(name);
+  const names = new Set([name]);
+  if (method) {
+    method.dataset.callers.split(' ').concat(method.dataset.callees.split(' ')).filter(n => n).forEach(n => names.add(n));
+  }
+  return names;
+};
+const select = name => {
+  const names = neighbours(name);
+  methods.forEach(method => method.classList.toggle('selected', method.dataset.name === name));
+  document.querySelectorAll('main svg g.node').forEach(node => {
+    node.classList.toggle('dimmed', !names.has(node.id));
+    node.classList.toggle('selected', node.id === name);
+  });
+  document.querySelectorAll('main svg path.edge').forEach(edge => {
+    const incident = edge.dataset.source === name || edge.dataset.target === name;
+    edge.classList.toggle('dimmed', !incident);
+  });
+  document.querySelectorAll('main svg g.edge-label, main svg g.cluster').forEach(other => other.classList.add('dimmed'));
+  const method = byName.get(name);
+  if (method) {
+    method.scrollIntoView({ block: 'nearest' });
+  }
+};
+const clear = () => {
+  methods.forEach(method => method.classList.remove('selected'));
+  document.querySelectorAll('main svg .dimmed, main svg .selected').forEach(element => element.classList.remove('dimmed', 'selected'));
+};
+methods.forEach(method => method.addEventListener('click', event => {
+  const link = event.target.closest('a[data-method]');
+  select(link ? link.dataset.method : method.dataset.name);
+  if (link) {
+    event.preventDefault();
+  }
+}));
+document.querySelectorAll('main svg g.node').forEach(node => node.addEventListener('click', event => {
+  event.preventDefault();
+  event.stopPropagation();
+  select(node.id);
+}));
+document.querySelector('main').addEventListener('click', event => {
+  if (!event.target.closest('g.node')) {
+    clear();
+  }
+});
+document.getElementById('search').addEventListener('input', event => {
+  const query = event.target.value.trim().toLowerCase();
+  methods.forEach(method => {
+    method.hidden = query !== '' && !method.textContent.toLowerCase().includes(query);
+  });
+});
+";
+
+/// A method of the contract, as described in the side panel of the report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportMethod {
+    /// The name of the method, the id of its node in the diagram
+    pub name: String,
+    /// How the method is classified, e.g. `view`, `callback` or `private`
+    pub classification: String,
+    /// The class of its node in the diagram, e.g. `Public-View`
+    pub class: String,
+    /// The signature of the method
+    pub signature: Option<String>,
+    /// The doc comment of the method
+    pub docs: Option<String>,
+    /// The names of the methods calling this one
+    pub callers: Vec<String>,
+    /// The names of the methods this one calls
+    pub callees: Vec<String>,
+}
+
+/// A single offline HTML page showing the diagram of a contract next to a searchable list of its methods.
+///
+/// Clicking a method, in the list or in the diagram, highlights its callers & callees.
+pub struct HtmlReport {
+    /// The title of the page, usually the name of the contract
+    pub title: String,
+    /// The diagram, an SVG image embedded as is
+    pub svg: String,
+    /// The methods, in source order
+    pub methods: Vec<ReportMethod>,
+}
+
+/// Returns how a function is classified in the report: the kind of entrypoint it is, or else the role it plays.
+///
+/// # Arguments
+///
+/// * `function` - The function
+fn classification(function: &FunctionInfo) -> String {
+    if function.external_contract.is_some() {
+        "external".to_string()
+    } else if function.is_transfer {
+        "transfer".to_string()
+    } else if function.is_event {
+        "event".to_string()
+    } else if function.is_private_cccalls {
+        "callback".to_string()
+    } else if let Some(kind) = EntrypointKind::of(function) {
+        kind.as_ref().to_string()
+    } else if function.is_out_of_contract_scope {
+        "out of scope".to_string()
+    } else {
+        "private".to_string()
+    }
+}
+
+impl HtmlReport {
+    /// Describes the methods of a contract, along with the calls between them, next to its rendered diagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the page
+    /// * `svg` - The diagram of the contract, rendered as [crate::syntax::Syntax::Svg]
+    /// * `contract` - The scanned contract
+    pub fn from_contract(
+        title: &str,
+        svg: String,
+        contract: &ContractInfo,
+    ) -> HtmlReport {
+        let graph = ContractGraph::from_contract(contract);
+        let methods = graph
+            .nodes
+            .iter()
+            .map(|function| {
+                let scope: ScopeType = function.clone().into();
+                let action: ActionType = function.clone().into();
+                let mut callers: Vec<String> = Vec::new();
+                let mut callees: Vec<String> = Vec::new();
+                for edge in &graph.edges {
+                    if edge.target == function.name && !callers.contains(&edge.source) {
+                        callers.push(edge.source.clone());
+                    }
+                    if edge.source == function.name && !callees.contains(&edge.target) {
+                        callees.push(edge.target.clone());
+                    }
+                }
+                ReportMethod {
+                    name: function.name.clone(),
+                    classification: classification(function),
+                    class: format!("{}-{}", scope.as_ref(), action.as_ref()),
+                    signature: function.signature.clone(),
+                    docs: function.docs.clone(),
+                    callers,
+                    callees,
+                }
+            })
+            .collect();
+
+        HtmlReport {
+            title: title.to_string(),
+            svg,
+            methods,
+        }
+    }
+
+    /// Returns the links selecting each of the methods named.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the methods
+    fn method_links(names: &[String]) -> String {
+        names
+            .iter()
+            .map(|name| format!("<a href=\"#\" data-method=\"{0}\"><code>{0}</code></a>", escape_xml(name)))
+            .collect()
+    }
+
+    /// Returns the report as an HTML page, which needs nothing but a browser to be viewed.
+    pub fn to_html(&self) -> String {
+        let title = escape_xml(&self.title);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{style}</style>\n</head>\n<body>\n<main>\n{svg}</main>\n<aside>\n<h1>{title}</h1>\n<input type=\"search\" id=\"search\" placeholder=\"Search the methods\">\n<ul class=\"methods\">\n",
+            title = title,
+            style = STYLE,
+            svg = self.svg,
+        );
+        for method in &self.methods {
+            let name = escape_xml(&method.name);
+            html.push_str(&format!(
+                "<li data-name=\"{}\" data-callers=\"{}\" data-callees=\"{}\" class=\"{}\">\n<code>{}</code><span class=\"kind\">{}</span>\n",
+                name,
+                escape_xml(&method.callers.join(" ")),
+                escape_xml(&method.callees.join(" ")),
+                escape_xml(&method.class),
+                name,
+                escape_xml(&method.classification)
+            ));
+            if let Some(signature) = &method.signature {
+                html.push_str(&format!("<pre>{}</pre>\n", escape_xml(signature)));
+            }
+            if let Some(docs) = &method.docs {
+                html.push_str(&format!("<p class=\"docs\">{}</p>\n", escape_xml(docs)));
+            }
+            if !method.callers.is_empty() || !method.callees.is_empty() {
+                html.push_str("<dl>\n");
+                if !method.callers.is_empty() {
+                    html.push_str(&format!("<dt>Callers</dt><dd>{}</dd>\n", HtmlReport::method_links(&method.callers)));
+                }
+                if !method.callees.is_empty() {
+                    html.push_str(&format!("<dt>Callees</dt><dd>{}</dd>\n", HtmlReport::method_links(&method.callees)));
+                }
+                html.push_str("</dl>\n");
+            }
+            html.push_str("</li>\n");
+        }
+        html.push_str(&format!("</ul>\n</aside>\n<script>\n{}</script>\n</body>\n</html>\n", SCRIPT));
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scanner_syn::contract_descriptor::ContractDescriptorMeta;
+
+    #[test]
+    fn it_lists_the_methods_with_their_callers_and_callees() {
+        let assert_owner = FunctionInfo {
+            name: "assert_owner".to_string(),
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_mutable: true,
+            signature: Some("fn withdraw(&mut self, amount: U128)".to_string()),
+            docs: Some("Sends the <amount> back.".to_string()),
+            inner_calls: Some(vec![FunctionInfo {
+                call_count: 1,
+                ..assert_owner.clone()
+            }]),
+            ..Default::default()
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![withdraw, assert_owner],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        };
+
+        let report = HtmlReport::from_contract("vault", "<svg></svg>\n".to_string(), &contract);
+        assert_eq!(report.methods.len(), 2);
+        assert_eq!(report.methods[0].classification, "call");
+        assert_eq!(report.methods[0].class, "Public-Mutation");
+        assert_eq!(report.methods[0].callees, vec!["assert_owner"]);
+        assert_eq!(report.methods[1].classification, "private");
+        assert_eq!(report.methods[1].callers, vec!["withdraw"]);
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<main>\n<svg></svg>\n</main>"));
+        assert!(html.contains("<li data-name=\"withdraw\" data-callers=\"\" data-callees=\"assert_owner\" class=\"Public-Mutation\">"));
+        assert!(html.contains("<p class=\"docs\">Sends the &lt;amount&gt; back.</p>"));
+        assert!(html.contains("<dt>Callers</dt><dd><a href=\"#\" data-method=\"withdraw\"><code>withdraw</code></a></dd>"));
+        // Everything is inlined, so the page works offline
+        assert!(!html.contains("src="));
+    }
+}
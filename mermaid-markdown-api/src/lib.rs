@@ -1,7 +1,16 @@
+pub mod api_diff;
+pub mod documentation;
+pub mod entrypoints;
+pub mod graph_export;
+pub mod html_report;
+pub mod lint;
 pub mod md_api;
 pub mod objects;
 pub mod scanner_pipeline;
+pub mod scope;
+pub mod structurizr;
 pub mod syntax;
+pub mod theme;
 extern crate strum;
 #[macro_use]
 extern crate strum_macros;
@@ -0,0 +1,178 @@
+use crate::graph_export::ContractGraph;
+use scanner_syn::contract_descriptor::{ContractInfo, FunctionInfo};
+use std::fmt;
+use std::path::PathBuf;
+
+/// The problems looked for in a scanned contract.
+#[derive(AsRefStr, EnumString, EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Rule {
+    /// A private helper of the contract which no function calls
+    UnusedHelper,
+    /// A `#[private]` method, meant as the callback of a cross-contract call, which no function schedules
+    UncalledCallback,
+    /// A view method marked `#[payable]`, which can't be given a deposit
+    PayableView,
+}
+
+/// A problem found in a contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The rule broken
+    pub rule: Rule,
+    /// The name of the function breaking it
+    pub function: String,
+    /// The source file of the function, relative to the scanned crate root
+    pub file_path: Option<PathBuf>,
+    /// The first line of the function in its source file
+    pub line: Option<usize>,
+    /// What is wrong, for people
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "warning[{}]", self.rule.as_ref())?;
+        if let Some(file_path) = &self.file_path {
+            write!(f, " {}", file_path.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Returns the problem a function has under a rule, if any.
+///
+/// # Arguments
+///
+/// * `rule` - The rule to check
+/// * `function` - The function
+/// * `called` - Whether any function calls it
+fn check(
+    rule: Rule,
+    function: &FunctionInfo,
+    called: bool,
+) -> Option<String> {
+    match rule {
+        Rule::UnusedHelper => {
+            let helper = !function.is_public
+                && !function.is_trait_impl
+                && !function.is_event
+                && !function.is_test
+                && !function.is_out_of_contract_scope;
+            (helper && !called).then(|| format!("`{}` is private and never called", function.name))
+        }
+        // Initializers are often kept `#[private]` so only the contract account can run them
+        Rule::UncalledCallback => (function.is_private_cccalls && !function.is_init && !called).then(|| {
+            format!(
+                "`{}` is `#[private]` but no cross-contract call schedules it as a callback",
+                function.name
+            )
+        }),
+        Rule::PayableView => (function.is_payable && function.is_view)
+            .then(|| format!("`{}` is `#[payable]` but doesn't modify the state", function.name)),
+    }
+}
+
+/// Checks the functions of a contract against the rules, returning the problems found in source order.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+/// * `rules` - The rules to check
+pub fn lint(
+    contract: &ContractInfo,
+    rules: &[Rule],
+) -> Vec<Finding> {
+    let graph = ContractGraph::from_contract(contract);
+    let mut findings: Vec<Finding> = Vec::new();
+    for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+        let called = graph.edges.iter().any(|edge| edge.target == function.name);
+        for rule in rules {
+            if let Some(message) = check(*rule, function, called) {
+                findings.push(Finding {
+                    rule: *rule,
+                    function: function.name.clone(),
+                    file_path: function.file_path.clone(),
+                    line: function.line_span.map(|(start, _)| start),
+                    message,
+                });
+            }
+        }
+    }
+    findings.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scanner_syn::contract_descriptor::ContractDescriptorMeta;
+    use strum::IntoEnumIterator;
+
+    fn function(
+        name: &str,
+        line: usize,
+    ) -> FunctionInfo {
+        FunctionInfo {
+            name: name.to_string(),
+            file_path: Some(PathBuf::from("src").join("lib.rs")),
+            line_span: Some((line, line + 3)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_finds_unused_helpers_uncalled_callbacks_and_payable_views() {
+        let used_helper = function("add_amount", 30);
+        let on_transfer = FunctionInfo {
+            is_public: true,
+            is_private_cccalls: true,
+            ..function("on_transfer", 40)
+        };
+        let withdraw = FunctionInfo {
+            is_public: true,
+            is_mutable: true,
+            inner_calls: Some(vec![used_helper.clone()]),
+            ..function("withdraw", 10)
+        };
+        let balance = FunctionInfo {
+            is_public: true,
+            is_view: true,
+            is_payable: true,
+            ..function("balance", 20)
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![on_transfer, withdraw, balance, used_helper, function("display", 50)],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        };
+
+        let findings = lint(&contract, &Rule::iter().collect::<Vec<_>>());
+        let found: Vec<(Rule, &str)> = findings
+            .iter()
+            .map(|finding| (finding.rule, finding.function.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Rule::PayableView, "balance"),
+                (Rule::UncalledCallback, "on_transfer"),
+                (Rule::UnusedHelper, "display"),
+            ]
+        );
+        assert_eq!(
+            findings[2].to_string(),
+            format!("warning[unused-helper] {}:50: `display` is private and never called", PathBuf::from("src").join("lib.rs").display())
+        );
+        assert!(lint(&contract, &[Rule::PayableView]).iter().all(|finding| finding.rule == Rule::PayableView));
+    }
+}
@@ -1,25 +1,91 @@
+use crate::objects::group::Group;
+use crate::objects::legend::Legend;
+use crate::objects::link::Link;
 use crate::objects::node::Node;
 use crate::syntax::{CoreSyntaxFunctions, FlowDirection};
+use crate::theme::Theme;
 
 pub struct MdAPI<T: CoreSyntaxFunctions> {
     schema: T,
     hierarchy_root: Node,
+    groups: Vec<Group>,
+    legend: Option<Legend>,
+    links: Vec<Link>,
 }
 
 impl<T: CoreSyntaxFunctions> MdAPI<T> {
     pub fn new(
         flow_direction: FlowDirection,
         hierarchy_root: Node,
+    ) -> Self {
+        MdAPI::from_schema(T::new(flow_direction), hierarchy_root)
+    }
+
+    /// Creates the API around an already created schema, for syntax's that are created some other way than [CoreSyntaxFunctions::new].
+    pub fn from_schema(
+        schema: T,
+        hierarchy_root: Node,
     ) -> Self {
         MdAPI {
-            schema: T::new(flow_direction),
+            schema,
             hierarchy_root,
+            groups: Vec::new(),
+            legend: None,
+            links: Vec::new(),
         }
     }
 
+    /// Adds a [Group] whose nodes are declared ahead of the hierarchy, placing them in a subgraph.
+    pub fn add_group(
+        &mut self,
+        group: Group,
+    ) {
+        self.groups.push(group);
+    }
+
+    /// Adds a [Link] making a node of the hierarchy clickable.
+    pub fn add_link(
+        &mut self,
+        link: Link,
+    ) {
+        self.links.push(link);
+    }
+
+    /// Sets a [Legend] that is appended after the hierarchy as a subgraph.
+    pub fn set_legend(
+        &mut self,
+        legend: Legend,
+    ) {
+        self.legend = Some(legend);
+    }
+
+    /// Sets the [Theme] used to style the nodes & connections of the diagram.
+    pub fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.schema.set_theme(theme);
+    }
+
     pub fn parse_hierarchy(&mut self) -> String {
+        for group in &self.groups {
+            group.traverse(&mut self.schema, 1);
+            self.schema.add_linebreak(None);
+        }
+
         self.hierarchy_root.traverse(&mut self.schema);
 
+        if let Some(legend) = &self.legend {
+            legend.traverse(&mut self.schema, 1);
+            self.schema.add_linebreak(None);
+        }
+
+        for link in &self.links {
+            self.schema
+                .add_click(&link.id, &link.url, link.tooltip.as_deref());
+            self.schema.add_linebreak(None);
+        }
+
         self.schema.return_schema()
     }
 }
@@ -28,6 +94,7 @@ impl<T: CoreSyntaxFunctions> MdAPI<T> {
 mod tests {
     use super::*;
     use crate::objects::connection::{Connection, ConnectionType};
+    use crate::objects::group::Group;
     use crate::objects::node::{ActionType, ScopeType};
     use crate::syntax::flow_chart::FlowChart;
 
@@ -35,13 +102,17 @@ mod tests {
     fn it_works() {
         let hierarchy_tree_root = Node {
             name: "function_a".to_string(),
+            label: None,
             scope: ScopeType::Public,
             action: ActionType::Mutation,
             connections: vec![
                 Connection {
                     connection_type: ConnectionType::Emission,
+                    label: None,
+                    extra_length_num: None,
                     node: Node {
                         name: "function_a_event".to_string(),
+                        label: None,
                         scope: ScopeType::Public,
                         action: ActionType::Event,
                         connections: vec![],
@@ -49,8 +120,11 @@ mod tests {
                 },
                 Connection {
                     connection_type: ConnectionType::DirectConnection,
+                    label: None,
+                    extra_length_num: None,
                     node: Node {
                         name: "function_b_private".to_string(),
+                        label: None,
                         scope: ScopeType::Private,
                         action: ActionType::Mutation,
                         connections: vec![],
@@ -69,4 +143,97 @@ mod tests {
 
         assert_eq!(result, expected_string);
     }
+
+    #[test]
+    fn it_declares_groups_before_the_hierarchy() {
+        let hierarchy_tree_root = Node {
+            name: "function_a".to_string(),
+            label: None,
+            scope: ScopeType::Public,
+            action: ActionType::Mutation,
+            connections: vec![Connection {
+                connection_type: ConnectionType::DirectConnection,
+                label: None,
+                extra_length_num: None,
+                node: Node {
+                    name: "function_b".to_string(),
+                    label: None,
+                    scope: ScopeType::Private,
+                    action: ActionType::View,
+                    connections: vec![],
+                },
+            }],
+        };
+
+        let mut api = MdAPI::<FlowChart>::new(FlowDirection::TD, hierarchy_tree_root);
+        api.add_group(Group {
+            id: "module_a".to_string(),
+            title: "a".to_string(),
+            nodes: vec![],
+            groups: vec![Group {
+                id: "module_a_b".to_string(),
+                title: "b".to_string(),
+                nodes: vec![Node {
+                    name: "function_b".to_string(),
+                    label: None,
+                    scope: ScopeType::Private,
+                    action: ActionType::View,
+                    connections: vec![],
+                }],
+                groups: vec![],
+            }],
+        });
+
+        let result = api.parse_hierarchy();
+
+        let expected_string = r#"flowchart TD
+	subgraph module_a ["a"]
+		subgraph module_a_b ["b"]
+			function_b((function_b)):::Private-View
+		end
+	end
+	function_a{{function_a}}:::Public-Mutation --> function_b((function_b)):::Private-View
+	"#;
+
+        assert_eq!(result, expected_string);
+    }
+
+    #[test]
+    fn it_appends_a_legend_of_the_used_classes_and_connections() {
+        let hierarchy_tree_root = Node {
+            name: "function_a".to_string(),
+            label: None,
+            scope: ScopeType::Public,
+            action: ActionType::Mutation,
+            connections: vec![Connection {
+                connection_type: ConnectionType::Emission,
+                label: None,
+                extra_length_num: None,
+                node: Node {
+                    name: "function_a_event".to_string(),
+                    label: None,
+                    scope: ScopeType::Public,
+                    action: ActionType::Event,
+                    connections: vec![],
+                },
+            }],
+        };
+
+        let legend = Legend::from_hierarchy(&hierarchy_tree_root, &[]);
+        let mut api = MdAPI::<FlowChart>::new(FlowDirection::TD, hierarchy_tree_root);
+        api.set_legend(legend);
+
+        let result = api.parse_hierarchy();
+
+        let expected_string = r#"flowchart TD
+	function_a{{function_a}}:::Public-Mutation -..- function_a_event>function_a_event]:::Public-Event
+	subgraph legend ["Legend"]
+		legend_Public_Mutation{{Public Mutation}}:::Public-Mutation
+		legend_Public_Event>Public Event]:::Public-Event
+		legend_Emission[emits]:::Contract-None -..- legend_function[function]:::Contract-None
+	end
+	"#;
+
+        assert_eq!(result, expected_string);
+    }
 }
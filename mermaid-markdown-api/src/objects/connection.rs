@@ -2,15 +2,28 @@ use crate::objects::node::Node;
 use crate::objects::DiagramObject;
 use crate::syntax::CoreSyntaxFunctions;
 
+/// The relationship a [Connection] represents, the `Label` property is the text used to label the edge.
+#[derive(AsRefStr, EnumIter, EnumProperty, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConnectionType {
+    #[strum(props(Label = "calls"))]
     DirectConnection,
+    #[strum(props(Label = "cross-contract"))]
     CrossContractConnection,
+    #[strum(props(Label = "emits"))]
     Emission,
+    #[strum(props(Label = "callback"))]
+    Callback,
+    #[strum(props(Label = "transfers"))]
+    Transfer,
 }
 
 pub struct Connection {
     pub connection_type: ConnectionType,
     pub node: Node,
+    /// An optional text to be displayed on the connection
+    pub label: Option<String>,
+    /// An optional amount of additional flags to increase line length
+    pub extra_length_num: Option<u8>,
 }
 
 impl<T: CoreSyntaxFunctions> DiagramObject<T> for Connection {
@@ -20,10 +33,11 @@ impl<T: CoreSyntaxFunctions> DiagramObject<T> for Connection {
         _id: Option<&str>,
         extra_length_num: Option<u8>,
     ) {
-        let config = schema.build_connection_config(self, extra_length_num);
+        // An explicitly requested length takes precedence over the connection's own
+        let config =
+            schema.build_connection_config(self, extra_length_num.or(self.extra_length_num));
         schema.add_connection(config);
     }
 }
 
-// TODO: Extra length
 // TODO: Separate via generic vs param
@@ -0,0 +1,44 @@
+use crate::objects::node::Node;
+use crate::objects::DiagramObject;
+use crate::syntax::CoreSyntaxFunctions;
+
+/// A titled collection of [Node]s & nested [Group]s, rendered as a subgraph.
+pub struct Group {
+    /// The ID that will be assigned to the group
+    pub id: String,
+    /// The text to be displayed as the title of the group
+    pub title: String,
+    /// The nodes placed directly inside of this group
+    pub nodes: Vec<Node>,
+    /// The groups nested inside of this group
+    pub groups: Vec<Group>,
+}
+
+impl Group {
+    /// Appends the group, the declarations of its nodes & its nested groups to the schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The schema the group is added to
+    /// * `depth` - The number of indents the group is opened at
+    pub fn traverse(
+        &self,
+        schema: &mut impl CoreSyntaxFunctions,
+        depth: u8,
+    ) {
+        schema.add_group_start(&self.id, &self.title);
+
+        for node in &self.nodes {
+            schema.add_linebreak(Some(depth + 1));
+            node.add_object_to_schema(schema, None, None);
+        }
+
+        for group in &self.groups {
+            schema.add_linebreak(Some(depth + 1));
+            group.traverse(schema, depth + 1);
+        }
+
+        schema.add_linebreak(Some(depth));
+        schema.add_group_end();
+    }
+}
@@ -0,0 +1,142 @@
+use crate::objects::connection::{Connection, ConnectionType};
+use crate::objects::group::Group;
+use crate::objects::node::{ActionType, Node, ScopeType};
+use crate::objects::DiagramObject;
+use crate::syntax::CoreSyntaxFunctions;
+use strum::{EnumProperty, IntoEnumIterator};
+
+/// A key explaining the node shapes, classes & connection styles that occur in a diagram, rendered as a subgraph.
+pub struct Legend {
+    /// One node per class that occurs in the diagram, named after its scope & action (e.g. `Public View`)
+    pub nodes: Vec<Node>,
+    /// One connection per kind of connection that occurs in the diagram, each pointing at a placeholder node
+    pub connections: Vec<Connection>,
+}
+
+impl Legend {
+    /// Builds a legend listing only the classes & connection kinds used by the supplied hierarchy & groups.
+    ///
+    /// # Arguments
+    ///
+    /// * `hierarchy_root` - The root of the diagram's hierarchy
+    /// * `groups` - The groups declared ahead of the hierarchy
+    pub fn from_hierarchy(
+        hierarchy_root: &Node,
+        groups: &[Group],
+    ) -> Legend {
+        let mut classes: Vec<(ScopeType, ActionType)> = Vec::new();
+        let mut connection_types: Vec<ConnectionType> = Vec::new();
+        collect_from_node(hierarchy_root, &mut classes, &mut connection_types);
+        collect_from_groups(groups, &mut classes, &mut connection_types);
+
+        // Follow the declaration order of the enums so the legend doesn't depend on the order of the functions
+        let nodes = ScopeType::iter()
+            .flat_map(|scope| ActionType::iter().map(move |action| (scope, action)))
+            .filter(|class| classes.contains(class))
+            .map(|(scope, action)| Node {
+                name: format!("{} {}", scope.as_ref(), action.as_ref()),
+                label: None,
+                scope,
+                action,
+                connections: Vec::new(),
+            })
+            .collect();
+        let connections = ConnectionType::iter()
+            .filter(|connection_type| connection_types.contains(connection_type))
+            .map(|connection_type| Connection {
+                connection_type,
+                node: legend_placeholder("function"),
+                label: None,
+                extra_length_num: None,
+            })
+            .collect();
+
+        Legend { nodes, connections }
+    }
+
+    /// Returns `true` if the legend has nothing to explain.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.connections.is_empty()
+    }
+
+    /// Appends the legend to the schema as a subgraph, its IDs are prefixed with `legend_` to keep them apart from the diagram's.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The schema the legend is added to
+    /// * `depth` - The number of indents the legend is opened at
+    pub fn traverse(
+        &self,
+        schema: &mut impl CoreSyntaxFunctions,
+        depth: u8,
+    ) {
+        schema.add_group_start("legend", "Legend");
+
+        for node in &self.nodes {
+            schema.add_linebreak(Some(depth + 1));
+            let id = format!("legend_{}_{}", node.scope.as_ref(), node.action.as_ref());
+            node.add_object_to_schema(schema, Some(&id), None);
+        }
+
+        for connection in &self.connections {
+            schema.add_linebreak(Some(depth + 1));
+            // The source is named after the relationship, e.g. `calls`, & every kind points at the same placeholder
+            let source = legend_placeholder(connection.connection_type.get_str("Label").unwrap());
+            let id = format!("legend_{}", connection.connection_type.as_ref());
+            source.add_object_to_schema(schema, Some(&id), None);
+            connection.add_object_to_schema(schema, None, None);
+            connection
+                .node
+                .add_object_to_schema(schema, Some("legend_function"), None);
+        }
+
+        schema.add_linebreak(Some(depth));
+        schema.add_group_end();
+    }
+}
+
+/// Creates an unclassified node used to anchor the connections of the legend.
+///
+/// # Arguments
+///
+/// * `name` - The text displayed in the node
+fn legend_placeholder(name: &str) -> Node {
+    Node {
+        name: name.to_string(),
+        label: None,
+        scope: ScopeType::Contract,
+        action: ActionType::None,
+        connections: Vec::new(),
+    }
+}
+
+/// Records the class of the node & the kinds of its connections, then recurses into the connected nodes.
+fn collect_from_node(
+    node: &Node,
+    classes: &mut Vec<(ScopeType, ActionType)>,
+    connection_types: &mut Vec<ConnectionType>,
+) {
+    if !classes.contains(&(node.scope, node.action)) {
+        classes.push((node.scope, node.action));
+    }
+    for connection in &node.connections {
+        if !connection_types.contains(&connection.connection_type) {
+            connection_types.push(connection.connection_type);
+        }
+        collect_from_node(&connection.node, classes, connection_types);
+    }
+}
+
+/// Records the classes & connection kinds of every node placed in the groups & their nested groups.
+fn collect_from_groups(
+    groups: &[Group],
+    classes: &mut Vec<(ScopeType, ActionType)>,
+    connection_types: &mut Vec<ConnectionType>,
+) {
+    for group in groups {
+        for node in &group.nodes {
+            collect_from_node(node, classes, connection_types);
+        }
+        collect_from_groups(&group.groups, classes, connection_types);
+    }
+}
@@ -0,0 +1,9 @@
+/// A URL a node is linked to, e.g. the source of the function it represents.
+pub struct Link {
+    /// The ID of the node that is linked
+    pub id: String,
+    /// The URL opened when the node is clicked
+    pub url: String,
+    /// An optional text displayed when hovering over the node
+    pub tooltip: Option<String>,
+}
@@ -1,6 +1,9 @@
 use crate::syntax::CoreSyntaxFunctions;
 
 pub mod connection;
+pub mod group;
+pub mod legend;
+pub mod link;
 pub mod node;
 
 pub trait DiagramObject<T: CoreSyntaxFunctions> {
@@ -2,9 +2,9 @@ use crate::objects::connection::Connection;
 use crate::objects::DiagramObject;
 use crate::syntax::CoreSyntaxFunctions;
 use std::collections::VecDeque;
-use strum_macros::AsRefStr;
+use strum_macros::{AsRefStr, EnumIter};
 
-#[derive(AsRefStr, Debug)]
+#[derive(AsRefStr, EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScopeType {
     Private,
     Public,
@@ -12,9 +12,13 @@ pub enum ScopeType {
     Payable,
     Contract,
     Initializer,
+    /// A method of an external contract, called across contracts
+    External,
+    /// A callback, i.e. a `#[private]` method only the contract itself may call
+    Callback,
 }
 
-#[derive(AsRefStr, Debug)]
+#[derive(AsRefStr, EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ActionType {
     None,
     Mutation,
@@ -25,6 +29,8 @@ pub enum ActionType {
 
 pub struct Node {
     pub name: String,
+    /// The text shown in the node, the name if none is given
+    pub label: Option<String>,
     pub scope: ScopeType,
     pub action: ActionType,
     pub connections: Vec<Connection>,
@@ -43,6 +49,10 @@ impl<T: CoreSyntaxFunctions> DiagramObject<T> for Node {
 }
 
 impl Node {
+    /// Returns the text shown in the node.
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
     fn parse_node(
         &self,
         schema: &mut impl CoreSyntaxFunctions,
@@ -1,18 +1,30 @@
 use crate::{
+    documentation::ContractDocumentation,
+    graph_export::ContractGraph,
+    html_report::HtmlReport,
     md_api::MdAPI,
     objects::{
         connection::{self, Connection, ConnectionType},
+        group::Group,
+        legend::Legend,
+        link::Link,
         node::{ActionType, Node, ScopeType},
     },
-    syntax::{flow_chart::FlowChart, FlowDirection},
-};
-use scanner_syn::contract_descriptor::{
-    ContractDescriptor, ContractInfo, DefaultContractDescriptor, FunctionInfo,
+    scope::{self, DiagramScope},
+    structurizr::StructurizrWorkspace,
+    syntax::{
+        d2::D2, dot::Dot, draw_io::DrawIo, flow_chart::FlowChart, plant_uml::PlantUml, svg::Svg,
+        CoreSyntaxFunctions, FlowDirection, Syntax,
+    },
+    theme::{Style, Theme},
 };
+use scanner_syn::contract_descriptor::{ContractDescriptorMeta, ContractInfo, FunctionInfo};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     ops::{Deref, DerefMut},
     vec::Vec,
 };
+use strum::EnumProperty;
 struct Connections(Vec<Connection>);
 impl Deref for Connections {
     type Target = Vec<Connection>;
@@ -30,6 +42,12 @@ impl DerefMut for Connections {
 
 impl Into<ScopeType> for FunctionInfo {
     fn into(self) -> ScopeType {
+        if self.external_contract.is_some() {
+            return ScopeType::External
+        }
+        if self.is_private_cccalls {
+            return ScopeType::Callback
+        }
         if self.is_public && !self.is_init && !self.is_payable {
             return ScopeType::Public
         }
@@ -68,30 +86,62 @@ impl Into<ConnectionType> for FunctionInfo {
     fn into(self) -> ConnectionType {
         if self.is_event {
             ConnectionType::Emission
-        } else if self.is_trait_impl {
+        } else if self.is_transfer {
+            ConnectionType::Transfer
+        } else if self.is_trait_impl || self.external_contract.is_some() {
             ConnectionType::CrossContractConnection
+        } else if self.is_private_cccalls {
+            ConnectionType::Callback
         } else {
             ConnectionType::DirectConnection
         }
     }
 }
 
-impl From<Option<Vec<FunctionInfo>>> for Connections {
-    fn from(val: Option<Vec<FunctionInfo>>) -> Self {
+impl Connections {
+    /// Builds the connections to the supplied inner calls, labelling them as requested by the options.
+    ///
+    /// # Arguments
+    ///
+    /// * `val` - The inner calls of a function
+    /// * `options` - The options deciding which labels are put on the connections
+    fn from_functions(
+        val: Option<Vec<FunctionInfo>>,
+        options: &PipelineOptions,
+    ) -> Self {
         if val.is_some() {
             let finfo = val.unwrap();
             if !finfo.is_empty() {
                 let inner = finfo
                     .into_iter()
                     .map(|ifn| -> Connection {
+                        let connection_type: ConnectionType = ifn.clone().into();
+                        let relationship = connection_type.get_str("Label").unwrap();
+                        let call_sites = format!("x{}", ifn.call_count);
+                        let show_call_sites = options.call_counts && ifn.call_count > 1;
+                        // Functions without call sites hang off the synthetic root & stay unlabelled
+                        let label = match (options.edge_labels, show_call_sites) {
+                            _ if ifn.call_count == 0 => None,
+                            (true, true) => Some(format!("{} {}", relationship, call_sites)),
+                            (true, false) => Some(relationship.to_string()),
+                            (false, true) => Some(call_sites),
+                            (false, false) => None,
+                        };
                         Connection {
-                            connection_type: ifn.clone().into(),
+                            connection_type,
                             node: Node {
                                 name: ifn.name.clone(),
+                                label: None,
                                 scope: ifn.clone().into(),
                                 action: ifn.clone().into(),
-                                connections: Connections::from(ifn.clone().inner_calls).0,
+                                connections: Connections::from_functions(
+                                    ifn.clone().inner_calls,
+                                    options,
+                                )
+                                .0,
                             },
+                            label,
+                            extra_length_num: None,
                         }
                     })
                     .collect();
@@ -102,13 +152,345 @@ impl From<Option<Vec<FunctionInfo>>> for Connections {
     }
 }
 
+/// The key used to group the nodes of the diagram into subgraphs.
+#[derive(AsRefStr, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum GroupBy {
+    /// The type the method is implemented for
+    Impl,
+    /// The trait the method is implemented as part of
+    Trait,
+    /// The source file the function is defined in
+    File,
+    /// The module path of the function, nested modules become nested subgraphs
+    Module,
+}
+
+impl GroupBy {
+    /// Returns the path of nested group titles a function belongs to, empty if it stays ungrouped.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The function that is being grouped
+    fn group_path(
+        &self,
+        function: &FunctionInfo,
+    ) -> Vec<String> {
+        match self {
+            GroupBy::Impl => function.impl_type.iter().cloned().collect(),
+            GroupBy::Trait => function.trait_name.iter().cloned().collect(),
+            GroupBy::File => function
+                .file_path
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            GroupBy::Module => function.module_path.clone(),
+        }
+    }
+}
+
+/// Where the legend explaining the shapes, classes & connection styles of the diagram is placed.
+#[derive(AsRefStr, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum LegendPlacement {
+    /// Appended to the diagram as a subgraph
+    Subgraph,
+    /// Kept out of the diagram as Markdown tables, see [ScannerPipeline::legend]
+    Markdown,
+}
+
+/// How the nodes are linked to the source of the functions they represent.
+#[derive(Debug, Clone)]
+pub struct SourceLinks {
+    /// The URL of a function's source, `{path}`, `{line}`, `{end_line}` & `{rev}` are substituted
+    /// (e.g. `https://github.com/org/repo/blob/{rev}/{path}#L{line}`)
+    pub url_template: String,
+    /// The revision substituted for `{rev}`, e.g. a commit hash or a branch name
+    pub rev: String,
+}
+
+impl SourceLinks {
+    /// Returns the link to the source of a function, `None` if its location is unknown.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The function that is being linked
+    fn link(
+        &self,
+        function: &FunctionInfo,
+    ) -> Option<Link> {
+        let (file_path, (line, end_line)) = function.file_path.as_ref().zip(function.line_span)?;
+        // URLs always use forward slashes, whatever the platform's separator is
+        let path = file_path
+            .iter()
+            .map(|component| component.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let url = self
+            .url_template
+            .replace("{rev}", &self.rev)
+            .replace("{path}", &path)
+            .replace("{line}", &line.to_string())
+            .replace("{end_line}", &end_line.to_string());
+
+        Some(Link {
+            id: function.name.clone(),
+            url,
+            tooltip: Some(format!("{}:{}-{}", path, line, end_line)),
+        })
+    }
+}
+
+/// Places a node into the group found by following `path`, creating missing groups on the way.
+///
+/// # Arguments
+///
+/// * `groups` - The groups at the current nesting level
+/// * `used_ids` - The IDs of every group created so far, a numeric suffix is added to a new ID found among them
+/// * `parent_id` - The ID of the enclosing group, used to keep nested IDs unique
+/// * `path` - The remaining group titles leading to the node's group
+/// * `node` - The node to place
+fn insert_into_groups(
+    groups: &mut Vec<Group>,
+    used_ids: &mut BTreeSet<String>,
+    parent_id: &str,
+    path: &[String],
+    node: Node,
+) {
+    let (title, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let position = match groups.iter().position(|group| &group.title == title) {
+        Some(position) => position,
+        None => {
+            let sanitized_title: String = title
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            // Titles differing only by the characters replaced above, e.g. `a-b` & `a_b`, would otherwise share an ID
+            let sanitized_id = format!("{}_{}", parent_id, sanitized_title);
+            let mut id = sanitized_id.clone();
+            let mut suffix = 2;
+            while used_ids.contains(&id) {
+                id = format!("{}_{}", sanitized_id, suffix);
+                suffix += 1;
+            }
+            used_ids.insert(id.clone());
+            groups.push(Group {
+                id,
+                title: title.clone(),
+                nodes: Vec::new(),
+                groups: Vec::new(),
+            });
+            groups.len() - 1
+        }
+    };
+    let group = &mut groups[position];
+    if rest.is_empty() {
+        group.nodes.push(node);
+    } else {
+        let group_id = group.id.clone();
+        insert_into_groups(&mut group.groups, used_ids, &group_id, rest, node);
+    }
+}
+
+/// Options that control how the scanned contract is laid out in the diagram.
+#[derive(Default, Debug, Clone)]
+pub struct PipelineOptions {
+    /// Groups the nodes into subgraphs by the given key
+    pub group_by: Option<GroupBy>,
+    /// Labels the connections by relationship (i.e. calls, callback, cross-contract, emits, transfers)
+    pub edge_labels: bool,
+    /// Labels the connections with the number of call sites when a function is called more than once
+    pub call_counts: bool,
+    /// The theme used to style the diagram, the built-in `light` theme if none is given
+    pub theme: Option<Theme>,
+    /// Adds a legend of the shapes, classes & connection styles that occur in the diagram
+    pub legend: Option<LegendPlacement>,
+    /// Makes the nodes clickable, linking them to the source of their functions
+    pub source_links: Option<SourceLinks>,
+    /// The syntax the diagram is written in
+    pub syntax: Syntax,
+    /// The name of the contract, e.g. the name of its crate, for the syntaxes naming it. Default: Contract
+    pub contract_name: Option<String>,
+    /// Leaves out the functions whose name matches one of these globs, e.g. `internal_*`, along with the calls to them
+    pub excluded_methods: Vec<glob::Pattern>,
+    /// The text shown in place of a function's name in the nodes of the diagram, by name
+    pub labels: BTreeMap<String, String>,
+    /// The part of the call graph shown, e.g. the ego graph of a method, everything if left empty
+    pub scope: DiagramScope,
+    /// The style of the nodes singled out by name, e.g. the changed methods of a diff, drawn over the one of the theme.
+    /// Only applies to Mermaid.js flowcharts
+    pub highlights: BTreeMap<String, Style>,
+}
+
+/// Returns the functions without the excluded ones, removing the calls to them too.
+///
+/// # Arguments
+///
+/// * `functions` - The functions
+/// * `excluded_methods` - The globs matching the names of the excluded functions
+fn without_excluded(
+    functions: Vec<FunctionInfo>,
+    excluded_methods: &[glob::Pattern],
+) -> Vec<FunctionInfo> {
+    scope::without(functions, &|function| {
+        excluded_methods.iter().any(|pattern| pattern.matches(&function.name))
+    })
+}
+
+/// Collects the names of a node & of the nodes it's connected to.
+///
+/// # Arguments
+///
+/// * `node` - The node
+/// * `names` - The names collected so far
+fn collect_names(
+    node: &Node,
+    names: &mut BTreeSet<String>,
+) {
+    names.insert(node.name.clone());
+    for connection in &node.connections {
+        collect_names(&connection.node, names);
+    }
+}
+
+/// Shows the renamed labels in place of the names of a node & of the nodes it's connected to.
+///
+/// # Arguments
+///
+/// * `node` - The node
+/// * `labels` - The labels by name
+fn apply_labels(
+    node: &mut Node,
+    labels: &BTreeMap<String, String>,
+) {
+    if let Some(label) = labels.get(&node.name) {
+        node.label = Some(label.clone());
+    }
+    for connection in node.connections.iter_mut() {
+        apply_labels(&mut connection.node, labels);
+    }
+}
+
+/// Shows the renamed labels in place of the names of the nodes of groups & of their nested groups.
+///
+/// # Arguments
+///
+/// * `groups` - The groups
+/// * `labels` - The labels by name
+fn apply_group_labels(
+    groups: &mut [Group],
+    labels: &BTreeMap<String, String>,
+) {
+    for group in groups {
+        for node in group.nodes.iter_mut() {
+            apply_labels(node, labels);
+        }
+        apply_group_labels(&mut group.groups, labels);
+    }
+}
+
 pub struct ScannerPipeline {
     pub content: String,
+    /// The legend as Markdown tables, when it was requested with [LegendPlacement::Markdown]
+    pub legend: Option<String>,
 }
 impl ScannerPipeline {
     pub fn from(contract: ContractInfo, flow_direction: FlowDirection) -> ScannerPipeline {
+        ScannerPipeline::from_with_options(contract, flow_direction, &PipelineOptions::default())
+    }
+
+    pub fn from_with_options(
+        contract: ContractInfo,
+        flow_direction: FlowDirection,
+        options: &PipelineOptions,
+    ) -> ScannerPipeline {
+        let contract = if options.excluded_methods.is_empty() {
+            contract
+        } else {
+            ContractInfo {
+                contract_metadata: contract
+                    .contract_metadata
+                    .into_iter()
+                    .map(|metadata| ContractDescriptorMeta {
+                        fns: without_excluded(metadata.fns, &options.excluded_methods),
+                        ext_contract_fns: without_excluded(metadata.ext_contract_fns, &options.excluded_methods),
+                        ..metadata
+                    })
+                    .collect(),
+            }
+        };
+        let contract = options.scope.apply(contract);
+        // The interchange formats export the call graph as is, without any of the diagram's layout
+        let export = match options.syntax {
+            Syntax::GraphMl => Some(ContractGraph::from_contract(&contract).to_graphml()),
+            Syntax::Cytoscape => Some(ContractGraph::from_contract(&contract).to_cytoscape()),
+            Syntax::Structurizr => {
+                let name = options.contract_name.as_deref().unwrap_or("Contract");
+                let mut workspace = StructurizrWorkspace::new(name, flow_direction, options.theme.clone());
+                workspace.add_contract(name, &contract);
+                Some(workspace.to_dsl())
+            }
+            _ => None,
+        };
+        if let Some(content) = export {
+            return ScannerPipeline {
+                content,
+                legend: None,
+            };
+        }
+
+        // The report & the documentation list the methods of the contract, as filtered, next to its diagram
+        let report_contract = match options.syntax {
+            Syntax::Html | Syntax::Markdown => Some(ContractInfo {
+                contract_metadata: contract.contract_metadata.clone(),
+            }),
+            _ => None,
+        };
+
+        let mut groups: Vec<Group> = Vec::new();
+        if let Some(group_by) = options.group_by {
+            let mut grouped_names: Vec<String> = Vec::new();
+            let mut used_ids: BTreeSet<String> = BTreeSet::new();
+            for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+                // Mermaid identifies nodes by name, so a node can only be declared in a single group
+                if grouped_names.contains(&function.name) {
+                    continue;
+                }
+                grouped_names.push(function.name.clone());
+                let node = Node {
+                    name: function.name.clone(),
+                    label: None,
+                    scope: function.clone().into(),
+                    action: function.clone().into(),
+                    connections: Vec::new(),
+                };
+                insert_into_groups(
+                    &mut groups,
+                    &mut used_ids,
+                    group_by.as_ref(),
+                    &group_by.group_path(function),
+                    node,
+                );
+            }
+        }
+
+        let mut links: Vec<Link> = Vec::new();
+        if let Some(source_links) = &options.source_links {
+            for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+                // Each node can only be linked once, so the first function with a name wins
+                if links.iter().any(|link| link.id == function.name) {
+                    continue;
+                }
+                links.extend(source_links.link(function));
+            }
+        }
+
         let mut hierarchy_tree_root = Node {
             name: "Contract".to_string(),
+            label: None,
             scope: ScopeType::Contract,
             action: ActionType::None,
             connections: Vec::new(),
@@ -120,28 +502,218 @@ impl ScannerPipeline {
             .for_each(|(_, value)| {
                 hierarchy_tree_root
                     .connections
-                    .extend(Connections::from(Some(value.fns)).0);
+                    .extend(Connections::from_functions(Some(value.fns), options).0);
             });
+        apply_labels(&mut hierarchy_tree_root, &options.labels);
+        // Styling a node Mermaid.js doesn't know of would add it, so only the drawn ones are highlighted
+        let mut drawn_names: BTreeSet<String> = BTreeSet::new();
+        if !options.highlights.is_empty() {
+            collect_names(&hierarchy_tree_root, &mut drawn_names);
+        }
+        apply_group_labels(&mut groups, &options.labels);
+
+        let theme = options
+            .theme
+            .clone()
+            .unwrap_or_else(|| Theme::builtin("light").unwrap());
+        let legend = options
+            .legend
+            .map(|placement| (placement, Legend::from_hierarchy(&hierarchy_tree_root, &groups)))
+            .filter(|(_, legend)| !legend.is_empty());
+
+        let mut markdown_legend = None;
+        let mut diagram = Diagram {
+            hierarchy_root: hierarchy_tree_root,
+            groups,
+            links,
+            legend: None,
+            theme: theme.clone(),
+        };
+        match legend {
+            Some((LegendPlacement::Subgraph, legend)) => diagram.legend = Some(legend),
+            Some((LegendPlacement::Markdown, legend)) => {
+                // Describe the legend with the same shape mapping & theme as the diagram
+                let mut flow_chart = FlowChart::new(FlowDirection::TD);
+                flow_chart.set_theme(theme);
+                markdown_legend = Some(flow_chart.legend_markdown(&legend));
+            }
+            None => {}
+        }
+        let mut result = match options.syntax {
+            Syntax::Mermaid => diagram.render(FlowChart::new(flow_direction)),
+            Syntax::Dot => diagram.render(Dot::new(flow_direction)),
+            Syntax::PlantUml => diagram.render(PlantUml::new(flow_direction)),
+            Syntax::PlantUmlSequence => diagram.render(PlantUml::sequence()),
+            Syntax::D2 => diagram.render(D2::new(flow_direction)),
+            Syntax::DrawIo => diagram.render(DrawIo::new(flow_direction)),
+            Syntax::Svg => diagram.render(Svg::new(flow_direction)),
+            Syntax::Html => {
+                let title = options.contract_name.as_deref().unwrap_or("Contract");
+                let svg = diagram.render(Svg::new(flow_direction));
+                HtmlReport::from_contract(title, svg, &report_contract.unwrap()).to_html()
+            }
+            Syntax::Markdown => {
+                let title = options.contract_name.as_deref().unwrap_or("Contract");
+                let flow_chart = diagram.render(FlowChart::new(flow_direction));
+                ContractDocumentation::from_contract(title, flow_chart, &report_contract.unwrap()).to_markdown()
+            }
+            Syntax::GraphMl | Syntax::Cytoscape | Syntax::Structurizr => unreachable!("Exported above"),
+        };
+        if options.syntax == Syntax::Mermaid {
+            for (name, style) in &options.highlights {
+                if let (true, Some(css)) = (drawn_names.contains(name), style.to_css()) {
+                    result.push_str(&format!("style {} {};\n\t", name, css));
+                }
+            }
+        }
+
+        ScannerPipeline {
+            content: result,
+            legend: markdown_legend,
+        }
+    }
+}
+
+/// Everything that goes into a diagram, independent of the syntax it's written in.
+struct Diagram {
+    hierarchy_root: Node,
+    groups: Vec<Group>,
+    links: Vec<Link>,
+    legend: Option<Legend>,
+    theme: Theme,
+}
+
+impl Diagram {
+    /// Writes the diagram into the supplied schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - An empty schema of the syntax the diagram is written in
+    fn render<T: CoreSyntaxFunctions>(
+        self,
+        schema: T,
+    ) -> String {
+        let mut api = MdAPI::from_schema(schema, self.hierarchy_root);
+        for group in self.groups {
+            api.add_group(group);
+        }
+        for link in self.links {
+            api.add_link(link);
+        }
+        if let Some(legend) = self.legend {
+            api.set_legend(legend);
+        }
+        api.set_theme(self.theme);
+        api.parse_hierarchy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_leaves_out_excluded_methods_and_renames_labels() {
+        let internal = FunctionInfo {
+            name: "internal_transfer".to_string(),
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_mutable: true,
+            inner_calls: Some(vec![internal.clone()]),
+            ..Default::default()
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![withdraw, internal],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        };
+        let options = PipelineOptions {
+            excluded_methods: vec![glob::Pattern::new("internal_*").unwrap()],
+            labels: BTreeMap::from([("withdraw".to_string(), "Withdraw (owner)".to_string())]),
+            ..PipelineOptions::default()
+        };
+
+        let content = ScannerPipeline::from_with_options(contract, FlowDirection::LR, &options).content;
+        assert!(content.starts_with("flowchart LR"));
+        assert!(content.contains("withdraw{{\"Withdraw (owner)\"}}"));
+        assert!(!content.contains("internal_transfer"));
+    }
+
+    #[test]
+    fn it_only_draws_the_scope() {
+        let balance = FunctionInfo {
+            name: "balance".to_string(),
+            is_public: true,
+            is_view: true,
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_mutable: true,
+            inner_calls: Some(vec![FunctionInfo {
+                call_count: 1,
+                ..balance.clone()
+            }]),
+            ..Default::default()
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![withdraw, balance],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        };
+        let options = PipelineOptions {
+            scope: DiagramScope {
+                focus: Some("withdraw".to_string()),
+                depth: Some(0),
+                ..DiagramScope::default()
+            },
+            ..PipelineOptions::default()
+        };
+
+        let content = ScannerPipeline::from_with_options(contract, FlowDirection::TD, &options).content;
+        assert!(content.contains("Contract[Contract]:::Contract-None --> withdraw{{withdraw}}"));
+        assert!(!content.contains("balance"));
+    }
+
+    #[test]
+    fn it_gives_every_group_a_unique_id() {
+        let function = |name: &str, file_path: &str| FunctionInfo {
+            name: name.to_string(),
+            is_public: true,
+            file_path: Some(file_path.into()),
+            ..Default::default()
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![
+                    function("a", "src/a-b.rs"),
+                    function("b", "src/a_b.rs"),
+                    function("c", "src/a+b.rs"),
+                ],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        };
+        let options = PipelineOptions {
+            group_by: Some(GroupBy::File),
+            ..PipelineOptions::default()
+        };
 
-        let mut api = MdAPI::<FlowChart>::new(flow_direction, hierarchy_tree_root);
-        let mut result = api.parse_hierarchy();
-
-        result.push_str("\n\tclassDef Public-Mutation fill:#12A5F1,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Public-View fill:#12A5F1,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Private-View fill:#858585,stroke:#333,stroke-width:2px;");
-
-        result.push_str("\n\tclassDef Private-Mutation fill:#858585,stroke:#333,stroke-width:1px;");
-        result.push_str("\n\tclassDef Public-Event fill:#FFDF80,stroke:#333,stroke-width:2px,stroke-dasharray: 4 4;");
-        result.push_str("\n\tclassDef Private-Event fill:#FFDF80,stroke:#333,stroke-width:1px,stroke-dasharray: 4 4;");
-        result.push_str("\n\tclassDef Private-None fill:#858585,stroke:#333,stroke-width:1px;");
-        result.push_str("\n\tclassDef Private-Process fill:#858585,stroke:#333,stroke-width:1px;");
-        result.push_str("\n\tclassDef Public-Process fill:#858585,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Public-None fill:#858585,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Initializer-None fill:#FFA080,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Payable-None fill:#6AA84F,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Payable-Mutation fill:#6AA84F,stroke:#333,stroke-width:2px;");
-        result.push_str("\n\tclassDef Contract-None fill:#C2D5E3,stroke:#333,stroke-width:2px;");
-
-        ScannerPipeline { content: result }
+        let content = ScannerPipeline::from_with_options(contract, FlowDirection::TD, &options).content;
+        // The three file names only differ by a character replaced in the IDs
+        assert!(content.contains("subgraph file_src_a_b_rs [\"src/a-b.rs\"]"));
+        assert!(content.contains("subgraph file_src_a_b_rs_2 [\"src/a_b.rs\"]"));
+        assert!(content.contains("subgraph file_src_a_b_rs_3 [\"src/a+b.rs\"]"));
     }
 }
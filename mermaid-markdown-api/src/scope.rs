@@ -0,0 +1,389 @@
+use crate::entrypoints::{expand, nested_contract};
+use crate::graph_export::ContractGraph;
+use crate::objects::node::{ActionType, ScopeType};
+use scanner_syn::contract_descriptor::{ContractDescriptorMeta, ContractInfo, FunctionInfo};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The kinds of functions that can be hidden from a diagram, along with the calls to them.
+#[derive(AsRefStr, EnumString, EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Hidden {
+    /// The private helpers of the contract
+    Private,
+    /// The functions not returning a value
+    Process,
+    /// The functions outside of a `#[near_bindgen]` impl, cross-contract calls & transfers aside
+    OutOfScope,
+    /// The events emitted
+    Events,
+}
+
+impl Hidden {
+    /// Returns `true` if the function is of this kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `function` - The function
+    fn matches(
+        &self,
+        function: &FunctionInfo,
+    ) -> bool {
+        match self {
+            Hidden::Private => matches!(function.clone().into(), ScopeType::Private),
+            Hidden::Process => matches!(function.clone().into(), ActionType::Process),
+            Hidden::OutOfScope => {
+                function.is_out_of_contract_scope && function.external_contract.is_none() && !function.is_transfer
+            }
+            Hidden::Events => function.is_event,
+        }
+    }
+}
+
+/// Restricts a diagram to a part of the call graph of the contract.
+#[derive(Default, Debug, Clone)]
+pub struct DiagramScope {
+    /// Only shows this method & the functions it calls, the ego graph of the method
+    pub focus: Option<String>,
+    /// How many levels of calls are followed from the focused method, or from the public methods without one. Every
+    /// level if `None`
+    pub depth: Option<usize>,
+    /// Shows the functions calling the focused method rather than the ones it calls. Ignored without a focus
+    pub reverse: bool,
+    /// The kinds of functions left out, along with the calls to them
+    pub hidden: Vec<Hidden>,
+    /// Merges the private helpers called from a single function into it, so their calls are made by the caller
+    pub collapse_helpers: bool,
+}
+
+/// Returns the functions without the ones matching the predicate, removing the calls to them too.
+///
+/// # Arguments
+///
+/// * `functions` - The functions
+/// * `removed` - Whether a function is removed
+pub(crate) fn without<F: Fn(&FunctionInfo) -> bool>(
+    functions: Vec<FunctionInfo>,
+    removed: &F,
+) -> Vec<FunctionInfo> {
+    functions
+        .into_iter()
+        .filter(|function| !removed(function))
+        .map(|function| FunctionInfo {
+            inner_calls: function
+                .inner_calls
+                .map(|inner_calls| without(inner_calls, removed))
+                .filter(|inner_calls| !inner_calls.is_empty()),
+            ..function
+        })
+        .collect()
+}
+
+/// Returns the calls a function makes once the single-use helpers it calls are merged into it, along with the calls they
+/// make in turn.
+///
+/// # Arguments
+///
+/// * `inner_calls` - The calls made by the function
+/// * `helpers` - The single-use helpers by name
+/// * `merged` - The names of the helpers merged so far, which stops at recursive helpers
+fn merge_helpers(
+    inner_calls: Vec<FunctionInfo>,
+    helpers: &BTreeMap<String, FunctionInfo>,
+    merged: &mut BTreeSet<String>,
+) -> Vec<FunctionInfo> {
+    let mut calls: Vec<FunctionInfo> = Vec::new();
+    for inner_call in inner_calls {
+        let helper_calls = match helpers.get(&inner_call.name) {
+            Some(helper) if merged.insert(inner_call.name.clone()) => {
+                merge_helpers(helper.inner_calls.clone().unwrap_or_default(), helpers, merged)
+            }
+            Some(_) => continue,
+            None => vec![inner_call],
+        };
+        // A function called both directly & through a helper is called from more places
+        for call in helper_calls {
+            match calls.iter_mut().find(|existing| existing.name == call.name) {
+                Some(existing) => existing.call_count += call.call_count,
+                None => calls.push(call),
+            }
+        }
+    }
+    calls
+}
+
+impl DiagramScope {
+    /// Returns `true` if the scope leaves the contract as it is.
+    pub fn is_empty(&self) -> bool {
+        self.focus.is_none() && self.depth.is_none() && self.hidden.is_empty() && !self.collapse_helpers
+    }
+
+    /// Returns the part of a contract in the scope. Hidden functions are left out first, then the helpers are collapsed &
+    /// finally the call graph is walked from the focused method.
+    ///
+    /// # Arguments
+    ///
+    /// * `contract` - The scanned contract
+    pub fn apply(
+        &self,
+        contract: ContractInfo,
+    ) -> ContractInfo {
+        if self.is_empty() {
+            return contract;
+        }
+        let contract = ContractInfo {
+            contract_metadata: contract
+                .contract_metadata
+                .into_iter()
+                .map(|metadata| ContractDescriptorMeta {
+                    fns: without(metadata.fns, &|function| self.hidden.iter().any(|hidden| hidden.matches(function))),
+                    ..metadata
+                })
+                .collect(),
+        };
+        let contract = if self.collapse_helpers {
+            collapse_helpers(contract)
+        } else {
+            contract
+        };
+
+        let functions: Vec<&FunctionInfo> = contract.contract_metadata.iter().flat_map(|m| &m.fns).collect();
+        let mut expanded = BTreeSet::new();
+        let fns = match &self.focus {
+            Some(focus) if self.reverse => {
+                let callers = callers_of(&contract, focus, self.depth);
+                // The callers nobody else in the scope calls are the tops of the tree, the others hang off of them
+                let called: BTreeSet<&str> = functions
+                    .iter()
+                    .filter(|function| callers.contains(&function.name))
+                    .flat_map(|function| function.inner_calls.iter().flatten())
+                    .map(|inner_call| inner_call.name.as_str())
+                    .collect();
+                let tops: Vec<&FunctionInfo> = functions
+                    .iter()
+                    .filter(|function| callers.contains(&function.name) && !called.contains(function.name.as_str()))
+                    .cloned()
+                    .collect();
+                let keep = |name: &str| callers.contains(name);
+                let mut fns: Vec<FunctionInfo> = tops
+                    .into_iter()
+                    .map(|top| expand(top.clone(), &functions, &mut expanded, None, &keep))
+                    .collect();
+                // Callers calling each other in a cycle have no top
+                for function in functions.iter().filter(|function| callers.contains(&function.name)) {
+                    if !expanded.contains(&function.name) {
+                        fns.push(expand((*function).clone(), &functions, &mut expanded, None, &keep));
+                    }
+                }
+                fns
+            }
+            Some(focus) => functions
+                .iter()
+                .find(|function| &function.name == focus)
+                .map(|function| expand((*function).clone(), &functions, &mut expanded, self.depth, &|_| true))
+                .into_iter()
+                .collect(),
+            None => match self.depth {
+                // Without a focus the levels are counted from the methods that can be called from outside
+                Some(depth) => functions
+                    .iter()
+                    .filter(|function| !matches!(FunctionInfo::clone(function).into(), ScopeType::Private))
+                    .map(|function| expand((*function).clone(), &functions, &mut expanded, Some(depth), &|_| true))
+                    .collect(),
+                None => return contract,
+            },
+        };
+        nested_contract(&contract, fns)
+    }
+}
+
+/// Returns the names of a function & of the functions calling it, directly or through other functions, in at most `depth`
+/// calls.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+/// * `name` - The name of the called function
+/// * `depth` - How many levels of callers are followed, all of them if `None`
+fn callers_of(
+    contract: &ContractInfo,
+    name: &str,
+    depth: Option<usize>,
+) -> BTreeSet<String> {
+    let graph = ContractGraph::from_contract(contract);
+    let mut callers = BTreeSet::from([name.to_string()]);
+    let mut level = vec![name.to_string()];
+    let mut remaining = depth;
+    while !level.is_empty() && remaining != Some(0) {
+        level = graph
+            .edges
+            .iter()
+            .filter(|edge| level.contains(&edge.target) && !callers.contains(&edge.source))
+            .map(|edge| edge.source.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        callers.extend(level.iter().cloned());
+        remaining = remaining.map(|remaining| remaining - 1);
+    }
+    callers
+}
+
+/// Merges the private helpers called from a single function into their caller, removing them from the contract.
+///
+/// # Arguments
+///
+/// * `contract` - The scanned contract
+fn collapse_helpers(contract: ContractInfo) -> ContractInfo {
+    let graph = ContractGraph::from_contract(&contract);
+    let mut helpers: BTreeMap<String, FunctionInfo> = BTreeMap::new();
+    for function in contract.contract_metadata.iter().flat_map(|m| &m.fns) {
+        let callers: BTreeSet<&str> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.target == function.name)
+            .map(|edge| edge.source.as_str())
+            .collect();
+        let single_use = callers.len() == 1 && !callers.contains(function.name.as_str());
+        if single_use && matches!(function.clone().into(), ScopeType::Private) && !function.is_event {
+            helpers.entry(function.name.clone()).or_insert_with(|| function.clone());
+        }
+    }
+
+    ContractInfo {
+        contract_metadata: contract
+            .contract_metadata
+            .into_iter()
+            .map(|metadata| ContractDescriptorMeta {
+                fns: metadata
+                    .fns
+                    .into_iter()
+                    .filter(|function| !helpers.contains_key(&function.name))
+                    .map(|function| FunctionInfo {
+                        inner_calls: function
+                            .inner_calls
+                            .map(|inner_calls| merge_helpers(inner_calls, &helpers, &mut BTreeSet::new()))
+                            .filter(|inner_calls| !inner_calls.is_empty()),
+                        ..function
+                    })
+                    .collect(),
+                ..metadata
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `withdraw` & `deposit` call `assert_owner`, `withdraw` also calls `internal_withdraw`, which calls `log` & emits.
+    fn contract() -> ContractInfo {
+        let function = |name: &str| FunctionInfo {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let public = |name: &str| FunctionInfo {
+            is_public: true,
+            is_mutable: true,
+            ..function(name)
+        };
+        // Calls carry the flags of the called function, as resolved by the scanner
+        let calling = |caller: FunctionInfo, callees: &[&FunctionInfo]| FunctionInfo {
+            inner_calls: Some(
+                callees
+                    .iter()
+                    .map(|callee| FunctionInfo {
+                        call_count: 1,
+                        ..(*callee).clone()
+                    })
+                    .collect(),
+            ),
+            ..caller
+        };
+        let assert_owner = function("assert_owner");
+        let log = function("log");
+        let withdrawn = FunctionInfo {
+            is_event: true,
+            ..function("withdrawn")
+        };
+        let internal_withdraw = calling(function("internal_withdraw"), &[&log, &withdrawn]);
+        ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![
+                    calling(public("withdraw"), &[&assert_owner, &FunctionInfo { inner_calls: None, ..internal_withdraw.clone() }]),
+                    calling(public("deposit"), &[&assert_owner]),
+                    assert_owner.clone(),
+                    internal_withdraw,
+                    log,
+                    withdrawn,
+                ],
+                ext_contract_fns: vec![],
+                connections: None,
+                tokens: None,
+            }],
+        }
+    }
+
+    /// Returns every call of the contract as `caller->callee`, sorted.
+    fn calls(contract: &ContractInfo) -> Vec<String> {
+        let mut calls: Vec<String> = ContractGraph::from_contract(contract)
+            .edges
+            .iter()
+            .map(|edge| format!("{}->{}", edge.source, edge.target))
+            .collect();
+        calls.sort();
+        calls
+    }
+
+    #[test]
+    fn it_focuses_on_a_method_up_to_a_depth() {
+        let scope = DiagramScope {
+            focus: Some("withdraw".to_string()),
+            depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(calls(&scope.apply(contract())), vec!["withdraw->assert_owner", "withdraw->internal_withdraw"]);
+
+        let scope = DiagramScope {
+            focus: Some("withdraw".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(calls(&scope.apply(contract())).len(), 4);
+    }
+
+    #[test]
+    fn it_shows_the_callers_of_a_method() {
+        let scope = DiagramScope {
+            focus: Some("assert_owner".to_string()),
+            reverse: true,
+            ..Default::default()
+        };
+        let reversed = scope.apply(contract());
+        assert_eq!(calls(&reversed), vec!["deposit->assert_owner", "withdraw->assert_owner"]);
+        let tops: Vec<&str> = reversed.contract_metadata[0].fns.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(tops, vec!["withdraw", "deposit"]);
+    }
+
+    #[test]
+    fn it_hides_kinds_of_functions_and_collapses_single_use_helpers() {
+        let scope = DiagramScope {
+            hidden: vec![Hidden::Events],
+            collapse_helpers: true,
+            ..Default::default()
+        };
+        // `log` is only called from `internal_withdraw`, so the whole chain is merged into `withdraw`
+        assert_eq!(calls(&scope.apply(contract())), vec!["deposit->assert_owner", "withdraw->assert_owner"]);
+
+        let scope = DiagramScope {
+            hidden: vec![Hidden::Events],
+            ..Default::default()
+        };
+        assert!(!calls(&scope.apply(contract())).contains(&"internal_withdraw->withdrawn".to_string()));
+
+        let scope = DiagramScope {
+            hidden: vec![Hidden::Private],
+            ..Default::default()
+        };
+        assert_eq!(calls(&scope.apply(contract())), Vec::<String>::new());
+    }
+}
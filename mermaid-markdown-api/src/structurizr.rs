@@ -0,0 +1,380 @@
+use crate::graph_export::ContractGraph;
+use crate::objects::connection::ConnectionType;
+use crate::objects::node::{ActionType, Node, ScopeType};
+use crate::syntax::flow_chart::Shape;
+use crate::syntax::{resolve_shape, FlowDirection};
+use crate::theme::Theme;
+use scanner_syn::contract_descriptor::{ContractInfo, FunctionInfo};
+use strum::EnumProperty;
+
+/// A scanned contract, modelled as a container of the system.
+struct Container {
+    /// The name of the contract, e.g. the name of its crate
+    name: String,
+    /// The call graph of the contract
+    graph: ContractGraph,
+}
+
+/// A [Structurizr DSL](https://docs.structurizr.com/dsl) workspace describing one or more contracts as a
+/// [C4 model](https://c4model.com).
+///
+/// The contracts are containers of a single software system, their public methods are its components & the external contracts
+/// they call across contracts are external software systems. The relationships between the components & to the external
+/// systems are derived from the call graph, the calls through private helpers being attributed to the public method making them.
+pub struct StructurizrWorkspace {
+    /// The name of the software system the contracts make up
+    name: String,
+    /// The direction of the automatic layout of the views
+    direction: &'static str,
+    /// The theme the element styles are taken from
+    theme: Theme,
+    containers: Vec<Container>,
+}
+
+/// Quotes a string of the DSL.
+///
+/// # Arguments
+///
+/// * `text` - The text to quote
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Turns a name into an identifier of the DSL, which only allows letters, digits, underscores & hyphens.
+///
+/// # Arguments
+///
+/// * `name` - The name to turn into an identifier
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Returns `true` if the function is a method of the contract, i.e. one of its components.
+///
+/// # Arguments
+///
+/// * `function` - The function
+fn is_component(function: &FunctionInfo) -> bool {
+    function.is_public
+        && !function.is_out_of_contract_scope
+        && !function.is_test
+        && !function.is_event
+        && !function.is_transfer
+        && function.external_contract.is_none()
+}
+
+/// Returns the name of the Structurizr shape closest to a diagram shape.
+///
+/// # Arguments
+///
+/// * `shape` - The shape of the nodes in the diagrams
+fn structurizr_shape(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Rounded | Shape::Stadium => "RoundedBox",
+        Shape::Circle | Shape::DoubleCircle => "Circle",
+        Shape::Hexagon => "Hexagon",
+        Shape::Cylinder => "Cylinder",
+        Shape::Subroutine => "Component",
+        Shape::Flag => "Folder",
+        _ => "Box",
+    }
+}
+
+impl StructurizrWorkspace {
+    /// Creates an empty workspace.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the software system the contracts make up
+    /// * `direction` - The direction of the automatic layout of the views
+    /// * `theme` - The theme the element styles are taken from, the built-in `light` theme if none is given
+    pub fn new(
+        name: &str,
+        direction: FlowDirection,
+        theme: Option<Theme>,
+    ) -> StructurizrWorkspace {
+        let direction = match direction {
+            FlowDirection::TB | FlowDirection::TD => "tb",
+            FlowDirection::BT => "bt",
+            FlowDirection::LR => "lr",
+            FlowDirection::BL => "rl",
+        };
+
+        StructurizrWorkspace {
+            name: name.to_string(),
+            direction,
+            theme: theme.unwrap_or_else(|| Theme::builtin("light").unwrap()),
+            containers: Vec::new(),
+        }
+    }
+
+    /// Adds a scanned contract as a container of the system, a contract already added under the same name is left as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the contract, e.g. the name of its crate
+    /// * `contract` - The scanned contract
+    pub fn add_contract(
+        &mut self,
+        name: &str,
+        contract: &ContractInfo,
+    ) {
+        if self.containers.iter().any(|container| container.name == name) {
+            return;
+        }
+        self.containers.push(Container {
+            name: name.to_string(),
+            graph: ContractGraph::from_contract(contract),
+        });
+    }
+
+    /// Returns the components & external methods a component ends up calling, along with the kind of the last call.
+    ///
+    /// The calls are followed through the functions which aren't components, e.g. private helpers.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The call graph of the contract
+    /// * `source` - The name of the component
+    fn resolve_calls<'a>(
+        graph: &'a ContractGraph,
+        source: &str,
+    ) -> Vec<(&'a FunctionInfo, ConnectionType)> {
+        let mut calls: Vec<(&FunctionInfo, ConnectionType)> = Vec::new();
+        let mut visited: Vec<&str> = vec![source];
+        let mut pending: Vec<&str> = vec![source];
+
+        while let Some(caller) = pending.pop() {
+            for edge in graph.edges.iter().filter(|edge| edge.source == caller) {
+                let target = match graph.nodes.iter().find(|node| node.name == edge.target) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                if is_component(target) || target.external_contract.is_some() {
+                    if !calls.iter().any(|(call, _)| call.name == target.name) {
+                        calls.push((target, edge.connection_type));
+                    }
+                } else if !visited.contains(&target.name.as_str()) {
+                    visited.push(&target.name);
+                    pending.push(&target.name);
+                }
+            }
+        }
+
+        calls
+    }
+
+    /// Returns the workspace written in the Structurizr DSL.
+    pub fn to_dsl(&self) -> String {
+        // The system is named after a contract more often than not, so its identifier can't be derived from its name
+        let system_id = "system";
+        let mut model = String::new();
+        let mut relationships = String::new();
+        let mut external_systems: Vec<String> = Vec::new();
+        let mut classes: Vec<(ScopeType, ActionType)> = Vec::new();
+
+        // Push the contracts & their components
+        model.push_str(&format!(
+            "\t\t{} = softwareSystem {} {{\n",
+            system_id,
+            quote(&self.name)
+        ));
+        for container in &self.containers {
+            let container_id = identifier(&container.name);
+            model.push_str(&format!(
+                "\t\t\t{} = container {} {} {} {{\n",
+                container_id,
+                quote(&container.name),
+                quote("Smart contract"),
+                quote("Rust")
+            ));
+
+            let components = container.graph.nodes.iter().filter(|node| is_component(node));
+            for function in components.clone() {
+                let scope: ScopeType = function.clone().into();
+                let action: ActionType = function.clone().into();
+                let class = format!("{}-{}", scope.as_ref(), action.as_ref());
+                if !classes.contains(&(scope, action)) {
+                    classes.push((scope, action));
+                }
+                model.push_str(&format!(
+                    "\t\t\t\t{}_{} = component {} {} {} {{\n\t\t\t\t\ttags {} {} {}\n\t\t\t\t}}\n",
+                    container_id,
+                    identifier(&function.name),
+                    quote(&function.name),
+                    quote(function.signature.as_deref().unwrap_or_default()),
+                    quote("Contract method"),
+                    quote(scope.as_ref()),
+                    quote(action.as_ref()),
+                    quote(&class)
+                ));
+            }
+            model.push_str("\t\t\t}\n");
+
+            // Push the relationships of the components, merging the methods called on the same external contract
+            for function in components {
+                let source_id = format!("{}_{}", container_id, identifier(&function.name));
+                let mut external_calls: Vec<(String, Vec<&str>)> = Vec::new();
+                for (target, connection_type) in StructurizrWorkspace::resolve_calls(&container.graph, &function.name) {
+                    match &target.external_contract {
+                        Some(external_contract) => {
+                            match external_calls.iter_mut().find(|(name, _)| name == external_contract) {
+                                Some((_, methods)) => methods.push(&target.name),
+                                None => external_calls.push((external_contract.clone(), vec![&target.name])),
+                            }
+                        }
+                        None => relationships.push_str(&format!(
+                            "\t\t{} -> {}_{} {} {}\n",
+                            source_id,
+                            container_id,
+                            identifier(&target.name),
+                            quote(connection_type.get_str("Label").unwrap()),
+                            quote("Rust")
+                        )),
+                    }
+                }
+                for (external_contract, methods) in external_calls {
+                    if !external_systems.contains(&external_contract) {
+                        external_systems.push(external_contract.clone());
+                    }
+                    relationships.push_str(&format!(
+                        "\t\t{} -> {} {} {}\n",
+                        source_id,
+                        identifier(&external_contract),
+                        quote(&format!("Calls {}", methods.join(", "))),
+                        quote("Cross-contract call")
+                    ));
+                }
+            }
+        }
+        model.push_str("\t\t}\n");
+
+        // Push the external contracts
+        for external_contract in &external_systems {
+            model.push_str(&format!(
+                "\t\t{} = softwareSystem {} {} {{\n\t\t\ttags \"External\"\n\t\t}}\n",
+                identifier(external_contract),
+                quote(external_contract),
+                quote("External contract")
+            ));
+        }
+
+        // Push the views, one component view per contract
+        let mut views = format!(
+            "\t\tsystemContext {} \"SystemContext\" {{\n\t\t\tinclude *\n\t\t\tautolayout {}\n\t\t}}\n\t\tcontainer {} \"Containers\" {{\n\t\t\tinclude *\n\t\t\tautolayout {}\n\t\t}}\n",
+            system_id, self.direction, system_id, self.direction
+        );
+        for container in &self.containers {
+            let container_id = identifier(&container.name);
+            views.push_str(&format!(
+                "\t\tcomponent {} \"{}_components\" {{\n\t\t\tinclude *\n\t\t\tautolayout {}\n\t\t}}\n",
+                container_id, container_id, self.direction
+            ));
+        }
+
+        // Push the styles of the classes, matching the theme of the diagrams
+        views.push_str("\t\tstyles {\n");
+        views.push_str("\t\t\telement \"External\" {\n\t\t\t\tbackground #999999\n\t\t\t\tcolor #ffffff\n\t\t\t}\n");
+        for (scope, action) in classes {
+            let style = self.theme.node_style(&scope, &action);
+            let node = Node {
+                name: String::new(),
+                label: None,
+                scope,
+                action,
+                connections: Vec::new(),
+            };
+            views.push_str(&format!(
+                "\t\t\telement \"{}-{}\" {{\n\t\t\t\tshape {}\n",
+                scope.as_ref(),
+                action.as_ref(),
+                structurizr_shape(resolve_shape(&node, Some(&self.theme)))
+            ));
+            if let Some(fill) = &style.fill {
+                views.push_str(&format!("\t\t\t\tbackground {}\n", fill));
+            }
+            if let Some(stroke) = &style.stroke {
+                views.push_str(&format!("\t\t\t\tstroke {}\n", stroke));
+            }
+            if let Some(color) = &style.color {
+                views.push_str(&format!("\t\t\t\tcolor {}\n", color));
+            }
+            if style.stroke_dasharray.is_some() {
+                views.push_str("\t\t\t\tborder dashed\n");
+            }
+            views.push_str("\t\t\t}\n");
+        }
+        views.push_str("\t\t}\n");
+
+        format!(
+            "workspace {} {} {{\n\n\tmodel {{\n{}\n{}\t}}\n\n\tviews {{\n{}\t}}\n\n}}\n",
+            quote(&self.name),
+            quote("Generated by web3d"),
+            model,
+            relationships,
+            views
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scanner_syn::contract_descriptor::ContractDescriptorMeta;
+
+    #[test]
+    fn it_models_contracts_as_containers() {
+        let transfer = FunctionInfo {
+            name: "ft_transfer".to_string(),
+            is_public: true,
+            is_out_of_contract_scope: true,
+            external_contract: Some("ext_ft".to_string()),
+            ..Default::default()
+        };
+        let helper = FunctionInfo {
+            name: "pay".to_string(),
+            inner_calls: Some(vec![transfer.clone()]),
+            ..Default::default()
+        };
+        let get_balance = FunctionInfo {
+            name: "get_balance".to_string(),
+            is_public: true,
+            is_view: true,
+            signature: Some("fn get_balance(&self) -> U128".to_string()),
+            ..Default::default()
+        };
+        let withdraw = FunctionInfo {
+            name: "withdraw".to_string(),
+            is_public: true,
+            is_mutable: true,
+            inner_calls: Some(vec![get_balance.clone(), helper.clone()]),
+            ..Default::default()
+        };
+        let contract = ContractInfo {
+            contract_metadata: vec![ContractDescriptorMeta {
+                fns: vec![get_balance, withdraw, helper],
+                ext_contract_fns: vec![transfer],
+                connections: None,
+                tokens: None,
+            }],
+        };
+
+        let mut workspace = StructurizrWorkspace::new("Vault", FlowDirection::LR, None);
+        workspace.add_contract("vault-contract", &contract);
+        let dsl = workspace.to_dsl();
+
+        assert!(dsl.contains("\t\tsystem = softwareSystem \"Vault\" {\n\t\t\tvault_contract = container \"vault-contract\" \"Smart contract\" \"Rust\" {\n"));
+        assert!(dsl.contains(
+            "\t\t\t\tvault_contract_get_balance = component \"get_balance\" \"fn get_balance(&self) -> U128\" \"Contract method\" {\n\t\t\t\t\ttags \"Public\" \"View\" \"Public-View\"\n"
+        ));
+        // The private helper isn't a component, its call is attributed to the public method
+        assert!(!dsl.contains("vault_contract_pay"));
+        assert!(dsl.contains("\t\tvault_contract_withdraw -> vault_contract_get_balance \"calls\" \"Rust\"\n"));
+        assert!(dsl.contains("\t\tvault_contract_withdraw -> ext_ft \"Calls ft_transfer\" \"Cross-contract call\"\n"));
+        assert!(dsl.contains("\t\text_ft = softwareSystem \"ext_ft\" \"External contract\" {\n\t\t\ttags \"External\"\n"));
+        assert!(dsl.contains("\t\tcomponent vault_contract \"vault_contract_components\" {\n\t\t\tinclude *\n\t\t\tautolayout lr\n"));
+        assert!(dsl.contains("\t\t\telement \"Public-View\" {\n\t\t\t\tshape Circle\n\t\t\t\tbackground "));
+    }
+}
@@ -0,0 +1,554 @@
+use crate::objects::connection::Connection;
+use crate::objects::node::{ActionType, Node, ScopeType};
+use crate::syntax::flow_chart::{ArrowDirection, ArrowType, LineType, Shape};
+use crate::syntax::{
+    resolve_line_and_arrow, resolve_shape, CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile,
+};
+use crate::theme::{Style, Theme};
+use enum_as_inner::EnumAsInner;
+
+/// An enum representation of either a [NodeConfig] or an [EdgeConfig].
+#[derive(EnumAsInner, Debug)]
+pub enum ObjectConfig<'a> {
+    NodeConfig(NodeConfig<'a>),
+    EdgeConfig(EdgeConfig),
+}
+
+/// A struct representing the possible attributes for a [Node].
+#[derive(Debug)]
+pub struct NodeConfig<'a> {
+    /// The key that will be assigned to this node
+    pub id: &'a str,
+    /// The text to be displayed within the node
+    pub label: &'a str,
+    /// The shape of the node, part of its class
+    pub shape: Shape,
+    /// The scope of the node, part of its class
+    pub scope: ScopeType,
+    /// The action of the node, part of its class
+    pub action: ActionType,
+}
+
+/// A struct representing the possible attributes for a [Connection].
+#[derive(Debug)]
+pub struct EdgeConfig {
+    /// The enum representation of the type of line you want
+    pub line_type: LineType,
+    /// The enum representation of the type of arrow you want
+    pub arrow_type: ArrowType,
+    /// The enum representation of the direction you want the arrows to point
+    pub arrow_direction: ArrowDirection,
+    /// An optional text to be displayed on the line
+    pub label: Option<String>,
+    /// The colour, width & dash pattern of the line
+    pub style: Style,
+}
+
+/// This is the root struct for an individual [D2](https://d2lang.com) diagram.
+///
+/// Every kind of node is a D2 class holding its shape & style, groups become containers & the edges are appended after the
+/// nodes, referring to the nodes by their path through the containers (e.g. `module_a.function_b`).
+pub struct D2 {
+    /// The `direction` of the diagram
+    direction: &'static str,
+    /// The node declarations & containers
+    data: String,
+    /// The edge statements
+    edges: String,
+    /// The statements making nodes clickable
+    clicks: String,
+    /// The ID & path of every node declared so far, as a node is only declared once
+    paths: Vec<(String, String)>,
+    /// The classes of the nodes declared so far, in the order they were first used
+    classes: Vec<(Shape, ScopeType, ActionType)>,
+    /// The keys of the containers currently open
+    containers: Vec<String>,
+    /// The path of the node last added, the source of the next edge
+    last_path: Option<String>,
+    /// The edge waiting for its target node
+    pending_edge: Option<EdgeConfig>,
+    /// The theme applied when the schema is returned
+    theme: Option<Theme>,
+}
+
+/// Quotes a D2 string, escaping the characters that would end it early.
+///
+/// # Arguments
+///
+/// * `text` - The text to quote
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Converts a CSS length (e.g. `2px`) or dash pattern (e.g. `4 4`) into the number D2 expects.
+///
+/// # Arguments
+///
+/// * `value` - The CSS value
+fn number(value: &str) -> String {
+    value
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches("px")
+        .to_string()
+}
+
+/// Returns the name of the class of the nodes with the supplied scope & action, e.g. `Public-View`.
+///
+/// # Arguments
+///
+/// * `scope` - The scope of the node
+/// * `action` - The action of the node
+fn class_name(
+    scope: &ScopeType,
+    action: &ActionType,
+) -> String {
+    format!("{}-{}", scope.as_ref(), action.as_ref())
+}
+
+impl D2 {
+    /// Pushes a statement on a line of its own, indented by the number of containers it is nested in.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to push
+    fn push_statement(
+        &mut self,
+        statement: &str,
+    ) {
+        for _ in 0..self.containers.len() {
+            self.data.push('\t');
+        }
+        self.data.push_str(statement);
+        self.data.push('\n');
+    }
+
+    /// Returns the fields of a class, i.e. the D2 shape & the style of the node.
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` - The shape of the node
+    /// * `style` - The style of the node
+    fn class_fields(
+        shape: Shape,
+        style: &Style,
+    ) -> Vec<String> {
+        let (d2_shape, mut style_fields) = match shape {
+            Shape::Circle => ("circle", vec![]),
+            Shape::DoubleCircle => ("circle", vec!["double-border: true".to_string()]),
+            Shape::Hexagon => ("hexagon", vec![]),
+            Shape::Rectangle => ("rectangle", vec![]),
+            Shape::Flag => ("step", vec![]),
+            Shape::Rounded => ("rectangle", vec!["border-radius: 8".to_string()]),
+            Shape::Stadium => ("oval", vec![]),
+            Shape::Subroutine => ("rectangle", vec!["double-border: true".to_string()]),
+            Shape::Cylinder => ("cylinder", vec![]),
+            Shape::Rhombus => ("diamond", vec![]),
+            Shape::Parallelogram
+            | Shape::ParallelogramAlt
+            | Shape::Trapezoid
+            | Shape::TrapezoidAlt => ("parallelogram", vec![]),
+        };
+
+        if let Some(fill) = &style.fill {
+            style_fields.push(format!("fill: {}", quote(fill)));
+        }
+        if let Some(stroke) = &style.stroke {
+            style_fields.push(format!("stroke: {}", quote(stroke)));
+        }
+        if let Some(stroke_width) = &style.stroke_width {
+            style_fields.push(format!("stroke-width: {}", number(stroke_width)));
+        }
+        if let Some(stroke_dasharray) = &style.stroke_dasharray {
+            style_fields.push(format!("stroke-dash: {}", number(stroke_dasharray)));
+        }
+        if let Some(color) = &style.color {
+            style_fields.push(format!("font-color: {}", quote(color)));
+        }
+
+        let mut fields = vec![format!("shape: {}", d2_shape)];
+        fields.extend(style_fields.into_iter().map(|field| format!("style.{}", field)));
+        fields
+    }
+
+    /// Returns the fields of an edge, e.g. `style.stroke: "#333"; target-arrowhead.shape: circle`.
+    ///
+    /// # Arguments
+    ///
+    /// * `edge_config` - The attributes of the edge
+    fn edge_fields(edge_config: &EdgeConfig) -> Vec<String> {
+        let style = &edge_config.style;
+        let mut fields = Vec::new();
+
+        if let Some(stroke) = &style.stroke {
+            fields.push(format!("style.stroke: {}", quote(stroke)));
+        }
+        match (edge_config.line_type, &style.stroke_width) {
+            (LineType::Thick, None) => fields.push("style.stroke-width: 4".to_string()),
+            (_, Some(stroke_width)) => {
+                fields.push(format!("style.stroke-width: {}", number(stroke_width)))
+            }
+            (_, None) => {}
+        }
+        match (edge_config.line_type, &style.stroke_dasharray) {
+            (_, Some(stroke_dasharray)) => {
+                fields.push(format!("style.stroke-dash: {}", number(stroke_dasharray)))
+            }
+            (LineType::Dashed, None) => fields.push("style.stroke-dash: 3".to_string()),
+            (_, None) => {}
+        }
+        if let LineType::Invisible = edge_config.line_type {
+            fields.push("style.opacity: 0".to_string());
+        }
+
+        // The default arrowhead is a triangle, only the other types need to be set
+        let arrowhead = match edge_config.arrow_type {
+            ArrowType::Standard => None,
+            ArrowType::X => Some("cross"),
+            ArrowType::O => Some("circle"),
+        };
+        if let Some(arrowhead) = arrowhead {
+            if matches!(
+                edge_config.arrow_direction,
+                ArrowDirection::Left | ArrowDirection::BiDirectional
+            ) {
+                fields.push(format!("source-arrowhead.shape: {}", arrowhead));
+            }
+            if matches!(
+                edge_config.arrow_direction,
+                ArrowDirection::Right | ArrowDirection::BiDirectional
+            ) {
+                fields.push(format!("target-arrowhead.shape: {}", arrowhead));
+            }
+        }
+
+        fields
+    }
+}
+
+impl CoreSyntaxFunctions for D2 {
+    fn new(direction: FlowDirection) -> Self {
+        // Map the direction onto the `direction` values D2 understands
+        let direction = match direction {
+            FlowDirection::TB | FlowDirection::TD => "down",
+            FlowDirection::BT => "up",
+            FlowDirection::LR => "right",
+            FlowDirection::BL => "left",
+        };
+
+        D2 {
+            direction,
+            data: String::new(),
+            edges: String::new(),
+            clicks: String::new(),
+            paths: Vec::new(),
+            classes: Vec::new(),
+            containers: Vec::new(),
+            last_path: None,
+            pending_edge: None,
+            theme: None,
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        node_config: SyntaxConfigFile,
+    ) {
+        let node_config: NodeConfig = node_config
+            .into_d2()
+            .unwrap()
+            .into_node_config()
+            .unwrap();
+
+        // Declare the node the first time it's added, so it lands in the container it was first added in
+        let path = match self.paths.iter().find(|(id, _)| id == node_config.id) {
+            Some((_, path)) => path.clone(),
+            None => {
+                let mut path = self.containers.join(".");
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(node_config.id);
+                self.paths.push((node_config.id.to_string(), path.clone()));
+
+                let class = (node_config.shape, node_config.scope, node_config.action);
+                if !self.classes.contains(&class) {
+                    self.classes.push(class);
+                }
+                let statement = format!(
+                    "{}: {} {{class: {}}}",
+                    node_config.id,
+                    quote(node_config.label),
+                    class_name(&node_config.scope, &node_config.action)
+                );
+                self.push_statement(&statement);
+                path
+            }
+        };
+
+        // If an edge is waiting for its target, this node is it
+        match (self.pending_edge.take(), self.last_path.take()) {
+            (Some(edge_config), Some(source_path)) => {
+                let arrow = match edge_config.arrow_direction {
+                    ArrowDirection::BiDirectional => "<->",
+                    ArrowDirection::Left => "<-",
+                    ArrowDirection::Right => "->",
+                    ArrowDirection::None => "--",
+                };
+                self.edges
+                    .push_str(&format!("{} {} {}", source_path, arrow, path));
+                if let Some(label) = &edge_config.label {
+                    self.edges.push_str(&format!(": {}", quote(label)));
+                }
+                let fields = D2::edge_fields(&edge_config);
+                if !fields.is_empty() {
+                    self.edges
+                        .push_str(&format!(" {{{}}}", fields.join("; ")));
+                }
+                self.edges.push('\n');
+            }
+            _ => self.last_path = Some(path),
+        }
+    }
+
+    fn add_connection(
+        &mut self,
+        connection_config: SyntaxConfigFile,
+    ) {
+        // Unwrap the `SyntaxConfigFile` into the needed `EdgeConfig`
+        let edge_config: EdgeConfig = connection_config
+            .into_d2()
+            .unwrap()
+            .into_edge_config()
+            .unwrap();
+
+        // Keep the edge until its target node is added
+        self.pending_edge = Some(edge_config);
+    }
+
+    fn add_linebreak(
+        &mut self,
+        _num_of_indents: Option<u8>,
+    ) {
+        // Every statement is put on a line of its own & indented by its nesting, so a linebreak only ends a chain of nodes
+        self.last_path = None;
+        self.pending_edge = None;
+    }
+
+    fn add_group_start(
+        &mut self,
+        id: &str,
+        title: &str,
+    ) {
+        let statement = format!("{}: {} {{", id, quote(title));
+        self.push_statement(&statement);
+        self.containers.push(id.to_string());
+    }
+
+    fn add_group_end(&mut self) {
+        self.containers.pop();
+        self.push_statement("}");
+    }
+
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    ) {
+        // Nodes are referred to by their path, the ID itself if the node wasn't declared in a container
+        let path = self
+            .paths
+            .iter()
+            .find(|(node_id, _)| node_id == id)
+            .map(|(_, path)| path.clone())
+            .unwrap_or_else(|| id.to_string());
+
+        self.clicks
+            .push_str(&format!("{}.link: {}\n", path, quote(url)));
+        if let Some(tooltip) = tooltip {
+            self.clicks
+                .push_str(&format!("{}.tooltip: {}\n", path, quote(tooltip)));
+        }
+    }
+
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.theme = Some(theme);
+    }
+
+    fn build_node_config<'a>(
+        &self,
+        node: &'a Node,
+        id: Option<&'a str>,
+    ) -> SyntaxConfigFile<'a> {
+        SyntaxConfigFile::D2(ObjectConfig::NodeConfig(NodeConfig {
+            // If an ID was passed use it, else use the Node's name
+            id: id.unwrap_or(&node.name),
+            label: node.label(),
+            shape: resolve_shape(node, self.theme.as_ref()),
+            scope: node.scope,
+            action: node.action,
+        }))
+    }
+
+    fn build_connection_config<'a>(
+        &self,
+        connection: &'a Connection,
+        _extra_length_num: Option<u8>,
+    ) -> SyntaxConfigFile<'a> {
+        let (line_type, arrow_type, arrow_direction) =
+            resolve_line_and_arrow(&connection.connection_type);
+
+        SyntaxConfigFile::D2(ObjectConfig::EdgeConfig(EdgeConfig {
+            line_type,
+            arrow_type,
+            arrow_direction,
+            label: connection.label.clone(),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.edge_style(&connection.connection_type))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn return_schema(&self) -> String {
+        let mut schema = format!("direction: {}\n", self.direction);
+
+        if let Some(background) = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.variables.get("background"))
+        {
+            schema.push_str(&format!("style.fill: {}\n", quote(background)));
+        }
+
+        // Push a class for every kind of node declared, holding its shape & the style of the theme
+        if !self.classes.is_empty() {
+            schema.push_str("classes: {\n");
+            for (shape, scope, action) in &self.classes {
+                let style = self
+                    .theme
+                    .as_ref()
+                    .map(|theme| theme.node_style(scope, action))
+                    .unwrap_or_default();
+                schema.push_str(&format!("\t{}: {{\n", class_name(scope, action)));
+                for field in D2::class_fields(*shape, &style) {
+                    schema.push_str(&format!("\t\t{}\n", field));
+                }
+                schema.push_str("\t}\n");
+            }
+            schema.push_str("}\n");
+        }
+
+        // Push the nodes & containers, then the edges between them & finally the links
+        schema.push_str(&self.data);
+        schema.push_str(&self.edges);
+        schema.push_str(&self.clicks);
+
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::connection::ConnectionType;
+    use crate::objects::group::Group;
+
+    #[test]
+    fn it_creates_classes_containers_and_edges() {
+        let mut d2 = D2::new(FlowDirection::LR);
+        d2.set_theme(Theme::builtin("light").unwrap());
+
+        Group {
+            id: "module_a".to_string(),
+            title: "a".to_string(),
+            nodes: vec![Node {
+                name: "get_balance".to_string(),
+                label: None,
+                scope: ScopeType::Public,
+                action: ActionType::View,
+                connections: vec![],
+            }],
+            groups: vec![],
+        }
+        .traverse(&mut d2, 1);
+        d2.add_linebreak(None);
+
+        Node {
+            name: "Contract".to_string(),
+            label: None,
+            scope: ScopeType::Contract,
+            action: ActionType::None,
+            connections: vec![
+                Connection {
+                    connection_type: ConnectionType::DirectConnection,
+                    node: Node {
+                        name: "get_balance".to_string(),
+                        label: None,
+                        scope: ScopeType::Public,
+                        action: ActionType::View,
+                        connections: vec![],
+                    },
+                    label: Some("calls".to_string()),
+                    extra_length_num: None,
+                },
+                Connection {
+                    connection_type: ConnectionType::Emission,
+                    node: Node {
+                        name: "log_deposit".to_string(),
+                        label: None,
+                        scope: ScopeType::Public,
+                        action: ActionType::Event,
+                        connections: vec![],
+                    },
+                    label: None,
+                    extra_length_num: None,
+                },
+            ],
+        }
+        .traverse(&mut d2);
+        d2.add_click("get_balance", "https://example.com", Some("src/lib.rs:1-2"));
+
+        assert_eq!(
+            d2.return_schema(),
+            "direction: right
+classes: {
+	Public-View: {
+		shape: circle
+		style.fill: \"#12A5F1\"
+		style.stroke: \"#333\"
+		style.stroke-width: 2
+	}
+	Contract-None: {
+		shape: rectangle
+		style.fill: \"#C2D5E3\"
+		style.stroke: \"#333\"
+		style.stroke-width: 2
+	}
+	Public-Event: {
+		shape: step
+		style.fill: \"#FFDF80\"
+		style.stroke: \"#333\"
+		style.stroke-width: 2
+		style.stroke-dash: 4
+	}
+}
+module_a: \"a\" {
+	get_balance: \"get_balance\" {class: Public-View}
+}
+Contract: \"Contract\" {class: Contract-None}
+log_deposit: \"log_deposit\" {class: Public-Event}
+Contract -> module_a.get_balance: \"calls\" {style.stroke: \"#333\"}
+Contract -- log_deposit {style.stroke: \"#E6B800\"; style.stroke-dash: 3}
+module_a.get_balance.link: \"https://example.com\"
+module_a.get_balance.tooltip: \"src/lib.rs:1-2\"
+"
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::objects::connection::Connection;
+use crate::objects::node::Node;
+use crate::syntax::flow_chart::{ArrowDirection, ArrowType, LineType, Shape};
+use crate::syntax::{
+    resolve_line_and_arrow, resolve_shape, CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile,
+};
+use crate::theme::{Style, Theme};
+use enum_as_inner::EnumAsInner;
+
+/// An enum representation of either a [NodeConfig] or an [EdgeConfig].
+#[derive(EnumAsInner, Debug)]
+pub enum ObjectConfig<'a> {
+    NodeConfig(NodeConfig<'a>),
+    EdgeConfig(EdgeConfig),
+}
+
+/// A struct representing the possible attributes for a [Node].
+#[derive(Debug)]
+pub struct NodeConfig<'a> {
+    /// The ID that will be assigned to this node
+    pub id: &'a str,
+    /// The class name written to the `class` attribute, kept in the SVG output of Graphviz
+    pub class: Option<String>,
+    /// The shape of the node, mapped to the closest [Graphviz shape](https://graphviz.org/doc/info/shapes.html)
+    pub shape: Shape,
+    /// The text to be displayed within the node
+    pub label: &'a str,
+    /// The fill, border & text colours of the node
+    pub style: Style,
+}
+
+/// A struct representing the possible attributes for a [Connection].
+#[derive(Debug)]
+pub struct EdgeConfig {
+    /// The enum representation of the type of line you want
+    pub line_type: LineType,
+    /// The enum representation of the type of arrow you want
+    pub arrow_type: ArrowType,
+    /// The enum representation of the direction you want the arrows to point
+    pub arrow_direction: ArrowDirection,
+    /// An optional text to be displayed on the line
+    pub label: Option<String>,
+    /// The colour & width of the line
+    pub style: Style,
+}
+
+/// This is the root struct for an individual [Graphviz DOT](https://graphviz.org/doc/info/lang.html) graph.
+///
+/// DOT declares nodes & edges as separate statements, so the nodes are declared where they are first added (i.e. inside of
+/// the cluster of their group) & the edges are collected & appended after them.
+pub struct Dot {
+    /// The `rankdir` of the graph
+    rank_direction: &'static str,
+    /// The node declarations & clusters
+    data: String,
+    /// The edge statements
+    edges: String,
+    /// The statements making nodes clickable
+    clicks: String,
+    /// The IDs of the nodes declared so far, as a node is only declared once
+    declared_ids: Vec<String>,
+    /// The number of clusters currently open
+    depth: usize,
+    /// The ID of the node last added, the source of the next edge
+    last_id: Option<String>,
+    /// The edge waiting for its target node
+    pending_edge: Option<EdgeConfig>,
+    /// The theme applied when the schema is returned
+    theme: Option<Theme>,
+}
+
+/// Quotes a DOT ID, escaping the characters that would end it early.
+///
+/// # Arguments
+///
+/// * `text` - The text to quote
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Converts a CSS length (e.g. `2px`) into a Graphviz `penwidth`.
+///
+/// # Arguments
+///
+/// * `width` - The CSS length
+fn pen_width(width: &str) -> String {
+    width.trim().trim_end_matches("px").to_string()
+}
+
+/// Quotes a CSS colour as a Graphviz colour, expanding the short hex forms (`#333`, `#333c`) Graphviz doesn't accept.
+///
+/// # Arguments
+///
+/// * `colour` - The CSS colour, e.g. `#333`, `#12A5F1` or `white`
+fn colour(colour: &str) -> String {
+    let colour = colour.trim();
+    match colour.strip_prefix('#') {
+        Some(digits) if matches!(digits.len(), 3 | 4) && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            let expanded: String = digits.chars().flat_map(|c| [c, c]).collect();
+            quote(&format!("#{}", expanded))
+        }
+        _ => quote(colour),
+    }
+}
+
+impl Dot {
+    /// Pushes a statement on a line of its own, indented by the number of clusters it is nested in.
+    ///
+    /// # Arguments
+    ///
+    /// * `statement` - The statement to push
+    fn push_statement(
+        &mut self,
+        statement: &str,
+    ) {
+        self.data.push('\n');
+        for _ in 0..=self.depth {
+            self.data.push('\t');
+        }
+        self.data.push_str(statement);
+    }
+
+    /// Returns the attributes of a node as a DOT attribute list.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_config` - The attributes of the node
+    fn node_attributes(node_config: &NodeConfig) -> String {
+        let (shape, mut styles) = match node_config.shape {
+            Shape::Circle => ("circle", vec![]),
+            Shape::Hexagon => ("hexagon", vec![]),
+            Shape::Rectangle => ("box", vec![]),
+            Shape::Flag => ("cds", vec![]),
+            Shape::Rounded | Shape::Stadium => ("box", vec!["rounded"]),
+            Shape::Subroutine => ("component", vec![]),
+            Shape::Cylinder => ("cylinder", vec![]),
+            Shape::Rhombus => ("diamond", vec![]),
+            Shape::Parallelogram | Shape::ParallelogramAlt => ("parallelogram", vec![]),
+            Shape::Trapezoid => ("trapezium", vec![]),
+            Shape::TrapezoidAlt => ("invtrapezium", vec![]),
+            Shape::DoubleCircle => ("doublecircle", vec![]),
+        };
+        styles.push("filled");
+
+        let style = &node_config.style;
+        if style.stroke_dasharray.is_some() {
+            styles.push("dashed");
+        }
+
+        let mut attributes = vec![
+            format!("label={}", quote(node_config.label)),
+            format!("shape={}", shape),
+            format!("style={}", quote(&styles.join(","))),
+        ];
+        if let Some(class) = &node_config.class {
+            attributes.push(format!("class={}", quote(class)));
+        }
+        if let Some(fill) = &style.fill {
+            attributes.push(format!("fillcolor={}", colour(fill)));
+        }
+        if let Some(stroke) = &style.stroke {
+            attributes.push(format!("color={}", colour(stroke)));
+        }
+        if let Some(stroke_width) = &style.stroke_width {
+            attributes.push(format!("penwidth={}", pen_width(stroke_width)));
+        }
+        if let Some(color) = &style.color {
+            attributes.push(format!("fontcolor={}", colour(color)));
+        }
+
+        format!("[{}]", attributes.join(", "))
+    }
+
+    /// Returns the attributes of an edge as a DOT attribute list.
+    ///
+    /// # Arguments
+    ///
+    /// * `edge_config` - The attributes of the edge
+    fn edge_attributes(edge_config: &EdgeConfig) -> String {
+        let line_style = match edge_config.line_type {
+            LineType::Solid => "solid",
+            LineType::Dashed => "dashed",
+            LineType::Thick => "bold",
+            LineType::Invisible => "invis",
+        };
+        let arrow = match edge_config.arrow_type {
+            ArrowType::Standard => "normal",
+            ArrowType::X => "tee",
+            ArrowType::O => "odot",
+        };
+        let direction = match edge_config.arrow_direction {
+            ArrowDirection::BiDirectional => "both",
+            ArrowDirection::Left => "back",
+            ArrowDirection::Right => "forward",
+            ArrowDirection::None => "none",
+        };
+
+        let mut attributes = vec![
+            format!("style={}", line_style),
+            format!("dir={}", direction),
+            format!("arrowhead={}", arrow),
+            format!("arrowtail={}", arrow),
+        ];
+        if let Some(label) = &edge_config.label {
+            attributes.push(format!("label={}", quote(label)));
+        }
+        if let Some(stroke) = &edge_config.style.stroke {
+            attributes.push(format!("color={}", colour(stroke)));
+        }
+        if let Some(stroke_width) = &edge_config.style.stroke_width {
+            attributes.push(format!("penwidth={}", pen_width(stroke_width)));
+        }
+
+        format!("[{}]", attributes.join(", "))
+    }
+}
+
+impl CoreSyntaxFunctions for Dot {
+    fn new(direction: FlowDirection) -> Self {
+        // Map the direction onto the `rankdir` values Graphviz understands
+        let rank_direction = match direction {
+            FlowDirection::TB | FlowDirection::TD => "TB",
+            FlowDirection::BT => "BT",
+            FlowDirection::LR => "LR",
+            FlowDirection::BL => "RL",
+        };
+
+        Dot {
+            rank_direction,
+            data: String::new(),
+            edges: String::new(),
+            clicks: String::new(),
+            declared_ids: Vec::new(),
+            depth: 0,
+            last_id: None,
+            pending_edge: None,
+            theme: None,
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        node_config: SyntaxConfigFile,
+    ) {
+        let node_config: NodeConfig = node_config
+            .into_dot()
+            .unwrap()
+            .into_node_config()
+            .unwrap();
+
+        // Declare the node the first time it's added, so it lands in the cluster it was first added in
+        if !self.declared_ids.iter().any(|id| id == node_config.id) {
+            self.declared_ids.push(node_config.id.to_string());
+            let statement = format!(
+                "{} {};",
+                quote(node_config.id),
+                Dot::node_attributes(&node_config)
+            );
+            self.push_statement(&statement);
+        }
+
+        // If an edge is waiting for its target, this node is it
+        match (self.pending_edge.take(), self.last_id.take()) {
+            (Some(edge_config), Some(source_id)) => {
+                self.edges.push_str(&format!(
+                    "\n\t{} -> {} {};",
+                    quote(&source_id),
+                    quote(node_config.id),
+                    Dot::edge_attributes(&edge_config)
+                ));
+            }
+            _ => self.last_id = Some(node_config.id.to_string()),
+        }
+    }
+
+    fn add_connection(
+        &mut self,
+        connection_config: SyntaxConfigFile,
+    ) {
+        // Unwrap the `SyntaxConfigFile` into the needed `EdgeConfig`
+        let edge_config: EdgeConfig = connection_config
+            .into_dot()
+            .unwrap()
+            .into_edge_config()
+            .unwrap();
+
+        // Keep the edge until its target node is added
+        self.pending_edge = Some(edge_config);
+    }
+
+    fn add_linebreak(
+        &mut self,
+        _num_of_indents: Option<u8>,
+    ) {
+        // Every statement is put on a line of its own & indented by its nesting, so a linebreak only ends a chain of nodes
+        self.last_id = None;
+        self.pending_edge = None;
+    }
+
+    fn add_group_start(
+        &mut self,
+        id: &str,
+        title: &str,
+    ) {
+        // Graphviz only draws subgraphs whose name starts with `cluster`
+        let statement = format!("subgraph {} {{", quote(&format!("cluster_{}", id)));
+        self.push_statement(&statement);
+        self.depth += 1;
+        let label = format!("label={};", quote(title));
+        self.push_statement(&label);
+    }
+
+    fn add_group_end(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        self.push_statement("}");
+    }
+
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    ) {
+        // Push the link as attributes of the already declared node, opening it in a new tab
+        let mut attributes = vec![format!("URL={}", quote(url)), "target=\"_blank\"".to_string()];
+        if let Some(tooltip) = tooltip {
+            attributes.push(format!("tooltip={}", quote(tooltip)));
+        }
+        self.clicks.push_str(&format!(
+            "\n\t{} [{}];",
+            quote(id),
+            attributes.join(", ")
+        ));
+    }
+
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.theme = Some(theme);
+    }
+
+    fn build_node_config<'a>(
+        &self,
+        node: &'a Node,
+        id: Option<&'a str>,
+    ) -> SyntaxConfigFile<'a> {
+        SyntaxConfigFile::Dot(ObjectConfig::NodeConfig(NodeConfig {
+            // If an ID was passed use it, else use the Node's name
+            id: id.unwrap_or(&node.name),
+            class: Some(format!("{}-{}", node.scope.as_ref(), node.action.as_ref())),
+            shape: resolve_shape(node, self.theme.as_ref()),
+            label: node.label(),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.node_style(&node.scope, &node.action))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn build_connection_config<'a>(
+        &self,
+        connection: &'a Connection,
+        _extra_length_num: Option<u8>,
+    ) -> SyntaxConfigFile<'a> {
+        let (line_type, arrow_type, arrow_direction) =
+            resolve_line_and_arrow(&connection.connection_type);
+
+        SyntaxConfigFile::Dot(ObjectConfig::EdgeConfig(EdgeConfig {
+            line_type,
+            arrow_type,
+            arrow_direction,
+            label: connection.label.clone(),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.edge_style(&connection.connection_type))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn return_schema(&self) -> String {
+        let mut schema = String::from("digraph {");
+
+        // Push the graph wide attributes
+        schema.push_str(&format!("\n\trankdir={};", self.rank_direction));
+        if let Some(background) = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.variables.get("background"))
+        {
+            schema.push_str(&format!("\n\tbgcolor={};", colour(background)));
+        }
+        schema.push_str("\n\tnode [style=filled];");
+
+        // Push the nodes & clusters, then the edges between them & finally the links
+        schema.push_str(&self.data);
+        schema.push_str(&self.edges);
+        schema.push_str(&self.clicks);
+
+        schema.push_str("\n}\n");
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::test_node;
+    use crate::objects::connection::ConnectionType;
+    use crate::objects::group::Group;
+    use crate::objects::node::{ActionType, ScopeType};
+    use crate::objects::DiagramObject;
+
+    #[test]
+    fn it_declares_a_styled_node() {
+        let mut dot = Dot::new(FlowDirection::LR);
+        dot.set_theme(Theme::builtin("light").unwrap());
+
+        test_node("get_balance", ScopeType::Public, ActionType::View, vec![])
+            .add_object_to_schema(&mut dot, None, None);
+
+        assert_eq!(
+            dot.return_schema(),
+            "digraph {
+	rankdir=LR;
+	node [style=filled];
+	\"get_balance\" [label=\"get_balance\", shape=circle, style=\"filled\", class=\"Public-View\", fillcolor=\"#12A5F1\", color=\"#333333\", penwidth=2];
+}
+"
+        );
+    }
+
+    #[test]
+    fn it_expands_the_short_hex_colours() {
+        assert_eq!(colour("#333"), "\"#333333\"");
+        assert_eq!(colour("#f0a8"), "\"#ff00aa88\"");
+        assert_eq!(colour("#12A5F1"), "\"#12A5F1\"");
+        assert_eq!(colour("white"), "\"white\"");
+    }
+
+    #[test]
+    fn it_puts_groups_in_clusters_and_edges_after_the_nodes() {
+        let mut dot = Dot::new(FlowDirection::TD);
+
+        Group {
+            id: "module_a".to_string(),
+            title: "a \"b\"".to_string(),
+            nodes: vec![test_node("f", ScopeType::Private, ActionType::Mutation, vec![])],
+            groups: vec![],
+        }
+        .traverse(&mut dot, 1);
+        dot.add_linebreak(None);
+
+        let root = Node {
+            name: "Contract".to_string(),
+            label: None,
+            scope: ScopeType::Contract,
+            action: ActionType::None,
+            connections: vec![Connection {
+                connection_type: ConnectionType::Emission,
+                node: test_node("f", ScopeType::Private, ActionType::Mutation, vec![]),
+                label: Some("emits".to_string()),
+                extra_length_num: None,
+            }],
+        };
+        root.traverse(&mut dot);
+
+        assert_eq!(
+            dot.return_schema(),
+            "digraph {
+	rankdir=TB;
+	node [style=filled];
+	subgraph \"cluster_module_a\" {
+		label=\"a \\\"b\\\"\";
+		\"f\" [label=\"f\", shape=hexagon, style=\"filled\", class=\"Private-Mutation\"];
+	}
+	\"Contract\" [label=\"Contract\", shape=box, style=\"filled\", class=\"Contract-None\"];
+	\"Contract\" -> \"f\" [style=dashed, dir=none, arrowhead=odot, arrowtail=odot, label=\"emits\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn it_styles_edges_by_connection_type() {
+        let mut dot = Dot::new(FlowDirection::TD);
+        dot.set_theme(Theme::builtin("light").unwrap());
+
+        let root = Node {
+            name: "a".to_string(),
+            label: None,
+            scope: ScopeType::Public,
+            action: ActionType::Mutation,
+            connections: vec![Connection {
+                connection_type: ConnectionType::Transfer,
+                node: test_node("b", ScopeType::Private, ActionType::Process, vec![]),
+                label: None,
+                extra_length_num: None,
+            }],
+        };
+        root.traverse(&mut dot);
+        dot.add_click("b", "https://example.com/\"lib.rs\"", Some("src/lib.rs:1-2"));
+
+        let schema = dot.return_schema();
+        assert!(schema.contains(
+            "\"a\" -> \"b\" [style=bold, dir=forward, arrowhead=normal, arrowtail=normal, color=\"#6AA84F\""
+        ));
+        assert!(schema.contains(
+            "\"b\" [URL=\"https://example.com/\\\"lib.rs\\\"\", target=\"_blank\", tooltip=\"src/lib.rs:1-2\"];"
+        ));
+    }
+}
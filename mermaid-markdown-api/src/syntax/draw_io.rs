@@ -0,0 +1,664 @@
+use crate::objects::connection::{Connection, ConnectionType};
+use crate::objects::node::Node;
+use crate::syntax::flow_chart::{ArrowDirection, ArrowType, LineType, Shape};
+use crate::syntax::layout::{layout, LayoutGraph, Spacing};
+use crate::syntax::{
+    escape_xml, resolve_line_and_arrow, resolve_shape, CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile,
+};
+use crate::theme::{Style, Theme};
+use enum_as_inner::EnumAsInner;
+
+/// The height of a node.
+const NODE_HEIGHT: isize = 60;
+/// The space between two nodes of the same layer.
+const NODE_GAP: isize = 60;
+/// The space between two layers.
+const LAYER_GAP: isize = 100;
+/// The space between the border of a group & its content.
+const GROUP_PADDING: isize = 20;
+/// The height of the title bar of a group.
+const GROUP_HEADER: isize = 30;
+
+/// An enum representation of either a [NodeConfig] or an [EdgeConfig].
+#[derive(EnumAsInner, Debug)]
+pub enum ObjectConfig<'a> {
+    NodeConfig(NodeConfig<'a>),
+    EdgeConfig(EdgeConfig),
+}
+
+/// A struct representing the possible attributes for a [Node].
+#[derive(Debug)]
+pub struct NodeConfig<'a> {
+    /// The ID of the node in the diagram, the cells themselves are numbered
+    pub id: &'a str,
+    /// The text to be displayed within the node
+    pub label: &'a str,
+    /// The class of the node (e.g. `Public-View`), kept as a property of the cell
+    pub class: String,
+    /// The shape of the node
+    pub shape: Shape,
+    /// The fill, border & text colours of the node
+    pub style: Style,
+}
+
+/// A struct representing the possible attributes for a [Connection].
+#[derive(Debug)]
+pub struct EdgeConfig {
+    /// The kind of connection, kept as a property of the cell
+    pub connection_type: ConnectionType,
+    /// The enum representation of the type of line you want
+    pub line_type: LineType,
+    /// The enum representation of the type of arrow you want
+    pub arrow_type: ArrowType,
+    /// The enum representation of the direction you want the arrows to point
+    pub arrow_direction: ArrowDirection,
+    /// An optional text to be displayed on the line
+    pub label: Option<String>,
+    /// The colour, width & dash pattern of the line
+    pub style: Style,
+}
+
+/// A node waiting to be laid out.
+struct Vertex {
+    id: String,
+    label: String,
+    class: String,
+    shape: Shape,
+    style: Style,
+    /// The index of the innermost group the node was declared in
+    group: Option<usize>,
+}
+
+/// A group waiting to be laid out.
+struct Container {
+    id: String,
+    title: String,
+    /// The index of the enclosing group
+    parent: Option<usize>,
+}
+
+/// A rectangle, which may lie left of or above the origin until the diagram is moved into view.
+#[derive(Clone, Copy, Default)]
+struct Bounds {
+    x: isize,
+    y: isize,
+    width: isize,
+    height: isize,
+}
+
+/// This is the root struct for an individual [draw.io](https://www.drawio.com) diagram, written as uncompressed mxGraph XML.
+///
+/// The positions of the nodes are only known once the whole graph has been added, so the nodes, groups & edges are collected
+/// & laid out in layers by the layout shared with the other syntax's placing their nodes when the schema is returned.
+pub struct DrawIo {
+    /// `true` if the layers are laid out from left to right rather than from top to bottom
+    horizontal: bool,
+    /// `true` if the layers are laid out against the reading direction (i.e. bottom to top or right to left)
+    reversed: bool,
+    vertices: Vec<Vertex>,
+    containers: Vec<Container>,
+    /// The groups currently open
+    open_containers: Vec<usize>,
+    /// The source vertex, target vertex & attributes of every edge
+    edges: Vec<(usize, usize, EdgeConfig)>,
+    /// The ID, URL & tooltip of the clickable nodes
+    links: Vec<(String, String, Option<String>)>,
+    /// The vertex last added, the source of the next edge
+    last_vertex: Option<usize>,
+    /// The edge waiting for its target node
+    pending_edge: Option<EdgeConfig>,
+    /// The theme applied when the schema is returned
+    theme: Option<Theme>,
+}
+
+/// Converts a CSS length (e.g. `2px`) into a number.
+///
+/// # Arguments
+///
+/// * `width` - The CSS length
+fn number(width: &str) -> String {
+    width.trim().trim_end_matches("px").to_string()
+}
+
+impl DrawIo {
+    /// Returns the mxGraph style of a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex` - The node
+    fn vertex_style(vertex: &Vertex) -> String {
+        let shape = match vertex.shape {
+            Shape::Rectangle => "rounded=0;",
+            Shape::Rounded => "rounded=1;",
+            Shape::Stadium => "rounded=1;arcSize=50;",
+            Shape::Circle => "ellipse;",
+            Shape::DoubleCircle => "ellipse;shape=doubleEllipse;",
+            Shape::Hexagon => "shape=hexagon;perimeter=hexagonPerimeter2;",
+            Shape::Flag => "shape=step;perimeter=stepPerimeter;",
+            Shape::Subroutine => "shape=process;",
+            Shape::Cylinder => "shape=cylinder3;",
+            Shape::Rhombus => "rhombus;",
+            Shape::Parallelogram => "shape=parallelogram;perimeter=parallelogramPerimeter;",
+            Shape::ParallelogramAlt => {
+                "shape=parallelogram;perimeter=parallelogramPerimeter;flipH=1;"
+            }
+            Shape::Trapezoid => "shape=trapezoid;perimeter=trapezoidPerimeter;",
+            Shape::TrapezoidAlt => "shape=trapezoid;perimeter=trapezoidPerimeter;flipV=1;",
+        };
+        let mut style = format!("{}whiteSpace=wrap;html=1;", shape);
+
+        let theme_style = &vertex.style;
+        if let Some(fill) = &theme_style.fill {
+            style.push_str(&format!("fillColor={};", fill));
+        }
+        if let Some(stroke) = &theme_style.stroke {
+            style.push_str(&format!("strokeColor={};", stroke));
+        }
+        if let Some(stroke_width) = &theme_style.stroke_width {
+            style.push_str(&format!("strokeWidth={};", number(stroke_width)));
+        }
+        if theme_style.stroke_dasharray.is_some() {
+            style.push_str("dashed=1;");
+        }
+        if let Some(color) = &theme_style.color {
+            style.push_str(&format!("fontColor={};", color));
+        }
+
+        style
+    }
+
+    /// Returns the mxGraph style of an edge.
+    ///
+    /// # Arguments
+    ///
+    /// * `edge_config` - The attributes of the edge
+    fn edge_style(edge_config: &EdgeConfig) -> String {
+        let mut style = String::from("edgeStyle=orthogonalEdgeStyle;rounded=1;html=1;");
+
+        let (arrow, fill) = match edge_config.arrow_type {
+            ArrowType::Standard => ("classic", 1),
+            ArrowType::X => ("cross", 0),
+            ArrowType::O => ("oval", 0),
+        };
+        let (start, end) = match edge_config.arrow_direction {
+            ArrowDirection::BiDirectional => (arrow, arrow),
+            ArrowDirection::Left => (arrow, "none"),
+            ArrowDirection::Right => ("none", arrow),
+            ArrowDirection::None => ("none", "none"),
+        };
+        style.push_str(&format!(
+            "startArrow={};startFill={};endArrow={};endFill={};",
+            start, fill, end, fill
+        ));
+
+        let theme_style = &edge_config.style;
+        if let Some(stroke) = &theme_style.stroke {
+            style.push_str(&format!("strokeColor={};", stroke));
+        }
+        match (edge_config.line_type, &theme_style.stroke_width) {
+            (_, Some(stroke_width)) => {
+                style.push_str(&format!("strokeWidth={};", number(stroke_width)))
+            }
+            (LineType::Thick, None) => style.push_str("strokeWidth=3;"),
+            (_, None) => {}
+        }
+        if matches!(edge_config.line_type, LineType::Dashed) || theme_style.stroke_dasharray.is_some() {
+            style.push_str("dashed=1;");
+        }
+        if let LineType::Invisible = edge_config.line_type {
+            style.push_str("opacity=0;textOpacity=0;");
+        }
+
+        style
+    }
+
+    /// Returns the size of a node, wide enough for its label.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex` - The node
+    fn vertex_size(vertex: &Vertex) -> (isize, isize) {
+        let width = (vertex.label.chars().count() as isize * 8 + 40).max(120);
+        match vertex.shape {
+            // Round shapes would squash the label if they weren't as tall as they are wide
+            Shape::Circle | Shape::DoubleCircle => (width, width),
+            _ => (width, NODE_HEIGHT),
+        }
+    }
+
+    /// Returns the path of groups enclosing a node, outermost first.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The innermost group of the node
+    fn group_path(
+        &self,
+        group: Option<usize>,
+    ) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut current = group;
+        while let Some(index) = current {
+            path.insert(0, index);
+            current = self.containers[index].parent;
+        }
+        path
+    }
+
+    /// Places every node & group with the layered layout shared by the syntax's placing their nodes, returning their bounds.
+    fn layout(&self) -> (Vec<Bounds>, Vec<Option<Bounds>>) {
+        let sizes: Vec<(isize, isize)> = self.vertices.iter().map(DrawIo::vertex_size).collect();
+        let graph = LayoutGraph {
+            sizes: sizes.iter().map(|(width, height)| (*width as f64, *height as f64)).collect(),
+            group_paths: self.vertices.iter().map(|vertex| self.group_path(vertex.group)).collect(),
+            edges: self.edges.iter().map(|(source, target, _)| (*source, *target)).collect(),
+        };
+        let spacing = Spacing {
+            node_gap: NODE_GAP as f64,
+            layer_gap: LAYER_GAP as f64,
+            group_padding: GROUP_PADDING as f64,
+            group_header: GROUP_HEADER as f64,
+        };
+        let layout = layout(&graph, &spacing, self.horizontal, self.reversed);
+
+        let vertex_bounds = layout
+            .centres
+            .iter()
+            .zip(sizes)
+            .map(|((cx, cy), (width, height))| Bounds {
+                x: (cx - width as f64 / 2.0).round() as isize,
+                y: (cy - height as f64 / 2.0).round() as isize,
+                width,
+                height,
+            })
+            .collect();
+        let mut container_bounds: Vec<Option<Bounds>> = layout
+            .group_bounds
+            .iter()
+            .map(|bounds| {
+                bounds.map(|(left, top, right, bottom)| Bounds {
+                    x: left.round() as isize,
+                    y: top.round() as isize,
+                    width: (right - left).round() as isize,
+                    height: (bottom - top).round() as isize,
+                })
+            })
+            .collect();
+        container_bounds.resize(self.containers.len(), None);
+        (vertex_bounds, container_bounds)
+    }
+
+    /// Returns the ID of the cell a node or group is placed in & its bounds relative to that cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - The group the cell is placed in, if any
+    /// * `bounds` - The absolute bounds of the cell
+    /// * `container_bounds` - The absolute bounds of the groups
+    fn relative_to_parent(
+        &self,
+        parent: Option<usize>,
+        bounds: Bounds,
+        container_bounds: &[Option<Bounds>],
+    ) -> (String, Bounds) {
+        match parent.and_then(|index| container_bounds[index].map(|b| (index, b))) {
+            Some((index, parent_bounds)) => (
+                format!("group_{}", self.containers[index].id),
+                Bounds {
+                    x: bounds.x - parent_bounds.x,
+                    y: bounds.y - parent_bounds.y,
+                    ..bounds
+                },
+            ),
+            None => ("1".to_string(), bounds),
+        }
+    }
+}
+
+impl CoreSyntaxFunctions for DrawIo {
+    fn new(direction: FlowDirection) -> Self {
+        let (horizontal, reversed) = match direction {
+            FlowDirection::TB | FlowDirection::TD => (false, false),
+            FlowDirection::BT => (false, true),
+            FlowDirection::LR => (true, false),
+            FlowDirection::BL => (true, true),
+        };
+
+        DrawIo {
+            horizontal,
+            reversed,
+            vertices: Vec::new(),
+            containers: Vec::new(),
+            open_containers: Vec::new(),
+            edges: Vec::new(),
+            links: Vec::new(),
+            last_vertex: None,
+            pending_edge: None,
+            theme: None,
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        node_config: SyntaxConfigFile,
+    ) {
+        let node_config: NodeConfig = node_config
+            .into_draw_io()
+            .unwrap()
+            .into_node_config()
+            .unwrap();
+
+        // Keep the node the first time it's added, so it lands in the group it was first added in
+        let index = match self
+            .vertices
+            .iter()
+            .position(|vertex| vertex.id == node_config.id)
+        {
+            Some(index) => index,
+            None => {
+                self.vertices.push(Vertex {
+                    id: node_config.id.to_string(),
+                    label: node_config.label.to_string(),
+                    class: node_config.class,
+                    shape: node_config.shape,
+                    style: node_config.style,
+                    group: self.open_containers.last().copied(),
+                });
+                self.vertices.len() - 1
+            }
+        };
+
+        // If an edge is waiting for its target, this node is it
+        match (self.pending_edge.take(), self.last_vertex.take()) {
+            (Some(edge_config), Some(source)) => self.edges.push((source, index, edge_config)),
+            _ => self.last_vertex = Some(index),
+        }
+    }
+
+    fn add_connection(
+        &mut self,
+        connection_config: SyntaxConfigFile,
+    ) {
+        // Unwrap the `SyntaxConfigFile` into the needed `EdgeConfig`
+        let edge_config: EdgeConfig = connection_config
+            .into_draw_io()
+            .unwrap()
+            .into_edge_config()
+            .unwrap();
+
+        // Keep the edge until its target node is added
+        self.pending_edge = Some(edge_config);
+    }
+
+    fn add_linebreak(
+        &mut self,
+        _num_of_indents: Option<u8>,
+    ) {
+        // The cells are laid out when the schema is returned, so a linebreak only ends a chain of nodes
+        self.last_vertex = None;
+        self.pending_edge = None;
+    }
+
+    fn add_group_start(
+        &mut self,
+        id: &str,
+        title: &str,
+    ) {
+        self.containers.push(Container {
+            id: id.to_string(),
+            title: title.to_string(),
+            parent: self.open_containers.last().copied(),
+        });
+        self.open_containers.push(self.containers.len() - 1);
+    }
+
+    fn add_group_end(&mut self) {
+        self.open_containers.pop();
+    }
+
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    ) {
+        self.links
+            .push((id.to_string(), url.to_string(), tooltip.map(str::to_string)));
+    }
+
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.theme = Some(theme);
+    }
+
+    fn build_node_config<'a>(
+        &self,
+        node: &'a Node,
+        id: Option<&'a str>,
+    ) -> SyntaxConfigFile<'a> {
+        SyntaxConfigFile::DrawIo(ObjectConfig::NodeConfig(NodeConfig {
+            // If an ID was passed use it, else use the Node's name
+            id: id.unwrap_or(&node.name),
+            label: node.label(),
+            class: format!("{}-{}", node.scope.as_ref(), node.action.as_ref()),
+            shape: resolve_shape(node, self.theme.as_ref()),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.node_style(&node.scope, &node.action))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn build_connection_config<'a>(
+        &self,
+        connection: &'a Connection,
+        _extra_length_num: Option<u8>,
+    ) -> SyntaxConfigFile<'a> {
+        let (line_type, arrow_type, arrow_direction) =
+            resolve_line_and_arrow(&connection.connection_type);
+
+        SyntaxConfigFile::DrawIo(ObjectConfig::EdgeConfig(EdgeConfig {
+            connection_type: connection.connection_type,
+            line_type,
+            arrow_type,
+            arrow_direction,
+            label: connection.label.clone(),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.edge_style(&connection.connection_type))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn return_schema(&self) -> String {
+        let (mut vertex_bounds, mut container_bounds) = self.layout();
+
+        // Move the diagram into view, as the groups reach above & left of the first nodes
+        let left = vertex_bounds
+            .iter()
+            .chain(container_bounds.iter().flatten())
+            .map(|bounds| bounds.x)
+            .min()
+            .unwrap_or_default();
+        let top = vertex_bounds
+            .iter()
+            .chain(container_bounds.iter().flatten())
+            .map(|bounds| bounds.y)
+            .min()
+            .unwrap_or_default();
+        for bounds in vertex_bounds
+            .iter_mut()
+            .chain(container_bounds.iter_mut().flatten())
+        {
+            bounds.x -= left;
+            bounds.y -= top;
+        }
+
+        let background = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.variables.get("background"))
+            .map(|background| format!(" background=\"{}\"", escape_xml(background)))
+            .unwrap_or_default();
+        let mut schema = format!(
+            "<mxfile host=\"web3d\">\n\t<diagram id=\"web3d\" name=\"Contract\">\n\t\t<mxGraphModel grid=\"1\" gridSize=\"10\" guides=\"1\" tooltips=\"1\" connect=\"1\" arrows=\"1\" fold=\"1\" page=\"0\" math=\"0\" shadow=\"0\"{}>\n\t\t\t<root>\n\t\t\t\t<mxCell id=\"0\"/>\n\t\t\t\t<mxCell id=\"1\" parent=\"0\"/>\n",
+            background
+        );
+
+        // Push the groups before their content, as draw.io requires a parent to be declared first
+        for (index, container) in self.containers.iter().enumerate() {
+            let bounds = match container_bounds[index] {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+            let (parent, bounds) = self.relative_to_parent(container.parent, bounds, &container_bounds);
+            schema.push_str(&format!(
+                "\t\t\t\t<mxCell id=\"group_{}\" value=\"{}\" style=\"swimlane;startSize={};html=1;fillColor=none;\" vertex=\"1\" parent=\"{}\">\n",
+                escape_xml(&container.id),
+                escape_xml(&container.title),
+                GROUP_HEADER,
+                parent
+            ));
+            push_geometry(&mut schema, bounds);
+            schema.push_str("\t\t\t\t</mxCell>\n");
+        }
+
+        // Push the nodes, keeping their class & link as properties of the cell
+        for (index, vertex) in self.vertices.iter().enumerate() {
+            let (parent, bounds) = self.relative_to_parent(vertex.group, vertex_bounds[index], &container_bounds);
+            let mut properties = format!(
+                "label=\"{}\" class=\"{}\"",
+                escape_xml(&vertex.label),
+                escape_xml(&vertex.class)
+            );
+            if let Some((_, url, tooltip)) = self.links.iter().find(|(id, _, _)| *id == vertex.id) {
+                properties.push_str(&format!(" link=\"{}\"", escape_xml(url)));
+                if let Some(tooltip) = tooltip {
+                    properties.push_str(&format!(" tooltip=\"{}\"", escape_xml(tooltip)));
+                }
+            }
+            schema.push_str(&format!(
+                "\t\t\t\t<object id=\"node_{}\" {}>\n\t\t\t\t\t<mxCell style=\"{}\" vertex=\"1\" parent=\"{}\">\n",
+                index,
+                properties,
+                DrawIo::vertex_style(vertex),
+                parent
+            ));
+            push_geometry(&mut schema, bounds);
+            schema.push_str("\t\t\t\t\t</mxCell>\n\t\t\t\t</object>\n");
+        }
+
+        // Push the edges, keeping their connection type as a property of the cell
+        for (index, (source, target, edge_config)) in self.edges.iter().enumerate() {
+            schema.push_str(&format!(
+                "\t\t\t\t<object id=\"edge_{}\" label=\"{}\" connection_type=\"{}\">\n\t\t\t\t\t<mxCell style=\"{}\" edge=\"1\" parent=\"1\" source=\"node_{}\" target=\"node_{}\">\n\t\t\t\t\t\t<mxGeometry relative=\"1\" as=\"geometry\"/>\n\t\t\t\t\t</mxCell>\n\t\t\t\t</object>\n",
+                index,
+                escape_xml(edge_config.label.as_deref().unwrap_or_default()),
+                edge_config.connection_type.as_ref(),
+                DrawIo::edge_style(edge_config),
+                source,
+                target
+            ));
+        }
+
+        schema.push_str("\t\t\t</root>\n\t\t</mxGraphModel>\n\t</diagram>\n</mxfile>\n");
+        schema
+    }
+}
+
+/// Appends the geometry of a cell.
+///
+/// # Arguments
+///
+/// * `schema` - The document being written
+/// * `bounds` - The bounds of the cell, relative to its parent
+fn push_geometry(
+    schema: &mut String,
+    bounds: Bounds,
+) {
+    schema.push_str(&format!(
+        "\t\t\t\t\t\t<mxGeometry x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" as=\"geometry\"/>\n",
+        bounds.x, bounds.y, bounds.width, bounds.height
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::test_node;
+    use crate::objects::group::Group;
+    use crate::objects::node::{ActionType, ScopeType};
+
+    fn call(node: Node) -> Connection {
+        Connection {
+            connection_type: ConnectionType::DirectConnection,
+            node,
+            label: Some("calls".to_string()),
+            extra_length_num: None,
+        }
+    }
+
+    #[test]
+    fn it_lays_the_nodes_out_in_layers() {
+        let mut draw_io = DrawIo::new(FlowDirection::TD);
+        draw_io.set_theme(Theme::builtin("light").unwrap());
+
+        // `c` is called by both `a` & `b`, so it's placed below the deeper of the two, under `a`
+        let c = || test_node("c", ScopeType::Public, ActionType::Process, vec![]);
+        test_node(
+            "a",
+            ScopeType::Public,
+            ActionType::Mutation,
+            vec![
+                call(test_node("b", ScopeType::Public, ActionType::Mutation, vec![call(c())])),
+                call(c()),
+            ],
+        )
+        .traverse(&mut draw_io);
+        draw_io.add_click("c", "https://example.com/?a=1&b=2", None);
+
+        let schema = draw_io.return_schema();
+        assert!(schema.contains(
+            "<object id=\"node_0\" label=\"a\" class=\"Public-Mutation\">\n\t\t\t\t\t<mxCell style=\"shape=hexagon;perimeter=hexagonPerimeter2;whiteSpace=wrap;html=1;fillColor=#12A5F1;strokeColor=#333;strokeWidth=2;\" vertex=\"1\" parent=\"1\">\n\t\t\t\t\t\t<mxGeometry x=\"45\" y=\"0\" width=\"120\" height=\"60\" as=\"geometry\"/>"
+        ));
+        assert!(schema.contains("<mxGeometry x=\"0\" y=\"160\" width=\"120\" height=\"60\" as=\"geometry\"/>"));
+        assert!(schema.contains(
+            "<object id=\"node_2\" label=\"c\" class=\"Public-Process\" link=\"https://example.com/?a=1&amp;b=2\">"
+        ));
+        assert!(schema.contains("<mxGeometry x=\"45\" y=\"320\" width=\"120\" height=\"60\" as=\"geometry\"/>"));
+        assert!(schema.contains(
+            "<object id=\"edge_0\" label=\"calls\" connection_type=\"DirectConnection\">\n\t\t\t\t\t<mxCell style=\"edgeStyle=orthogonalEdgeStyle;rounded=1;html=1;startArrow=none;startFill=1;endArrow=classic;endFill=1;strokeColor=#333;\" edge=\"1\" parent=\"1\" source=\"node_0\" target=\"node_1\">"
+        ));
+    }
+
+    #[test]
+    fn it_places_grouped_nodes_in_swimlanes() {
+        let mut draw_io = DrawIo::new(FlowDirection::LR);
+
+        Group {
+            id: "module_a".to_string(),
+            title: "a".to_string(),
+            nodes: vec![test_node("f", ScopeType::Public, ActionType::View, vec![])],
+            groups: vec![Group {
+                id: "module_a_b".to_string(),
+                title: "b".to_string(),
+                nodes: vec![test_node("g", ScopeType::Public, ActionType::View, vec![])],
+                groups: vec![],
+            }],
+        }
+        .traverse(&mut draw_io, 1);
+
+        let schema = draw_io.return_schema();
+        // `f` & `g` share the first layer, so `g` is placed below `f` & its group nested in the group of `f`
+        assert!(schema.contains(
+            "<mxCell id=\"group_module_a\" value=\"a\" style=\"swimlane;startSize=30;html=1;fillColor=none;\" vertex=\"1\" parent=\"1\">\n\t\t\t\t\t\t<mxGeometry x=\"0\" y=\"0\" width=\"200\" height=\"440\" as=\"geometry\"/>"
+        ));
+        assert!(schema.contains(
+            "<mxCell id=\"group_module_a_b\" value=\"b\" style=\"swimlane;startSize=30;html=1;fillColor=none;\" vertex=\"1\" parent=\"group_module_a\">\n\t\t\t\t\t\t<mxGeometry x=\"20\" y=\"230\" width=\"160\" height=\"190\" as=\"geometry\"/>"
+        ));
+        assert!(schema.contains("vertex=\"1\" parent=\"group_module_a_b\">\n\t\t\t\t\t\t<mxGeometry x=\"20\" y=\"50\" width=\"120\" height=\"120\" as=\"geometry\"/>"));
+    }
+}
@@ -1,12 +1,19 @@
-use crate::objects::connection::{Connection, ConnectionType};
-use crate::objects::node::{ActionType, Node};
-use crate::syntax::{CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile};
+use crate::objects::connection::Connection;
+use crate::objects::legend::Legend;
+use crate::objects::node::Node;
+use crate::syntax::{
+    resolve_line_and_arrow, resolve_shape, CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile,
+};
+use crate::theme::Theme;
 use enum_as_inner::EnumAsInner;
+use serde::{Deserialize, Serialize};
 use strum::EnumProperty;
 use strum_macros::EnumProperty;
 
 /// The various different shapes enabled by this syntax.
-#[derive(EnumProperty, Debug)]
+///
+/// Themes refer to the shapes by their variant name (e.g. `Cylinder`).
+#[derive(AsRefStr, EnumProperty, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Shape {
     #[strum(props(Left = "((", Right = "))"))]
     Circle,
@@ -16,15 +23,44 @@ pub enum Shape {
     Rectangle,
     #[strum(props(Left = ">", Right = "]"))]
     Flag,
+    /// A rectangle with rounded corners
+    #[strum(props(Left = "(", Right = ")"))]
+    Rounded,
+    #[strum(props(Left = "([", Right = "])"))]
+    Stadium,
+    /// A rectangle with double vertical edges
+    #[strum(props(Left = "[[", Right = "]]"))]
+    Subroutine,
+    /// A database
+    #[strum(props(Left = "[(", Right = ")]"))]
+    Cylinder,
+    /// A decision
+    #[strum(props(Left = "{", Right = "}"))]
+    Rhombus,
+    #[strum(props(Left = "[/", Right = "/]"))]
+    Parallelogram,
+    #[strum(props(Left = "[\\", Right = "\\]"))]
+    ParallelogramAlt,
+    #[strum(props(Left = "[/", Right = "\\]"))]
+    Trapezoid,
+    #[strum(props(Left = "[\\", Right = "/]"))]
+    TrapezoidAlt,
+    #[strum(props(Left = "(((", Right = ")))"))]
+    DoubleCircle,
 }
 
 /// The various different line types enabled by this syntax.
-#[derive(EnumProperty, Debug)]
+#[derive(AsRefStr, EnumProperty, Debug, Clone, Copy)]
 pub enum LineType {
     #[strum(props(Complete = "--", Addition = "-"))]
     Solid,
     #[strum(props(Left = "-.", Right = ".-", Addition = "."))]
     Dashed,
+    #[strum(props(Complete = "==", Addition = "="))]
+    Thick,
+    /// A link that is not drawn but still affects the layout, it can't have arrows or a label
+    #[strum(props(Complete = "~~~", Addition = "~"))]
+    Invisible,
 }
 
 /// The various different arrow types enabled by this syntax.
@@ -78,12 +114,22 @@ pub struct ConnectionConfig {
     pub arrow_direction: ArrowDirection,
     /// An optional amount of additional flags to increase line length
     pub extra_length_num: Option<u8>,
+    /// An optional text to be displayed on the line
+    pub label: Option<String>,
+    /// An optional [Mermaid.js link style](https://mermaid-js.github.io/mermaid/#/flowchart?id=styling-links) (e.g. `stroke:#ff3,stroke-width:4px`)
+    pub style: Option<String>,
 }
 
 /// This is the root struct for an individual flow chart.
 pub struct FlowChart {
     /// This is the data location of the string data for the markdown
     data: String,
+    /// The number of connections added so far, used as the index of the next connection
+    link_count: usize,
+    /// The styles of the styled connections & the indexes of the connections using each of them
+    link_styles: Vec<(String, Vec<usize>)>,
+    /// The theme applied when the schema is returned
+    theme: Option<Theme>,
 }
 
 impl FlowChart {
@@ -139,6 +185,71 @@ impl FlowChart {
         }
     }
 
+    /// Creates a [Mermaid.js Thick Line](https://mermaid-js.github.io/mermaid/#/flowchart?id=thick-link) with the supplied attributes & appends it to the current data of the flow chart struct (i.e. `self.data`).
+    ///
+    /// # Arguments
+    ///
+    /// * `extra_length_num` - An optional amount of additional flags to increase line length
+    fn add_thick_line(
+        &mut self,
+        extra_length_num: Option<u8>,
+    ) {
+        // Push the main portion of the thick line flag
+        self.data
+            .push_str(LineType::Thick.get_str("Complete").unwrap());
+
+        // Check to see if an additional length was requested
+        if let Some(extra_length_num) = extra_length_num {
+            // Range over `extra_length_num` to add the appropriate number of length additions
+            for _ in 0..extra_length_num {
+                // Add in a `=`
+                self.data
+                    .push_str(LineType::Thick.get_str("Addition").unwrap());
+            }
+        }
+    }
+
+    /// Creates a [Mermaid.js Invisible Line](https://mermaid-js.github.io/mermaid/#/flowchart?id=an-invisible-link) with the supplied attributes & appends it to the current data of the flow chart struct (i.e. `self.data`).
+    ///
+    /// # Arguments
+    ///
+    /// * `extra_length_num` - An optional amount of additional flags to increase line length
+    fn add_invisible_line(
+        &mut self,
+        extra_length_num: Option<u8>,
+    ) {
+        // Push the main portion of the invisible line flag
+        self.data
+            .push_str(LineType::Invisible.get_str("Complete").unwrap());
+
+        // Check to see if an additional length was requested
+        if let Some(extra_length_num) = extra_length_num {
+            // Range over `extra_length_num` to add the appropriate number of length additions
+            for _ in 0..extra_length_num {
+                // Add in a `~`
+                self.data
+                    .push_str(LineType::Invisible.get_str("Addition").unwrap());
+            }
+        }
+    }
+
+    /// Closes a line that doesn't end in an arrow, as solid & thick lines need a third flag to be valid (i.e. `---`, `===`).
+    ///
+    /// # Arguments
+    ///
+    /// * `line_type` - The enum representation of the line type being closed
+    fn close_line(
+        &mut self,
+        line_type: LineType,
+    ) {
+        match line_type {
+            LineType::Solid | LineType::Thick => self
+                .data
+                .push_str(line_type.get_str("Addition").unwrap()),
+            LineType::Dashed | LineType::Invisible => {}
+        }
+    }
+
     /// Creates a [Mermaid.js Connection Line with no arrow](https://mermaid-js.github.io/mermaid/#/flowchart?id=links-between-nodes) with the supplied attributes & appends it to the current data of the flow chart struct (i.e. `self.data`).
     ///
     /// # Arguments
@@ -153,6 +264,8 @@ impl FlowChart {
         match line_type {
             LineType::Solid => self.add_solid_line(extra_length_num),
             LineType::Dashed => self.add_dashed_line(extra_length_num),
+            LineType::Thick => self.add_thick_line(extra_length_num),
+            LineType::Invisible => self.add_invisible_line(extra_length_num),
         }
     }
 
@@ -172,6 +285,67 @@ impl FlowChart {
             .push_str(arrow_type.get_str(arrow_direction.as_ref()).unwrap())
     }
 
+    /// Returns the [Legend] as Markdown tables describing the shape & style of each class & the line & style of each connection kind,
+    /// for when the legend is kept out of the diagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `legend` - The legend to describe
+    pub fn legend_markdown(
+        &self,
+        legend: &Legend,
+    ) -> String {
+        let mut markdown = String::new();
+
+        if !legend.nodes.is_empty() {
+            markdown.push_str("| Node | Shape | Style |\n| ---- | ----- | ----- |\n");
+            for node in &legend.nodes {
+                let style = self
+                    .theme
+                    .as_ref()
+                    .and_then(|theme| theme.node_style(&node.scope, &node.action).to_css())
+                    .map(|css| format!("`{}`", css))
+                    .unwrap_or_default();
+                markdown.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    node.name,
+                    self.get_shape_from_node(node).as_ref(),
+                    style
+                ));
+            }
+        }
+
+        if !legend.connections.is_empty() {
+            if !markdown.is_empty() {
+                markdown.push('\n');
+            }
+            markdown.push_str("| Connection | Line | Arrow | Style |\n| ---------- | ---- | ----- | ----- |\n");
+            for connection in &legend.connections {
+                let (line_type, arrow_type, arrow_direction) =
+                    self.get_line_and_arrow_type_from_connection(connection);
+                let arrow = match arrow_direction {
+                    ArrowDirection::None => "None",
+                    _ => arrow_type.as_ref(),
+                };
+                let style = self
+                    .theme
+                    .as_ref()
+                    .and_then(|theme| theme.edge_style(&connection.connection_type).to_css())
+                    .map(|css| format!("`{}`", css))
+                    .unwrap_or_default();
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    connection.connection_type.get_str("Label").unwrap(),
+                    line_type.as_ref(),
+                    arrow,
+                    style
+                ));
+            }
+        }
+
+        markdown
+    }
+
     /// Determines which [Shape] to put in a [NodeConfig].
     ///
     /// # Arguments
@@ -181,13 +355,7 @@ impl FlowChart {
         &self,
         node: &Node,
     ) -> Shape {
-        match node.action {
-            ActionType::Mutation => Shape::Hexagon,
-            ActionType::View => Shape::Circle,
-            ActionType::Process => Shape::Rectangle,
-            ActionType::Event => Shape::Flag,
-            ActionType::None => Shape::Rectangle,
-        }
+        resolve_shape(node, self.theme.as_ref())
     }
 
     /// Determines which [LineType] & [ArrowType] to put in a [ConnectionConfig].
@@ -199,17 +367,7 @@ impl FlowChart {
         &self,
         connection: &Connection,
     ) -> (LineType, ArrowType, ArrowDirection) {
-        match connection.connection_type {
-            ConnectionType::DirectConnection => {
-                (LineType::Solid, ArrowType::Standard, ArrowDirection::Right)
-            }
-            ConnectionType::CrossContractConnection => {
-                (LineType::Dashed, ArrowType::Standard, ArrowDirection::Right)
-            }
-            ConnectionType::Emission => {
-                (LineType::Dashed, ArrowType::O, ArrowDirection::None)
-            }
-        }
+        resolve_line_and_arrow(&connection.connection_type)
     }
 }
 
@@ -222,7 +380,12 @@ impl CoreSyntaxFunctions for FlowChart {
         schema_root.push_str(direction.as_ref());
 
         // Instantiate `FlowChart`
-        let mut result = FlowChart { data: schema_root };
+        let mut result = FlowChart {
+            data: schema_root,
+            link_count: 0,
+            link_styles: Vec::new(),
+            theme: None,
+        };
 
         // Add a new line
         result.add_linebreak(None);
@@ -247,8 +410,12 @@ impl CoreSyntaxFunctions for FlowChart {
         self.data
             .push_str(node_config.shape.get_str("Left").unwrap());
 
-        // Push the inner text
-        self.data.push_str(node_config.inner_text);
+        // Push the inner text, quoting renamed labels so their brackets & punctuation aren't read as syntax
+        if !node_config.inner_text.contains(|c| "()[]{}<>\"|;#&".contains(c)) {
+            self.data.push_str(node_config.inner_text);
+        } else {
+            self.data.push_str(&format!("\"{}\"", node_config.inner_text.replace('"', "#quot;")));
+        }
 
         // Push the left shape flag
         self.data
@@ -276,8 +443,13 @@ impl CoreSyntaxFunctions for FlowChart {
         self.data.push(' ');
 
         // Depending on the arrow direction wanted make calls to `self.add_arrow` & `self.add_line`
-        match connection_config.arrow_direction {
-            ArrowDirection::BiDirectional => {
+        match (connection_config.line_type, connection_config.arrow_direction) {
+            // Invisible lines can't have any arrows
+            (LineType::Invisible, _) => self.add_line(
+                connection_config.line_type,
+                connection_config.extra_length_num,
+            ),
+            (_, ArrowDirection::BiDirectional) => {
                 self.add_arrow(connection_config.arrow_type, ArrowDirection::Left);
                 self.add_line(
                     connection_config.line_type,
@@ -285,33 +457,47 @@ impl CoreSyntaxFunctions for FlowChart {
                 );
                 self.add_arrow(connection_config.arrow_type, ArrowDirection::Right)
             }
-            ArrowDirection::Left => {
-                self.add_arrow(
-                    connection_config.arrow_type,
-                    connection_config.arrow_direction,
-                );
+            (_, ArrowDirection::Left) => {
+                self.add_arrow(connection_config.arrow_type, ArrowDirection::Left);
                 self.add_line(
                     connection_config.line_type,
                     connection_config.extra_length_num,
-                )
+                );
+                self.close_line(connection_config.line_type);
             }
-            ArrowDirection::Right => {
+            (_, ArrowDirection::Right) => {
                 self.add_line(
                     connection_config.line_type,
                     connection_config.extra_length_num,
                 );
-                self.add_arrow(
-                    connection_config.arrow_type,
-                    connection_config.arrow_direction,
-                )
+                self.add_arrow(connection_config.arrow_type, ArrowDirection::Right)
             }
-            ArrowDirection::None => {
+            (_, ArrowDirection::None) => {
                 self.add_line(
                     connection_config.line_type,
                     connection_config.extra_length_num,
                 );
+                self.close_line(connection_config.line_type);
+            }
+        }
+
+        // If a label was passed push it between pipes, invisible lines can't display one
+        if let Some(label) = connection_config.label {
+            if !matches!(connection_config.line_type, LineType::Invisible) {
+                self.data.push('|');
+                self.data.push_str(&label.replace('|', "/"));
+                self.data.push('|');
+            }
+        }
+
+        // If a style was passed keep this connection's index so it's referenced by the `linkStyle` statement of that style
+        if let Some(style) = connection_config.style {
+            match self.link_styles.iter_mut().find(|(s, _)| *s == style) {
+                Some((_, indexes)) => indexes.push(self.link_count),
+                None => self.link_styles.push((style, vec![self.link_count])),
             }
         }
+        self.link_count += 1;
 
         // Push a trailing space
         self.data.push(' ');
@@ -334,6 +520,61 @@ impl CoreSyntaxFunctions for FlowChart {
         }
     }
 
+    fn add_group_start(
+        &mut self,
+        id: &str,
+        title: &str,
+    ) {
+        // Push the subgraph keyword
+        self.data.push_str("subgraph ");
+
+        // Push the ID
+        self.data.push_str(id);
+
+        // Push the quoted title
+        self.data.push_str(" [\"");
+        self.data.push_str(title);
+        self.data.push_str("\"]");
+    }
+
+    fn add_group_end(&mut self) {
+        // Push the subgraph closing keyword
+        self.data.push_str("end");
+    }
+
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    ) {
+        // Push the click keyword & the ID
+        self.data.push_str("click ");
+        self.data.push_str(id);
+
+        // Push the quoted URL, quotes would end the string early so they are percent-encoded
+        self.data.push_str(" href \"");
+        self.data.push_str(&url.replace('"', "%22"));
+        self.data.push('"');
+
+        // If a tooltip was passed push it quoted as well
+        if let Some(tooltip) = tooltip {
+            self.data.push_str(" \"");
+            self.data.push_str(&tooltip.replace('"', "'"));
+            self.data.push('"');
+        }
+
+        // Open the link in a new tab
+        self.data.push_str(" _blank");
+    }
+
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.theme = Some(theme);
+    }
+
     fn build_node_config<'a>(
         &self,
         node: &'a Node,
@@ -346,7 +587,7 @@ impl CoreSyntaxFunctions for FlowChart {
                 id,
                 class: Some(format!("{}-{}",node.scope.as_ref(),node.action.as_ref())),
                 shape: self.get_shape_from_node(node),
-                inner_text: &node.name,
+                inner_text: node.label(),
             }))
         } else {
             // Else use the Node's name
@@ -354,7 +595,7 @@ impl CoreSyntaxFunctions for FlowChart {
                 id: &node.name,
                 class: Some(format!("{}-{}",node.scope.as_ref(),node.action.as_ref())),
                 shape: self.get_shape_from_node(node),
-                inner_text: &node.name,
+                inner_text: node.label(),
             }))
         }
     }
@@ -372,17 +613,49 @@ impl CoreSyntaxFunctions for FlowChart {
             arrow_type,
             arrow_direction,
             extra_length_num,
+            label: connection.label.clone(),
+            style: self
+                .theme
+                .as_ref()
+                .and_then(|theme| theme.edge_style(&connection.connection_type).to_css()),
         }))
     }
 
     fn return_schema(&self) -> String {
-        self.data.clone()
+        let mut schema = String::new();
+
+        // Prepend the directive selecting the Mermaid.js theme & its variables
+        if let Some(init_directive) = self.theme.as_ref().and_then(Theme::init_directive) {
+            schema.push_str(&init_directive);
+            schema.push('\n');
+        }
+
+        schema.push_str(&self.data);
+
+        // Append a `linkStyle` statement for each style, listing the indexes of the connections using it
+        for (style, indexes) in &self.link_styles {
+            let indexes: Vec<String> = indexes.iter().map(|index| index.to_string()).collect();
+            schema.push_str(&format!("linkStyle {} {};", indexes.join(","), style));
+            schema.push_str("\n\t");
+        }
+
+        // Append a `classDef` statement for every class the theme styles
+        if let Some(theme) = &self.theme {
+            for (class, style) in theme.class_styles() {
+                schema.push_str(&format!("classDef {} {};", class, style));
+                schema.push_str("\n\t");
+            }
+        }
+
+        schema
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::objects::connection::ConnectionType;
+    use crate::objects::node::{ActionType, ScopeType};
 
     #[test]
     fn it_creates_a_circle() {
@@ -495,6 +768,8 @@ mod tests {
                 arrow_type: ArrowType::Standard,
                 arrow_direction: ArrowDirection::Right,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -538,6 +813,8 @@ mod tests {
                 arrow_type: ArrowType::Standard,
                 arrow_direction: ArrowDirection::None,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -581,6 +858,8 @@ mod tests {
                 arrow_type: ArrowType::Standard,
                 arrow_direction: ArrowDirection::Left,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -624,6 +903,8 @@ mod tests {
                 arrow_type: ArrowType::Standard,
                 arrow_direction: ArrowDirection::BiDirectional,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -667,6 +948,8 @@ mod tests {
                 arrow_type: ArrowType::O,
                 arrow_direction: ArrowDirection::Right,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -710,6 +993,8 @@ mod tests {
                 arrow_type: ArrowType::O,
                 arrow_direction: ArrowDirection::Left,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -753,6 +1038,8 @@ mod tests {
                 arrow_type: ArrowType::O,
                 arrow_direction: ArrowDirection::BiDirectional,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -796,6 +1083,8 @@ mod tests {
                 arrow_type: ArrowType::X,
                 arrow_direction: ArrowDirection::Right,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -839,6 +1128,8 @@ mod tests {
                 arrow_type: ArrowType::X,
                 arrow_direction: ArrowDirection::Left,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -882,6 +1173,8 @@ mod tests {
                 arrow_type: ArrowType::X,
                 arrow_direction: ArrowDirection::BiDirectional,
                 extra_length_num: None,
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -925,6 +1218,8 @@ mod tests {
                 arrow_type: ArrowType::X,
                 arrow_direction: ArrowDirection::BiDirectional,
                 extra_length_num: Some(1),
+                label: None,
+                style: None,
             }));
 
         // Add the line to check afterwards
@@ -946,4 +1241,281 @@ mod tests {
 
         assert_eq!(flow_chart.data, expected);
     }
+
+    #[test]
+    fn it_creates_a_subgraph() {
+        // Instantiate the flow chart
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+        flow_chart.add_group_start("impl_Contract", "Contract");
+        flow_chart.add_linebreak(Some(2));
+
+        let node_config = SyntaxConfigFile::FlowChart(ObjectConfig::NodeConfig(NodeConfig {
+            id: "A",
+            class: None,
+            shape: Shape::Circle,
+            inner_text: "inner text",
+        }));
+
+        // Add the node inside of the subgraph
+        flow_chart.add_node(node_config);
+
+        flow_chart.add_linebreak(None);
+        flow_chart.add_group_end();
+
+        // The string we are expecting
+        let expected = r#"flowchart TD
+	subgraph impl_Contract ["Contract"]
+		A((inner text))
+	end"#;
+
+        assert_eq!(flow_chart.data, expected);
+    }
+
+    #[test]
+    fn it_adds_a_solid_line_with_right_arrow_and_label() {
+        // Instantiate the flow chart
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+        let node_config = SyntaxConfigFile::FlowChart(ObjectConfig::NodeConfig(NodeConfig {
+            id: "A",
+            class: None,
+            shape: Shape::Hexagon,
+            inner_text: "inner text",
+        }));
+
+        // Add the beginning node
+        flow_chart.add_node(node_config);
+
+        let connection_config =
+            SyntaxConfigFile::FlowChart(ObjectConfig::ConnectionConfig(ConnectionConfig {
+                line_type: LineType::Solid,
+                arrow_type: ArrowType::Standard,
+                arrow_direction: ArrowDirection::Right,
+                extra_length_num: None,
+                label: Some("calls".to_string()),
+                style: None,
+            }));
+
+        // Add the line to check afterwards
+        flow_chart.add_connection(connection_config);
+
+        let node_config = SyntaxConfigFile::FlowChart(ObjectConfig::NodeConfig(NodeConfig {
+            id: "B",
+            class: None,
+            shape: Shape::Hexagon,
+            inner_text: "inner text",
+        }));
+
+        // Add the trailing node
+        flow_chart.add_node(node_config);
+
+        // The string we are expecting
+        let expected = r"flowchart TD
+	A{{inner text}} -->|calls| B{{inner text}}";
+
+        assert_eq!(flow_chart.data, expected);
+    }
+
+    #[test]
+    fn it_adds_a_thick_line_with_no_arrow() {
+        // Instantiate the flow chart
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+        let connection_config =
+            SyntaxConfigFile::FlowChart(ObjectConfig::ConnectionConfig(ConnectionConfig {
+                line_type: LineType::Thick,
+                arrow_type: ArrowType::Standard,
+                arrow_direction: ArrowDirection::None,
+                extra_length_num: Some(1),
+                label: None,
+                style: None,
+            }));
+
+        // Add the line to check afterwards
+        flow_chart.add_connection(connection_config);
+
+        // The string we are expecting
+        let expected = "flowchart TD\n\t ==== ";
+
+        assert_eq!(flow_chart.data, expected);
+    }
+
+    #[test]
+    fn it_adds_an_invisible_line_without_arrows_or_label() {
+        // Instantiate the flow chart
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+        let connection_config =
+            SyntaxConfigFile::FlowChart(ObjectConfig::ConnectionConfig(ConnectionConfig {
+                line_type: LineType::Invisible,
+                arrow_type: ArrowType::Standard,
+                arrow_direction: ArrowDirection::BiDirectional,
+                extra_length_num: None,
+                label: Some("ignored".to_string()),
+                style: None,
+            }));
+
+        // Add the line to check afterwards
+        flow_chart.add_connection(connection_config);
+
+        // The string we are expecting
+        let expected = "flowchart TD\n\t ~~~ ";
+
+        assert_eq!(flow_chart.data, expected);
+    }
+
+    #[test]
+    fn it_groups_link_styles_by_connection_index() {
+        // Instantiate the flow chart
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+        for style in [
+            None,
+            Some("stroke:#ff3,stroke-width:4px".to_string()),
+            Some("stroke:#333".to_string()),
+            Some("stroke:#ff3,stroke-width:4px".to_string()),
+        ] {
+            let connection_config =
+                SyntaxConfigFile::FlowChart(ObjectConfig::ConnectionConfig(ConnectionConfig {
+                    line_type: LineType::Thick,
+                    arrow_type: ArrowType::Standard,
+                    arrow_direction: ArrowDirection::Right,
+                    extra_length_num: None,
+                    label: None,
+                    style,
+                }));
+
+            flow_chart.add_connection(connection_config);
+            flow_chart.add_linebreak(None);
+        }
+
+        // The string we are expecting
+        let expected = "flowchart TD\n\t ==> \n\t ==> \n\t ==> \n\t ==> \n\tlinkStyle 1,3 stroke:#ff3,stroke-width:4px;\n\tlinkStyle 2 stroke:#333;\n\t";
+
+        assert_eq!(flow_chart.return_schema(), expected);
+    }
+
+    #[test]
+    fn it_describes_a_legend_as_markdown() {
+        use crate::objects::node::ScopeType;
+
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+        flow_chart.set_theme(Theme::builtin("light").unwrap());
+
+        let legend = Legend {
+            nodes: vec![Node {
+                name: "Public View".to_string(),
+                label: None,
+                scope: ScopeType::Public,
+                action: ActionType::View,
+                connections: vec![],
+            }],
+            connections: vec![Connection {
+                connection_type: ConnectionType::Emission,
+                node: Node {
+                    name: "function".to_string(),
+                    label: None,
+                    scope: ScopeType::Contract,
+                    action: ActionType::None,
+                    connections: vec![],
+                },
+                label: None,
+                extra_length_num: None,
+            }],
+        };
+
+        // The string we are expecting
+        let expected = "| Node | Shape | Style |\n| ---- | ----- | ----- |\n| Public View | Circle | `fill:#12A5F1,stroke:#333,stroke-width:2px` |\n\n| Connection | Line | Arrow | Style |\n| ---------- | ---- | ----- | ----- |\n| emits | Dashed | None | `stroke:#E6B800` |\n";
+
+        assert_eq!(flow_chart.legend_markdown(&legend), expected);
+    }
+
+    #[test]
+    fn it_adds_a_click_with_a_tooltip() {
+        // Instantiate the flow chart
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+        // Add the click to check afterwards
+        flow_chart.add_click(
+            "A",
+            "https://github.com/org/repo/blob/main/src/lib.rs#L3",
+            Some("src/lib.rs:3-5 \"A\""),
+        );
+
+        // The string we are expecting
+        let expected = r#"flowchart TD
+	click A href "https://github.com/org/repo/blob/main/src/lib.rs#L3" "src/lib.rs:3-5 'A'" _blank"#;
+
+        assert_eq!(flow_chart.data, expected);
+    }
+
+    #[test]
+    fn it_creates_every_shape() {
+        let shapes = [
+            (Shape::Rounded, "A(inner text)"),
+            (Shape::Stadium, "A([inner text])"),
+            (Shape::Subroutine, "A[[inner text]]"),
+            (Shape::Cylinder, "A[(inner text)]"),
+            (Shape::Rhombus, "A{inner text}"),
+            (Shape::Parallelogram, "A[/inner text/]"),
+            (Shape::ParallelogramAlt, r"A[\inner text\]"),
+            (Shape::Trapezoid, r"A[/inner text\]"),
+            (Shape::TrapezoidAlt, r"A[\inner text/]"),
+            (Shape::DoubleCircle, "A(((inner text)))"),
+        ];
+
+        for (shape, expected_node) in shapes {
+            // Instantiate the flow chart
+            let mut flow_chart = FlowChart::new(FlowDirection::TD);
+
+            let node_config = SyntaxConfigFile::FlowChart(ObjectConfig::NodeConfig(NodeConfig {
+                id: "A",
+                class: None,
+                shape,
+                inner_text: "inner text",
+            }));
+
+            // Add the node to check afterwards
+            flow_chart.add_node(node_config);
+
+            assert_eq!(flow_chart.data, format!("flowchart TD\n\t{}", expected_node));
+        }
+    }
+
+    #[test]
+    fn it_takes_the_shape_from_the_theme() {
+        let mut flow_chart = FlowChart::new(FlowDirection::TD);
+        let node = Node {
+            name: "function_a".to_string(),
+            label: None,
+            scope: ScopeType::Private,
+            action: ActionType::Mutation,
+            connections: vec![],
+        };
+        let external = Node {
+            name: "function_b".to_string(),
+            label: None,
+            scope: ScopeType::External,
+            action: ActionType::Mutation,
+            connections: vec![],
+        };
+
+        assert_eq!(flow_chart.get_shape_from_node(&node), Shape::Hexagon);
+        assert_eq!(flow_chart.get_shape_from_node(&external), Shape::Subroutine);
+
+        flow_chart.set_theme(
+            toml::from_str(
+                r#"
+                [shapes.actions]
+                Mutation = "Cylinder"
+                "#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(flow_chart.get_shape_from_node(&node), Shape::Cylinder);
+        // Scope shapes win over action shapes
+        assert_eq!(flow_chart.get_shape_from_node(&external), Shape::Subroutine);
+    }
 }
@@ -0,0 +1,609 @@
+//! A layered graph layout in the style of Sugiyama et al., used by the syntax's that position the nodes themselves.
+//!
+//! The layout runs in the classic phases: cycles are broken by reversing the edges closing them, the nodes are assigned to
+//! layers by their longest path from a source, edges spanning several layers are split by dummy nodes, the nodes of each layer
+//! are ordered by the barycenter of their neighbours to reduce crossings & finally they are positioned along their layer as
+//! close to their neighbours as the spacing allows. Groups are laid out as compound nodes: the contents of every group are laid
+//! out on their own first, then the group is placed as a single node of the layout enclosing it, so groups never overlap.
+
+/// The number of ordering sweeps, alternating between downward & upward.
+const ORDERING_SWEEPS: usize = 24;
+/// The number of positioning sweeps, alternating between downward & upward.
+const POSITIONING_SWEEPS: usize = 8;
+
+/// A point of the layout.
+type Point = (f64, f64);
+
+/// The graph to lay out.
+pub(crate) struct LayoutGraph {
+    /// The width & height of every node
+    pub sizes: Vec<(f64, f64)>,
+    /// The groups enclosing every node, outermost first
+    pub group_paths: Vec<Vec<usize>>,
+    /// The source & target node of every edge
+    pub edges: Vec<(usize, usize)>,
+}
+
+/// The spaces left between the elements of the layout.
+pub(crate) struct Spacing {
+    /// The space between two nodes of the same layer
+    pub node_gap: f64,
+    /// The space between two layers
+    pub layer_gap: f64,
+    /// The space between the border of a group & its contents
+    pub group_padding: f64,
+    /// The extra space above the contents of a group, holding its title
+    pub group_header: f64,
+}
+
+/// The positioned graph.
+pub(crate) struct Layout {
+    /// The centre of every node
+    pub centres: Vec<(f64, f64)>,
+    /// The points every edge passes through, from the centre of its source to the centre of its target
+    pub routes: Vec<Vec<(f64, f64)>>,
+    /// The bounds (left, top, right, bottom) of every group, `None` for the groups without nodes
+    pub group_bounds: Vec<Option<(f64, f64, f64, f64)>>,
+}
+
+/// A node of a layer, either a node of the graph or a dummy node carrying an edge through the layer.
+#[derive(Clone)]
+struct Slot {
+    /// The node of the graph, `None` for a dummy node
+    node: Option<usize>,
+    /// The extent of the node along the layer
+    along: f64,
+    /// The extent of the node across the layer
+    across: f64,
+}
+
+/// An item laid out within a group, either a node or a nested group.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Node(usize),
+    Group(usize),
+}
+
+/// Returns the layer of every node, having reversed the edges that close a cycle.
+///
+/// # Arguments
+///
+/// * `count` - The number of nodes
+/// * `edges` - The source & target node of every edge, self loops excluded
+fn assign_layers(
+    count: usize,
+    edges: &[(usize, usize)],
+) -> (Vec<usize>, Vec<bool>) {
+    // A depth first search reverses every edge leading back to a node still on the stack
+    let mut reversed = vec![false; edges.len()];
+    let mut state = vec![0u8; count];
+    for root in 0..count {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        state[root] = 1;
+        while let Some((node, next)) = stack.pop() {
+            match edges.iter().enumerate().skip(next).find(|(_, (source, _))| *source == node) {
+                Some((index, (_, target))) => {
+                    stack.push((node, index + 1));
+                    match state[*target] {
+                        0 => {
+                            state[*target] = 1;
+                            stack.push((*target, 0));
+                        }
+                        1 => reversed[index] = true,
+                        _ => {}
+                    }
+                }
+                None => state[node] = 2,
+            }
+        }
+    }
+
+    // Longest path layering of the acyclic graph, in topological order
+    let acyclic: Vec<(usize, usize)> = edges
+        .iter()
+        .zip(&reversed)
+        .map(|((source, target), reversed)| if *reversed { (*target, *source) } else { (*source, *target) })
+        .collect();
+    let mut incoming = vec![0usize; count];
+    for (_, target) in &acyclic {
+        incoming[*target] += 1;
+    }
+    let mut layers = vec![0usize; count];
+    let mut ready: Vec<usize> = (0..count).filter(|node| incoming[*node] == 0).rev().collect();
+    while let Some(node) = ready.pop() {
+        for (_, target) in acyclic.iter().filter(|(source, _)| *source == node) {
+            layers[*target] = layers[*target].max(layers[node] + 1);
+            incoming[*target] -= 1;
+            if incoming[*target] == 0 {
+                ready.push(*target);
+            }
+        }
+    }
+
+    (layers, reversed)
+}
+
+/// Returns the number of crossings between the edges of two adjacent layers.
+///
+/// # Arguments
+///
+/// * `segments` - The position of the upper & lower end of every edge segment between the layers
+fn count_crossings(segments: &[(usize, usize)]) -> usize {
+    let mut crossings = 0;
+    for (index, (upper_a, lower_a)) in segments.iter().enumerate() {
+        for (upper_b, lower_b) in &segments[index + 1..] {
+            if (upper_a < upper_b && lower_a > lower_b) || (upper_a > upper_b && lower_a < lower_b) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+/// Lays out a flat graph, returning the centre of every node & the route of every edge.
+///
+/// # Arguments
+///
+/// * `sizes` - The width & height of every node
+/// * `all_edges` - The source & target node of every edge
+/// * `spacing` - The spaces left between the elements of the layout
+/// * `horizontal` - `true` if the layers are stacked horizontally
+/// * `reversed` - `true` if the layers are stacked against the reading direction
+fn layered(
+    sizes: &[(f64, f64)],
+    all_edges: &[(usize, usize)],
+    spacing: &Spacing,
+    horizontal: bool,
+    reversed: bool,
+) -> (Vec<Point>, Vec<Vec<Point>>) {
+    let count = sizes.len();
+    let edges: Vec<(usize, usize)> = all_edges
+        .iter()
+        .copied()
+        .filter(|(source, target)| source != target)
+        .collect();
+    let (node_layers, reversed_edges) = assign_layers(count, &edges);
+
+    // Push the nodes into their layers
+    let layer_count = node_layers.iter().max().map(|max| max + 1).unwrap_or(0);
+    let mut layers: Vec<Vec<Slot>> = vec![Vec::new(); layer_count];
+    let mut slots_of_nodes: Vec<(usize, usize)> = Vec::with_capacity(count);
+    for node in 0..count {
+        let (width, height) = sizes[node];
+        let (along, across) = if horizontal { (height, width) } else { (width, height) };
+        let layer = node_layers[node];
+        slots_of_nodes.push((layer, layers[layer].len()));
+        layers[layer].push(Slot {
+            node: Some(node),
+            along,
+            across,
+        });
+    }
+
+    // Split the edges spanning several layers with dummy nodes
+    let mut chains: Vec<Vec<(usize, usize)>> = Vec::with_capacity(edges.len());
+    for (index, (source, target)) in edges.iter().enumerate() {
+        let (upper, lower) = if reversed_edges[index] { (*target, *source) } else { (*source, *target) };
+        let mut chain = vec![slots_of_nodes[upper]];
+        for (layer, slots) in layers.iter_mut().enumerate().take(node_layers[lower]).skip(node_layers[upper] + 1) {
+            chain.push((layer, slots.len()));
+            slots.push(Slot {
+                node: None,
+                along: 0.0,
+                across: 0.0,
+            });
+        }
+        chain.push(slots_of_nodes[lower]);
+        chains.push(chain);
+    }
+
+    // The neighbours of every slot in the layers above & below it
+    let mut uppers: Vec<Vec<Vec<usize>>> = layers.iter().map(|layer| vec![Vec::new(); layer.len()]).collect();
+    let mut lowers: Vec<Vec<Vec<usize>>> = uppers.clone();
+    for chain in &chains {
+        for pair in chain.windows(2) {
+            let ((upper_layer, upper), (lower_layer, lower)) = (pair[0], pair[1]);
+            lowers[upper_layer][upper].push(lower);
+            uppers[lower_layer][lower].push(upper);
+        }
+    }
+
+    // Order the layers by barycenter, keeping the sweep with the fewest crossings
+    let mut orders: Vec<Vec<usize>> = layers.iter().map(|layer| (0..layer.len()).collect()).collect();
+    let crossings = |orders: &Vec<Vec<usize>>| -> usize {
+        let mut positions: Vec<Vec<usize>> = layers.iter().map(|layer| vec![0; layer.len()]).collect();
+        for (layer, order) in orders.iter().enumerate() {
+            for (position, slot) in order.iter().enumerate() {
+                positions[layer][*slot] = position;
+            }
+        }
+        (0..layers.len().saturating_sub(1))
+            .map(|layer| {
+                let segments: Vec<(usize, usize)> = lowers[layer]
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(upper, targets)| {
+                        let positions = &positions;
+                        targets
+                            .iter()
+                            .map(move |lower| (positions[layer][upper], positions[layer + 1][*lower]))
+                    })
+                    .collect();
+                count_crossings(&segments)
+            })
+            .sum()
+    };
+    let mut best: Option<(usize, Vec<Vec<usize>>)> = None;
+    for sweep in 0..ORDERING_SWEEPS {
+        let downward = sweep % 2 == 0;
+        let layer_indices: Vec<usize> = if downward {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for layer in layer_indices {
+            let (fixed, neighbours) = if downward {
+                (layer - 1, &uppers[layer])
+            } else {
+                (layer + 1, &lowers[layer])
+            };
+            let mut fixed_positions = vec![0.0; layers[fixed].len()];
+            for (position, slot) in orders[fixed].iter().enumerate() {
+                fixed_positions[*slot] = position as f64;
+            }
+            let mut barycenters = vec![0.0; layers[layer].len()];
+            for (position, slot) in orders[layer].iter().enumerate() {
+                let neighbours = &neighbours[*slot];
+                barycenters[*slot] = if neighbours.is_empty() {
+                    position as f64
+                } else {
+                    neighbours.iter().map(|n| fixed_positions[*n]).sum::<f64>() / neighbours.len() as f64
+                };
+            }
+
+            orders[layer].sort_by(|a, b| barycenters[*a].partial_cmp(&barycenters[*b]).unwrap());
+        }
+        let sweep_crossings = crossings(&orders);
+        if best.as_ref().map(|(fewest, _)| sweep_crossings < *fewest).unwrap_or(true) {
+            best = Some((sweep_crossings, orders.clone()));
+        }
+    }
+    if let Some((_, best_orders)) = best {
+        orders = best_orders;
+    }
+
+    // The minimal distance between the centres of two neighbouring slots
+    let separation = |a: &Slot, b: &Slot| -> f64 {
+        let gap = if a.node.is_none() || b.node.is_none() {
+            spacing.node_gap / 2.0
+        } else {
+            spacing.node_gap
+        };
+        (a.along + b.along) / 2.0 + gap
+    };
+
+    // Pack every layer, centred on the same axis
+    let mut along: Vec<Vec<f64>> = layers.iter().map(|layer| vec![0.0; layer.len()]).collect();
+    for (layer, order) in orders.iter().enumerate() {
+        let mut position = 0.0;
+        for (index, slot) in order.iter().enumerate() {
+            if index > 0 {
+                position += separation(&layers[layer][order[index - 1]], &layers[layer][*slot]);
+            }
+            along[layer][*slot] = position;
+        }
+        for slot in order {
+            along[layer][*slot] -= position / 2.0;
+        }
+    }
+
+    // Pull every slot towards its neighbours, averaging a left & a right biased placement so the order & spacing hold
+    for sweep in 0..POSITIONING_SWEEPS {
+        let downward = sweep % 2 == 0;
+        let layer_indices: Vec<usize> = if downward {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for layer in layer_indices {
+            let (fixed, neighbours) = if downward {
+                (layer - 1, &uppers[layer])
+            } else {
+                (layer + 1, &lowers[layer])
+            };
+            let order = &orders[layer];
+            let desired: Vec<f64> = order
+                .iter()
+                .map(|slot| {
+                    let neighbours = &neighbours[*slot];
+                    if neighbours.is_empty() {
+                        along[layer][*slot]
+                    } else {
+                        neighbours.iter().map(|n| along[fixed][*n]).sum::<f64>() / neighbours.len() as f64
+                    }
+                })
+                .collect();
+
+            let mut right_biased = desired.clone();
+            for index in 1..order.len() {
+                let minimum = right_biased[index - 1] + separation(&layers[layer][order[index - 1]], &layers[layer][order[index]]);
+                right_biased[index] = right_biased[index].max(minimum);
+            }
+            let mut left_biased = desired;
+            for index in (0..order.len().saturating_sub(1)).rev() {
+                let maximum = left_biased[index + 1] - separation(&layers[layer][order[index]], &layers[layer][order[index + 1]]);
+                left_biased[index] = left_biased[index].min(maximum);
+            }
+            for (index, slot) in order.iter().enumerate() {
+                along[layer][*slot] = (right_biased[index] + left_biased[index]) / 2.0;
+            }
+        }
+    }
+
+    // Stack the layers, each as deep as its deepest slot
+    let mut across = Vec::with_capacity(layers.len());
+    let mut position = 0.0;
+    for layer in &layers {
+        let depth = layer.iter().map(|slot| slot.across).fold(0.0, f64::max);
+        across.push(position + depth / 2.0);
+        position += depth + spacing.layer_gap;
+    }
+    if reversed {
+        let extent = position - spacing.layer_gap;
+        for centre in across.iter_mut() {
+            *centre = extent - *centre;
+        }
+    }
+
+    let point = |(layer, slot): (usize, usize)| -> (f64, f64) {
+        if horizontal {
+            (across[layer], along[layer][slot])
+        } else {
+            (along[layer][slot], across[layer])
+        }
+    };
+    let centres: Vec<(f64, f64)> = slots_of_nodes.iter().map(|slot| point(*slot)).collect();
+
+    // Route the edges through their dummy nodes, self loops are left to the syntax drawing them
+    let mut chain_routes = chains.iter().zip(&reversed_edges).map(|(chain, reversed)| {
+        let mut route: Vec<(f64, f64)> = chain.iter().map(|slot| point(*slot)).collect();
+        if *reversed {
+            route.reverse();
+        }
+        route
+    });
+    let routes = all_edges
+        .iter()
+        .map(|(source, target)| {
+            if source == target {
+                vec![centres[*source], centres[*target]]
+            } else {
+                chain_routes.next().unwrap()
+            }
+        })
+        .collect();
+
+    (centres, routes)
+}
+
+/// Lays out the contents of a group, or of the whole graph for the empty scope, with their top left corner at the origin &
+/// returns their size.
+///
+/// The nested groups are laid out first & then placed as single nodes, the edges between them being routed from & to the
+/// nodes they actually connect.
+///
+/// # Arguments
+///
+/// * `graph` - The graph to lay out
+/// * `spacing` - The spaces left between the elements of the layout
+/// * `horizontal` - `true` if the layers are stacked horizontally
+/// * `reversed` - `true` if the layers are stacked against the reading direction
+/// * `scope` - The path of the group to lay out, outermost first
+/// * `result` - The layout being filled
+fn layout_scope(
+    graph: &LayoutGraph,
+    spacing: &Spacing,
+    horizontal: bool,
+    reversed: bool,
+    scope: &[usize],
+    result: &mut Layout,
+) -> (f64, f64) {
+    let depth = scope.len();
+    let in_scope = |node: usize| graph.group_paths[node].starts_with(scope);
+    let item_of_node = |node: usize| match graph.group_paths[node].get(depth) {
+        Some(group) => Item::Group(*group),
+        None => Item::Node(node),
+    };
+    let in_item = |node: usize, item: Item| in_scope(node) && item_of_node(node) == item;
+
+    // Push the nodes & nested groups of the scope, laying out the nested groups to learn their size
+    let mut items: Vec<Item> = Vec::new();
+    for node in (0..graph.sizes.len()).filter(|node| in_scope(*node)) {
+        let item = item_of_node(node);
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    let sizes: Vec<(f64, f64)> = items
+        .iter()
+        .map(|item| match item {
+            Item::Node(node) => graph.sizes[*node],
+            Item::Group(group) => {
+                let mut path = scope.to_vec();
+                path.push(*group);
+                layout_scope(graph, spacing, horizontal, reversed, &path, result)
+            }
+        })
+        .collect();
+
+    // The edges between the items, those within a nested group having been routed with it
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    let mut edge_indices: Vec<usize> = Vec::new();
+    for (index, (source, target)) in graph.edges.iter().enumerate() {
+        if !in_scope(*source) || !in_scope(*target) {
+            continue;
+        }
+        let source_item = items.iter().position(|item| *item == item_of_node(*source)).unwrap();
+        let target_item = items.iter().position(|item| *item == item_of_node(*target)).unwrap();
+        if source_item == target_item && matches!(items[source_item], Item::Group(_)) {
+            continue;
+        }
+        edges.push((source_item, target_item));
+        edge_indices.push(index);
+    }
+    let (centres, routes) = layered(&sizes, &edges, spacing, horizontal, reversed);
+
+    // Move the contents next to the origin, leaving room for the border & title of the group
+    let mut extents: Vec<(f64, f64, f64, f64)> = centres
+        .iter()
+        .zip(&sizes)
+        .map(|((cx, cy), (width, height))| (cx - width / 2.0, cy - height / 2.0, cx + width / 2.0, cy + height / 2.0))
+        .collect();
+    extents.extend(routes.iter().flatten().map(|(x, y)| (*x, *y, *x, *y)));
+    let left = extents.iter().map(|e| e.0).fold(f64::MAX, f64::min);
+    let top = extents.iter().map(|e| e.1).fold(f64::MAX, f64::min);
+    let right = extents.iter().map(|e| e.2).fold(f64::MIN, f64::max);
+    let bottom = extents.iter().map(|e| e.3).fold(f64::MIN, f64::max);
+    let (padding, header) = if scope.is_empty() {
+        (0.0, 0.0)
+    } else {
+        (spacing.group_padding, spacing.group_header)
+    };
+    let (dx, dy) = (padding - left, padding + header - top);
+
+    // Place the items, moving the contents of the nested groups along with them
+    for (index, item) in items.iter().enumerate() {
+        let (cx, cy) = (centres[index].0 + dx, centres[index].1 + dy);
+        match item {
+            Item::Node(node) => result.centres[*node] = (cx, cy),
+            Item::Group(_) => {
+                let (ox, oy) = (cx - sizes[index].0 / 2.0, cy - sizes[index].1 / 2.0);
+                let nodes: Vec<usize> = (0..graph.sizes.len()).filter(|node| in_item(*node, *item)).collect();
+                let mut nested: Vec<usize> = nodes
+                    .iter()
+                    .flat_map(|node| graph.group_paths[*node][depth..].iter().copied())
+                    .collect();
+                nested.sort_unstable();
+                nested.dedup();
+                for node in &nodes {
+                    result.centres[*node].0 += ox;
+                    result.centres[*node].1 += oy;
+                }
+                for group in nested {
+                    if let Some(bounds) = result.group_bounds[group].as_mut() {
+                        *bounds = (bounds.0 + ox, bounds.1 + oy, bounds.2 + ox, bounds.3 + oy);
+                    }
+                }
+                for (edge, (source, target)) in graph.edges.iter().enumerate() {
+                    if in_item(*source, *item) && in_item(*target, *item) {
+                        for point in result.routes[edge].iter_mut() {
+                            point.0 += ox;
+                            point.1 += oy;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Route the edges of the scope from the node they leave to the node they enter
+    for (route, index) in routes.into_iter().zip(edge_indices) {
+        let (source, target) = graph.edges[index];
+        let mut route: Vec<(f64, f64)> = route.into_iter().map(|(x, y)| (x + dx, y + dy)).collect();
+        let last = route.len() - 1;
+        route[0] = result.centres[source];
+        route[last] = result.centres[target];
+        result.routes[index] = route;
+    }
+
+    let size = (right - left + 2.0 * padding, bottom - top + 2.0 * padding + header);
+    if let Some(group) = scope.last() {
+        result.group_bounds[*group] = Some((0.0, 0.0, size.0, size.1));
+    }
+    size
+}
+
+/// Lays out a graph.
+///
+/// The layers are stacked from top to bottom, or from left to right if `horizontal` is set, & the other way around if
+/// `reversed` is set.
+///
+/// # Arguments
+///
+/// * `graph` - The graph to lay out
+/// * `spacing` - The spaces left between the elements of the layout
+/// * `horizontal` - `true` if the layers are stacked horizontally
+/// * `reversed` - `true` if the layers are stacked against the reading direction
+pub(crate) fn layout(
+    graph: &LayoutGraph,
+    spacing: &Spacing,
+    horizontal: bool,
+    reversed: bool,
+) -> Layout {
+    let group_count = graph.group_paths.iter().flatten().max().map(|max| max + 1).unwrap_or(0);
+    let mut result = Layout {
+        centres: vec![(0.0, 0.0); graph.sizes.len()],
+        routes: vec![Vec::new(); graph.edges.len()],
+        group_bounds: vec![None; group_count],
+    };
+    if !graph.sizes.is_empty() {
+        layout_scope(graph, spacing, horizontal, reversed, &[], &mut result);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACING: Spacing = Spacing {
+        node_gap: 20.0,
+        layer_gap: 40.0,
+        group_padding: 10.0,
+        group_header: 20.0,
+    };
+
+    #[test]
+    fn it_layers_the_graph_and_breaks_cycles() {
+        // a -> b -> c -> a, & a -> c
+        let graph = LayoutGraph {
+            sizes: vec![(40.0, 20.0); 3],
+            group_paths: vec![Vec::new(); 3],
+            edges: vec![(0, 1), (1, 2), (2, 0), (0, 2)],
+        };
+        let layout = layout(&graph, &SPACING, false, false);
+
+        assert_eq!(layout.centres[0].1, 10.0);
+        assert_eq!(layout.centres[1].1, 70.0);
+        assert_eq!(layout.centres[2].1, 130.0);
+        // The reversed edge still runs from c to a, through the dummy node of the second layer
+        assert_eq!(layout.routes[2].len(), 3);
+        assert_eq!(layout.routes[2][0], layout.centres[2]);
+        assert_eq!(layout.routes[2][2], layout.centres[0]);
+    }
+
+    #[test]
+    fn it_keeps_groups_together_without_overlaps() {
+        // a calls b, c & d, where b & d share a group
+        let graph = LayoutGraph {
+            sizes: vec![(40.0, 20.0); 4],
+            group_paths: vec![vec![], vec![0], vec![], vec![0]],
+            edges: vec![(0, 1), (0, 2), (0, 3)],
+        };
+        let layout = layout(&graph, &SPACING, true, false);
+
+        let (x0, y0, x1, y1) = layout.group_bounds[0].unwrap();
+        let inside = |(x, y): (f64, f64)| x - 20.0 >= x0 && x + 20.0 <= x1 && y - 10.0 >= y0 && y + 10.0 <= y1;
+        assert!(inside(layout.centres[1]));
+        assert!(inside(layout.centres[3]));
+        let (cx, cy) = layout.centres[2];
+        assert!(cx + 20.0 <= x0 || cx - 20.0 >= x1 || cy + 10.0 <= y0 || cy - 10.0 >= y1);
+        assert_eq!(x1 - x0, 60.0);
+        assert_eq!(layout.centres[0].0, 20.0);
+        assert_eq!(layout.routes[0][0], layout.centres[0]);
+        assert_eq!(layout.routes[0][1], layout.centres[1]);
+    }
+}
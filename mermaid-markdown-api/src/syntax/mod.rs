@@ -1,12 +1,21 @@
-use crate::objects::connection::Connection;
-use crate::objects::node::Node;
-use crate::syntax::flow_chart::ObjectConfig;
+use crate::objects::connection::{Connection, ConnectionType};
+use crate::objects::node::{ActionType, Node, ScopeType};
+use crate::syntax::flow_chart::{ArrowDirection, ArrowType, LineType, ObjectConfig, Shape};
+use crate::theme::Theme;
 use enum_as_inner::EnumAsInner;
+use strum::EnumProperty;
 // TODO:
+pub mod d2;
+pub mod dot;
+pub mod draw_io;
 pub mod flow_chart;
+mod layout;
+pub mod plant_uml;
+pub mod svg;
 
 /// An Enum representing the possible options for the direction of flow for the diagram.
-#[derive(AsRefStr)]
+#[derive(AsRefStr, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
 pub enum FlowDirection {
     /// top to bottom
     TB,
@@ -20,10 +29,140 @@ pub enum FlowDirection {
     LR,
 }
 
+/// Determines the [Shape] of a [Node], shared by every syntax so a node looks the same whatever it's rendered with.
+///
+/// The shape configured by the theme for the node's class wins, then the one for its scope, then the one for its action.
+///
+/// # Arguments
+///
+/// * `node` - The [Node] that is being represented
+/// * `theme` - The theme of the diagram, if any
+pub(crate) fn resolve_shape(
+    node: &Node,
+    theme: Option<&Theme>,
+) -> Shape {
+    let shapes = theme.map(|theme| &theme.shapes);
+    let class = format!("{}-{}", node.scope.as_ref(), node.action.as_ref());
+
+    let class_shape = shapes.and_then(|shapes| shapes.classes.get(&class));
+    let scope_shape = shapes
+        .and_then(|shapes| shapes.scopes.get(node.scope.as_ref()))
+        .copied()
+        .or(match node.scope {
+            ScopeType::External => Some(Shape::Subroutine),
+            ScopeType::Callback => Some(Shape::Stadium),
+            _ => None,
+        });
+    let action_shape = shapes.and_then(|shapes| shapes.actions.get(node.action.as_ref()));
+
+    class_shape
+        .copied()
+        .or(scope_shape)
+        .or_else(|| action_shape.copied())
+        .unwrap_or(match node.action {
+            ActionType::Mutation => Shape::Hexagon,
+            ActionType::View => Shape::Circle,
+            ActionType::Process => Shape::Rectangle,
+            ActionType::Event => Shape::Flag,
+            ActionType::None => Shape::Rectangle,
+        })
+}
+
+/// Determines the [LineType], [ArrowType] & [ArrowDirection] of a kind of connection, shared by every syntax.
+///
+/// # Arguments
+///
+/// * `connection_type` - The kind of connection that is being represented
+pub(crate) fn resolve_line_and_arrow(
+    connection_type: &ConnectionType,
+) -> (LineType, ArrowType, ArrowDirection) {
+    match connection_type {
+        ConnectionType::DirectConnection => {
+            (LineType::Solid, ArrowType::Standard, ArrowDirection::Right)
+        }
+        ConnectionType::CrossContractConnection => {
+            (LineType::Dashed, ArrowType::Standard, ArrowDirection::Right)
+        }
+        ConnectionType::Emission => (LineType::Dashed, ArrowType::O, ArrowDirection::None),
+        ConnectionType::Callback => (LineType::Solid, ArrowType::O, ArrowDirection::Right),
+        ConnectionType::Transfer => (LineType::Thick, ArrowType::Standard, ArrowDirection::Right),
+    }
+}
+
 /// An enum representation of the available syntax's.
 #[derive(EnumProperty, EnumAsInner, Debug)]
 pub enum SyntaxConfigFile<'a> {
     FlowChart(ObjectConfig<'a>),
+    Dot(dot::ObjectConfig<'a>),
+    PlantUml(plant_uml::ObjectConfig<'a>),
+    D2(d2::ObjectConfig<'a>),
+    DrawIo(draw_io::ObjectConfig<'a>),
+    Svg(svg::ObjectConfig<'a>),
+}
+
+/// The syntax's a diagram can be written in.
+#[derive(AsRefStr, EnumString, EnumProperty, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum Syntax {
+    /// A [Mermaid.js flowchart](https://mermaid-js.github.io/mermaid/#/flowchart), see [flow_chart::FlowChart]
+    #[default]
+    #[strum(props(Extension = "md"))]
+    Mermaid,
+    /// A [Graphviz DOT](https://graphviz.org/doc/info/lang.html) graph, see [dot::Dot]
+    #[strum(props(Extension = "dot"))]
+    Dot,
+    /// A [PlantUML](https://plantuml.com) component diagram, see [plant_uml::PlantUml]
+    #[strum(serialize = "plantuml", props(Extension = "puml"))]
+    PlantUml,
+    /// A [PlantUML sequence diagram](https://plantuml.com/sequence-diagram), see [plant_uml::PlantUml::sequence]
+    #[strum(serialize = "plantuml_sequence", props(Extension = "puml"))]
+    PlantUmlSequence,
+    /// A [D2](https://d2lang.com) diagram, see [d2::D2]
+    #[strum(props(Extension = "d2"))]
+    D2,
+    /// A [draw.io](https://www.drawio.com) diagram with its nodes already laid out, see [draw_io::DrawIo]
+    #[strum(serialize = "drawio", props(Extension = "drawio"))]
+    DrawIo,
+    /// A [GraphML](http://graphml.graphdrawing.org) document of the call graph, see [crate::graph_export::ContractGraph::to_graphml]
+    #[strum(serialize = "graphml", props(Extension = "graphml"))]
+    GraphMl,
+    /// [Cytoscape.js elements](https://js.cytoscape.org/#notation/elements-json) of the call graph, see
+    /// [crate::graph_export::ContractGraph::to_cytoscape]
+    #[strum(props(Extension = "json"))]
+    Cytoscape,
+    /// A [Structurizr DSL](https://docs.structurizr.com/dsl) workspace modelling the contract in C4, see
+    /// [crate::structurizr::StructurizrWorkspace]
+    #[strum(props(Extension = "dsl"))]
+    Structurizr,
+    /// An SVG image rendered without mermaid-cli, see [svg::Svg], as selected by [Renderer::Native]
+    #[strum(props(Extension = "svg"))]
+    Svg,
+    /// An offline HTML page embedding the [Syntax::Svg] image next to a searchable list of the methods, see
+    /// [crate::html_report::HtmlReport]
+    #[strum(props(Extension = "html"))]
+    Html,
+    /// A Markdown document of the contract, its [Syntax::Mermaid] flowchart in a fenced block followed by its public
+    /// methods, see [crate::documentation::ContractDocumentation]
+    #[strum(props(Extension = "md"))]
+    Markdown,
+}
+
+/// The tools a Mermaid.js flowchart can be rendered to an image with.
+#[derive(AsRefStr, EnumString, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum Renderer {
+    /// [mermaid-cli](https://github.com/mermaid-js/mermaid-cli), which needs Node.js & a headless Chromium
+    #[default]
+    Mmdc,
+    /// The built-in renderer, which lays the flowchart out & draws it as [Syntax::Svg]
+    Native,
+}
+
+impl Syntax {
+    /// Returns the extension of the files written in this syntax.
+    pub fn extension(&self) -> &'static str {
+        self.get_str("Extension").unwrap()
+    }
 }
 
 pub trait CoreSyntaxFunctions {
@@ -51,12 +190,12 @@ pub trait CoreSyntaxFunctions {
     /// # Examples
     ///
     /// ```
-    /// use mermaid_markdown_api::syntax::flow_chart::{FlowChart, NodeConfig, Shape};
+    /// use mermaid_markdown_api::syntax::flow_chart::{FlowChart, NodeConfig, ObjectConfig, Shape};
     /// use mermaid_markdown_api::syntax::{CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile};
     ///
     /// let mut flow_chart = FlowChart::new(FlowDirection::TD);
     ///
-    /// let node_config =  SyntaxConfigFile::FlowChart(ConfigFile::NodeConfig(NodeConfig {
+    /// let node_config =  SyntaxConfigFile::FlowChart(ObjectConfig::NodeConfig(NodeConfig {
     ///   id: "A",
     ///   class: None,
     ///   shape: Shape::Circle,
@@ -78,22 +217,24 @@ pub trait CoreSyntaxFunctions {
     /// # Examples
     ///
     /// ```
-    /// use mermaid_markdown_api::syntax::flow_chart::{ArrowDirection, ArrowType, ConnectionConfig, FlowChart, LineType, NodeConfig, Shape};
+    /// use mermaid_markdown_api::syntax::flow_chart::{ArrowDirection, ArrowType, ConnectionConfig, FlowChart, LineType, NodeConfig, ObjectConfig, Shape};
     /// use mermaid_markdown_api::syntax::{CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile};
     /// let mut flow_chart = FlowChart::new(FlowDirection::TD);
     ///
-    /// let node_config =  SyntaxConfigFile::FlowChart(ConfigFile::NodeConfig(NodeConfig {
+    /// let node_config =  SyntaxConfigFile::FlowChart(ObjectConfig::NodeConfig(NodeConfig {
     ///   id: "A",
     ///   class: None,
     ///   shape: Shape::Circle,
     ///   inner_text: "inner text",
     /// }));
     ///
-    /// let connection_config = SyntaxConfigFile::FlowChart(ConfigFile::ConnectionConfig(ConnectionConfig {
+    /// let connection_config = SyntaxConfigFile::FlowChart(ObjectConfig::ConnectionConfig(ConnectionConfig {
     ///   line_type: LineType::Dashed,
     ///   arrow_type: ArrowType::Standard,
     ///   arrow_direction: ArrowDirection::Right,
     ///   extra_length_num: None,
+    ///   label: None,
+    ///   style: None,
     /// }));
     ///
     /// flow_chart.add_node(node_config);
@@ -124,6 +265,92 @@ pub trait CoreSyntaxFunctions {
         num_of_indents: Option<u8>,
     );
 
+    /// Opens a [Mermaid.js Subgraph](https://mermaid-js.github.io/mermaid/#/flowchart?id=subgraphs) with the supplied ID & title & appends it to the current data of the flow chart struct (i.e. `self.data`).
+    ///
+    /// Every node added before the matching `add_group_end` call is placed inside the subgraph.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID that will be assigned to the subgraph
+    /// * `title` - The text to be displayed as the title of the subgraph
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mermaid_markdown_api::syntax::flow_chart::FlowChart;
+    /// use mermaid_markdown_api::syntax::{CoreSyntaxFunctions, FlowDirection};
+    /// let mut flow_chart = FlowChart::new(FlowDirection::TD);
+    ///
+    /// flow_chart.add_group_start("impl_Contract", "Contract");
+    /// flow_chart.add_linebreak(None);
+    /// flow_chart.add_group_end();
+    /// ```
+    fn add_group_start(
+        &mut self,
+        id: &str,
+        title: &str,
+    );
+
+    /// Closes the most recently opened [Mermaid.js Subgraph](https://mermaid-js.github.io/mermaid/#/flowchart?id=subgraphs) by appending `end` to the current data of the flow chart struct (i.e. `self.data`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mermaid_markdown_api::syntax::flow_chart::FlowChart;
+    /// use mermaid_markdown_api::syntax::{CoreSyntaxFunctions, FlowDirection};
+    /// let mut flow_chart = FlowChart::new(FlowDirection::TD);
+    ///
+    /// flow_chart.add_group_start("impl_Contract", "Contract");
+    /// flow_chart.add_linebreak(None);
+    /// flow_chart.add_group_end();
+    /// ```
+    fn add_group_end(&mut self);
+
+    /// Creates a [Mermaid.js Click Interaction](https://mermaid-js.github.io/mermaid/#/flowchart?id=interaction) turning the node with the supplied ID into a link & appends it to the current data of the flow chart struct (i.e. `self.data`).
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the node to link
+    /// * `url` - The URL opened when the node is clicked
+    /// * `tooltip` - An optional text displayed when hovering over the node
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mermaid_markdown_api::syntax::flow_chart::FlowChart;
+    /// use mermaid_markdown_api::syntax::{CoreSyntaxFunctions, FlowDirection};
+    /// let mut flow_chart = FlowChart::new(FlowDirection::TD);
+    ///
+    /// flow_chart.add_click("function_a", "https://github.com/org/repo/blob/main/src/lib.rs#L12", Some("src/lib.rs:12-20"));
+    /// ```
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    );
+
+    /// Sets the [Theme] used to style the nodes & connections of the diagram, which is applied when the schema is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `theme` - The theme to apply
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mermaid_markdown_api::syntax::flow_chart::FlowChart;
+    /// use mermaid_markdown_api::syntax::{CoreSyntaxFunctions, FlowDirection};
+    /// use mermaid_markdown_api::theme::Theme;
+    /// let mut flow_chart = FlowChart::new(FlowDirection::TD);
+    ///
+    /// flow_chart.set_theme(Theme::builtin("dark").unwrap());
+    /// ```
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    );
+
     /// This method creates a [NodeConfig] referencing data from a supplied [Node].
     ///
     /// # Arguments
@@ -140,12 +367,13 @@ pub trait CoreSyntaxFunctions {
     ///
     /// let node = Node {
     ///     name: "function_a".to_string(),
+    ///     label: None,
     ///     scope: ScopeType::Public,
     ///     action: ActionType::Mutation,
     ///     connections: vec![],
     /// };
     ///
-    /// let node_config = flow_chart.build_node_config(&node);
+    /// let node_config = flow_chart.build_node_config(&node, None);
     /// ```
     fn build_node_config<'a>(
         &self,
@@ -172,13 +400,16 @@ pub trait CoreSyntaxFunctions {
     ///     connection_type: ConnectionType::DirectConnection,
     ///     node: Node {
     ///         name: "function_a".to_string(),
+    ///         label: None,
     ///         scope: ScopeType::Public,
     ///         action: ActionType::Mutation,
     ///         connections: vec![],
-    ///     }
+    ///     },
+    ///     label: None,
+    ///     extra_length_num: None,
     /// };
     ///
-    /// let connection_config = flow_chart.build_connection_config(&connection);
+    /// let connection_config = flow_chart.build_connection_config(&connection, None);
     /// ```
     fn build_connection_config<'a>(
         &self,
@@ -198,12 +429,13 @@ pub trait CoreSyntaxFunctions {
     ///
     /// let node = Node {
     ///     name: "function_a".to_string(),
+    ///     label: None,
     ///     scope: ScopeType::Public,
     ///     action: ActionType::Mutation,
     ///     connections: vec![],
     /// };
     ///
-    /// let node_config = flow_chart.build_node_config(&node);
+    /// let node_config = flow_chart.build_node_config(&node, None);
     ///
     /// flow_chart.add_node(node_config);
     ///
@@ -211,3 +443,39 @@ pub trait CoreSyntaxFunctions {
     /// ```
     fn return_schema(&self) -> String;
 }
+
+/// Escapes the characters XML & HTML give a meaning to, in text as well as in quoted attribute values.
+///
+/// # Arguments
+///
+/// * `text` - The text to escape
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns a node of the diagrams built by the tests of the syntax's.
+///
+/// # Arguments
+///
+/// * `name` - The name of the node
+/// * `scope` - The scope of the node
+/// * `action` - The action of the node
+/// * `connections` - The connections leaving the node
+#[cfg(test)]
+pub(crate) fn test_node(
+    name: &str,
+    scope: ScopeType,
+    action: ActionType,
+    connections: Vec<Connection>,
+) -> Node {
+    Node {
+        name: name.to_string(),
+        label: None,
+        scope,
+        action,
+        connections,
+    }
+}
@@ -0,0 +1,635 @@
+use crate::objects::connection::Connection;
+use crate::objects::node::{ActionType, Node, ScopeType};
+use crate::syntax::flow_chart::{ArrowDirection, ArrowType, LineType, Shape};
+use crate::syntax::{
+    resolve_line_and_arrow, resolve_shape, CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile,
+};
+use crate::theme::Theme;
+use enum_as_inner::EnumAsInner;
+
+/// An enum representation of either a [NodeConfig] or an [ArrowConfig].
+#[derive(EnumAsInner, Debug)]
+pub enum ObjectConfig<'a> {
+    NodeConfig(NodeConfig<'a>),
+    ArrowConfig(ArrowConfig),
+}
+
+/// A struct representing the possible attributes for a [Node].
+#[derive(Debug)]
+pub struct NodeConfig<'a> {
+    /// The alias that will be assigned to this node
+    pub id: &'a str,
+    /// The text to be displayed within the node
+    pub label: &'a str,
+    /// The shape of the node, mapped to the closest PlantUML element
+    pub shape: Shape,
+    /// The scope of the node, part of its stereotype
+    pub scope: ScopeType,
+    /// The action of the node, part of its stereotype
+    pub action: ActionType,
+}
+
+/// A struct representing the possible attributes for a [Connection].
+#[derive(Debug)]
+pub struct ArrowConfig {
+    /// The enum representation of the type of line you want
+    pub line_type: LineType,
+    /// The enum representation of the type of arrow you want
+    pub arrow_type: ArrowType,
+    /// The enum representation of the direction you want the arrows to point
+    pub arrow_direction: ArrowDirection,
+    /// An optional text to be displayed on the arrow
+    pub label: Option<String>,
+    /// An optional colour of the arrow (e.g. `#12A5F1`)
+    pub color: Option<String>,
+}
+
+/// Returns the stereotype of the methods with the supplied scope & action, e.g. `view`, `call`, `payable-call` or `init`.
+///
+/// # Arguments
+///
+/// * `scope` - The scope of the method
+/// * `action` - The action of the method
+pub fn stereotype(
+    scope: &ScopeType,
+    action: &ActionType,
+) -> String {
+    let qualifier = match scope {
+        ScopeType::Public => "",
+        ScopeType::Private => "private",
+        ScopeType::Trait => "trait",
+        ScopeType::Payable => "payable",
+        ScopeType::Contract => "contract",
+        ScopeType::Initializer => "init",
+        ScopeType::External => "external",
+        ScopeType::Callback => "callback",
+    };
+    let kind = match action {
+        ActionType::None => "",
+        ActionType::Mutation => "call",
+        ActionType::View => "view",
+        ActionType::Process => "process",
+        ActionType::Event => "event",
+    };
+
+    match (qualifier, kind) {
+        ("", "") => "method".to_string(),
+        ("", kind) => kind.to_string(),
+        (qualifier, "") => qualifier.to_string(),
+        (qualifier, kind) => format!("{}-{}", qualifier, kind),
+    }
+}
+
+/// A line of the declarations, nodes are kept apart so that links can be attached to them later on.
+enum Declaration {
+    /// A node, with its alias
+    Node(String, String),
+    /// Any other statement, e.g. the opening of a group
+    Statement(String),
+}
+
+/// This is the root struct for an individual [PlantUML](https://plantuml.com) diagram.
+///
+/// It's either a component diagram, where groups become packages, or a [sequence diagram](https://plantuml.com/sequence-diagram),
+/// where the nodes become participants, the connections become messages & groups become boxes. Every kind of method is given
+/// a stereotype (see [stereotype]) styled by a `skinparam` block with the colours of the theme.
+pub struct PlantUml {
+    /// `true` if this is a sequence diagram
+    sequence: bool,
+    /// The direction statement of a component diagram, if any
+    direction: Option<&'static str>,
+    /// The node declarations & groups, each followed by its indentation
+    declarations: Vec<(Declaration, usize)>,
+    /// The arrow statements
+    arrows: Vec<String>,
+    /// The links of the nodes, keyed by alias
+    links: Vec<(String, String)>,
+    /// The IDs of the nodes declared so far, as a node is only declared once
+    declared_ids: Vec<String>,
+    /// The element, stereotype & class of every kind of node declared so far
+    stereotypes: Vec<(&'static str, String, ScopeType, ActionType)>,
+    /// The number of groups currently open
+    depth: usize,
+    /// The ID of the node last added, the source of the next arrow
+    last_id: Option<String>,
+    /// The arrow waiting for its target node
+    pending_arrow: Option<ArrowConfig>,
+    /// The theme applied when the schema is returned
+    theme: Option<Theme>,
+}
+
+/// Quotes a label, PlantUML has no escape for double quotes so they are swapped for single ones.
+///
+/// # Arguments
+///
+/// * `text` - The text to quote
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+impl PlantUml {
+    /// Creates an empty [sequence diagram](https://plantuml.com/sequence-diagram), which has no direction of flow.
+    pub fn sequence() -> Self {
+        PlantUml {
+            sequence: true,
+            ..PlantUml::new(FlowDirection::TD)
+        }
+    }
+
+    /// Returns the PlantUML element a node of the supplied shape is declared as.
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` - The shape of the node
+    fn element(
+        &self,
+        shape: Shape,
+    ) -> &'static str {
+        if self.sequence {
+            // Sequence diagrams only know a handful of participant kinds
+            return match shape {
+                Shape::Cylinder => "database",
+                Shape::Flag => "queue",
+                _ => "participant",
+            };
+        }
+        match shape {
+            Shape::Circle | Shape::DoubleCircle => "circle",
+            Shape::Hexagon => "hexagon",
+            Shape::Flag => "card",
+            Shape::Rounded => "agent",
+            Shape::Stadium => "usecase",
+            Shape::Subroutine => "component",
+            Shape::Cylinder => "database",
+            Shape::Parallelogram | Shape::ParallelogramAlt => "queue",
+            Shape::Rectangle | Shape::Rhombus | Shape::Trapezoid | Shape::TrapezoidAlt => {
+                "rectangle"
+            }
+        }
+    }
+
+    /// Returns the arrow of a connection, e.g. `-[#333]->`.
+    ///
+    /// # Arguments
+    ///
+    /// * `arrow_config` - The attributes of the arrow
+    fn arrow(
+        &self,
+        arrow_config: &ArrowConfig,
+    ) -> String {
+        let mut attributes: Vec<&str> = Vec::new();
+        if let Some(color) = &arrow_config.color {
+            attributes.push(color);
+        }
+        match arrow_config.line_type {
+            LineType::Thick => attributes.push("bold"),
+            LineType::Invisible => attributes.push("hidden"),
+            LineType::Solid | LineType::Dashed => {}
+        }
+        let attributes = if attributes.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]", attributes.join(","))
+        };
+        let dashed = matches!(arrow_config.line_type, LineType::Dashed);
+
+        if self.sequence {
+            // Messages always point somewhere, the arrow type decorates the head
+            let head = match arrow_config.arrow_type {
+                ArrowType::Standard => ">",
+                ArrowType::X => ">x",
+                ArrowType::O => ">o",
+            };
+            let tail = match arrow_config.arrow_direction {
+                ArrowDirection::BiDirectional => "<",
+                _ => "",
+            };
+            return format!("{}-{}{}{}", tail, attributes, if dashed { "-" } else { "" }, head);
+        }
+
+        let line = if dashed { "." } else { "-" };
+        let (tail, head) = match arrow_config.arrow_type {
+            ArrowType::Standard => ("<", ">"),
+            ArrowType::X => ("x", "x"),
+            ArrowType::O => ("o", "o"),
+        };
+        let (tail, head) = match arrow_config.arrow_direction {
+            ArrowDirection::BiDirectional => (tail, head),
+            ArrowDirection::Left => (tail, ""),
+            ArrowDirection::Right => ("", head),
+            ArrowDirection::None => ("", ""),
+        };
+        format!("{}{}{}{}{}", tail, line, attributes, line, head)
+    }
+
+    /// Returns the `skinparam` blocks styling every kind of node declared, `None` if there is no theme.
+    fn skin_params(&self) -> Option<String> {
+        let theme = self.theme.as_ref()?;
+        let mut skin_params = String::new();
+
+        for (element, stereotype, scope, action) in &self.stereotypes {
+            let style = theme.node_style(scope, action);
+            let mut parameters = Vec::new();
+            if let Some(fill) = &style.fill {
+                parameters.push(format!("BackgroundColor {}", fill));
+            }
+            if let Some(stroke) = &style.stroke {
+                parameters.push(format!("BorderColor {}", stroke));
+            }
+            if let Some(stroke_width) = &style.stroke_width {
+                parameters.push(format!(
+                    "BorderThickness {}",
+                    stroke_width.trim().trim_end_matches("px")
+                ));
+            }
+            if style.stroke_dasharray.is_some() {
+                parameters.push("BorderStyle dashed".to_string());
+            }
+            if let Some(color) = &style.color {
+                parameters.push(format!("FontColor {}", color));
+            }
+            if parameters.is_empty() {
+                continue;
+            }
+
+            skin_params.push_str(&format!("skinparam {}<<{}>> {{\n", element, stereotype));
+            for parameter in parameters {
+                skin_params.push_str(&format!("\t{}\n", parameter));
+            }
+            skin_params.push_str("}\n");
+        }
+
+        Some(skin_params)
+    }
+}
+
+impl CoreSyntaxFunctions for PlantUml {
+    fn new(direction: FlowDirection) -> Self {
+        // PlantUML lays component diagrams out top to bottom unless told otherwise
+        let direction = match direction {
+            FlowDirection::LR => Some("left to right direction"),
+            _ => None,
+        };
+
+        PlantUml {
+            sequence: false,
+            direction,
+            declarations: Vec::new(),
+            arrows: Vec::new(),
+            links: Vec::new(),
+            declared_ids: Vec::new(),
+            stereotypes: Vec::new(),
+            depth: 0,
+            last_id: None,
+            pending_arrow: None,
+            theme: None,
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        node_config: SyntaxConfigFile,
+    ) {
+        let node_config: NodeConfig = node_config
+            .into_plant_uml()
+            .unwrap()
+            .into_node_config()
+            .unwrap();
+
+        // Declare the node the first time it's added, so it lands in the group it was first added in
+        if !self.declared_ids.iter().any(|id| id == node_config.id) {
+            self.declared_ids.push(node_config.id.to_string());
+            let element = self.element(node_config.shape);
+            let stereotype = stereotype(&node_config.scope, &node_config.action);
+            let declaration = format!(
+                "{} {} as {} <<{}>>",
+                element,
+                quote(node_config.label),
+                node_config.id,
+                stereotype
+            );
+            if !self.stereotypes.iter().any(|(e, s, _, _)| *e == element && *s == stereotype) {
+                self.stereotypes
+                    .push((element, stereotype, node_config.scope, node_config.action));
+            }
+            self.declarations.push((
+                Declaration::Node(node_config.id.to_string(), declaration),
+                self.depth,
+            ));
+        }
+
+        // If an arrow is waiting for its target, this node is it
+        match (self.pending_arrow.take(), self.last_id.take()) {
+            (Some(arrow_config), Some(source_id)) => {
+                // Sequence diagrams have no hidden messages, so invisible connections are left out
+                if self.sequence && matches!(arrow_config.line_type, LineType::Invisible) {
+                    return;
+                }
+                let mut statement = format!(
+                    "{} {} {}",
+                    source_id,
+                    self.arrow(&arrow_config),
+                    node_config.id
+                );
+                if let Some(label) = &arrow_config.label {
+                    statement.push_str(" : ");
+                    statement.push_str(label);
+                }
+                self.arrows.push(statement);
+            }
+            _ => self.last_id = Some(node_config.id.to_string()),
+        }
+    }
+
+    fn add_connection(
+        &mut self,
+        connection_config: SyntaxConfigFile,
+    ) {
+        // Unwrap the `SyntaxConfigFile` into the needed `ArrowConfig`
+        let arrow_config: ArrowConfig = connection_config
+            .into_plant_uml()
+            .unwrap()
+            .into_arrow_config()
+            .unwrap();
+
+        // Keep the arrow until its target node is added
+        self.pending_arrow = Some(arrow_config);
+    }
+
+    fn add_linebreak(
+        &mut self,
+        _num_of_indents: Option<u8>,
+    ) {
+        // Every statement is put on a line of its own & indented by its nesting, so a linebreak only ends a chain of nodes
+        self.last_id = None;
+        self.pending_arrow = None;
+    }
+
+    fn add_group_start(
+        &mut self,
+        _id: &str,
+        title: &str,
+    ) {
+        // Boxes of sequence diagrams can't be nested, so only the outermost group gets one
+        let statement = match (self.sequence, self.depth) {
+            (true, 0) => Some(format!("box {}", quote(title))),
+            (true, _) => None,
+            (false, _) => Some(format!("package {} {{", quote(title))),
+        };
+        if let Some(statement) = statement {
+            self.declarations
+                .push((Declaration::Statement(statement), self.depth));
+        }
+        self.depth += 1;
+    }
+
+    fn add_group_end(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        let statement = match (self.sequence, self.depth) {
+            (true, 0) => Some("end box"),
+            (true, _) => None,
+            (false, _) => Some("}"),
+        };
+        if let Some(statement) = statement {
+            self.declarations
+                .push((Declaration::Statement(statement.to_string()), self.depth));
+        }
+    }
+
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    ) {
+        // Brackets & braces would end the link early, so they are percent-encoded
+        let url = url
+            .replace('[', "%5B")
+            .replace(']', "%5D")
+            .replace('{', "%7B")
+            .replace('}', "%7D");
+        let link = match tooltip {
+            Some(tooltip) => format!("[[{}{{{}}}]]", url, tooltip.replace(['{', '}'], "")),
+            None => format!("[[{}]]", url),
+        };
+        self.links.push((id.to_string(), link));
+    }
+
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.theme = Some(theme);
+    }
+
+    fn build_node_config<'a>(
+        &self,
+        node: &'a Node,
+        id: Option<&'a str>,
+    ) -> SyntaxConfigFile<'a> {
+        SyntaxConfigFile::PlantUml(ObjectConfig::NodeConfig(NodeConfig {
+            // If an ID was passed use it, else use the Node's name
+            id: id.unwrap_or(&node.name),
+            label: node.label(),
+            shape: resolve_shape(node, self.theme.as_ref()),
+            scope: node.scope,
+            action: node.action,
+        }))
+    }
+
+    fn build_connection_config<'a>(
+        &self,
+        connection: &'a Connection,
+        _extra_length_num: Option<u8>,
+    ) -> SyntaxConfigFile<'a> {
+        let (line_type, arrow_type, arrow_direction) =
+            resolve_line_and_arrow(&connection.connection_type);
+
+        SyntaxConfigFile::PlantUml(ObjectConfig::ArrowConfig(ArrowConfig {
+            line_type,
+            arrow_type,
+            arrow_direction,
+            label: connection.label.clone(),
+            color: self
+                .theme
+                .as_ref()
+                .and_then(|theme| theme.edge_style(&connection.connection_type).stroke),
+        }))
+    }
+
+    fn return_schema(&self) -> String {
+        let mut schema = String::from("@startuml\n");
+
+        // Push the layout & the styles
+        if let Some(direction) = self.direction {
+            if !self.sequence {
+                schema.push_str(direction);
+                schema.push('\n');
+            }
+        }
+        if let Some(background) = self
+            .theme
+            .as_ref()
+            .and_then(|theme| theme.variables.get("background"))
+        {
+            schema.push_str(&format!("skinparam backgroundColor {}\n", background));
+        }
+        if let Some(skin_params) = self.skin_params() {
+            schema.push_str(&skin_params);
+        }
+
+        // Push the declarations, attaching the links to their nodes
+        for (declaration, depth) in &self.declarations {
+            for _ in 0..*depth {
+                schema.push('\t');
+            }
+            match declaration {
+                Declaration::Node(id, statement) => {
+                    schema.push_str(statement);
+                    if let Some((_, link)) = self.links.iter().find(|(link_id, _)| link_id == id) {
+                        schema.push(' ');
+                        schema.push_str(link);
+                    }
+                }
+                Declaration::Statement(statement) => schema.push_str(statement),
+            }
+            schema.push('\n');
+        }
+
+        // Push the arrows between the nodes
+        for arrow in &self.arrows {
+            schema.push_str(arrow);
+            schema.push('\n');
+        }
+
+        schema.push_str("@enduml\n");
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::connection::ConnectionType;
+    use crate::objects::group::Group;
+
+    fn hierarchy() -> Node {
+        Node {
+            name: "Contract".to_string(),
+            label: None,
+            scope: ScopeType::Contract,
+            action: ActionType::None,
+            connections: vec![
+                Connection {
+                    connection_type: ConnectionType::DirectConnection,
+                    node: Node {
+                        name: "get_balance".to_string(),
+                        label: None,
+                        scope: ScopeType::Public,
+                        action: ActionType::View,
+                        connections: vec![],
+                    },
+                    label: None,
+                    extra_length_num: None,
+                },
+                Connection {
+                    connection_type: ConnectionType::Emission,
+                    node: Node {
+                        name: "log_deposit".to_string(),
+                        label: None,
+                        scope: ScopeType::Private,
+                        action: ActionType::Event,
+                        connections: vec![],
+                    },
+                    label: Some("emits".to_string()),
+                    extra_length_num: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn it_names_the_stereotypes_after_the_kind_of_method() {
+        assert_eq!(stereotype(&ScopeType::Public, &ActionType::View), "view");
+        assert_eq!(stereotype(&ScopeType::Public, &ActionType::Mutation), "call");
+        assert_eq!(
+            stereotype(&ScopeType::Payable, &ActionType::Mutation),
+            "payable-call"
+        );
+        assert_eq!(stereotype(&ScopeType::Initializer, &ActionType::None), "init");
+        assert_eq!(stereotype(&ScopeType::Public, &ActionType::None), "method");
+    }
+
+    #[test]
+    fn it_creates_a_component_diagram() {
+        let mut plant_uml = PlantUml::new(FlowDirection::LR);
+        plant_uml.set_theme(Theme::builtin("light").unwrap());
+
+        Group {
+            id: "impl_Contract".to_string(),
+            title: "Contract".to_string(),
+            nodes: vec![Node {
+                name: "get_balance".to_string(),
+                label: None,
+                scope: ScopeType::Public,
+                action: ActionType::View,
+                connections: vec![],
+            }],
+            groups: vec![],
+        }
+        .traverse(&mut plant_uml, 1);
+        plant_uml.add_linebreak(None);
+        hierarchy().traverse(&mut plant_uml);
+        plant_uml.add_click("get_balance", "https://example.com/{rev}", Some("src/lib.rs:1-2"));
+
+        assert_eq!(
+            plant_uml.return_schema(),
+            "@startuml
+left to right direction
+skinparam circle<<view>> {
+	BackgroundColor #12A5F1
+	BorderColor #333
+	BorderThickness 2
+}
+skinparam rectangle<<contract>> {
+	BackgroundColor #C2D5E3
+	BorderColor #333
+	BorderThickness 2
+}
+skinparam card<<private-event>> {
+	BackgroundColor #FFDF80
+	BorderColor #333
+	BorderThickness 1
+	BorderStyle dashed
+}
+package \"Contract\" {
+	circle \"get_balance\" as get_balance <<view>> [[https://example.com/%7Brev%7D{src/lib.rs:1-2}]]
+}
+rectangle \"Contract\" as Contract <<contract>>
+card \"log_deposit\" as log_deposit <<private-event>>
+Contract -[#333]-> get_balance
+Contract .[#E6B800]. log_deposit : emits
+@enduml
+"
+        );
+    }
+
+    #[test]
+    fn it_creates_a_sequence_diagram() {
+        let mut plant_uml = PlantUml::sequence();
+
+        hierarchy().traverse(&mut plant_uml);
+
+        assert_eq!(
+            plant_uml.return_schema(),
+            "@startuml
+participant \"Contract\" as Contract <<contract>>
+participant \"get_balance\" as get_balance <<view>>
+queue \"log_deposit\" as log_deposit <<private-event>>
+Contract -> get_balance
+Contract -->o log_deposit : emits
+@enduml
+"
+        );
+    }
+}
@@ -0,0 +1,882 @@
+use crate::objects::connection::Connection;
+use crate::objects::node::Node;
+use crate::syntax::flow_chart::{ArrowDirection, ArrowType, LineType, Shape};
+use crate::syntax::layout::{layout, LayoutGraph, Spacing};
+use crate::syntax::{
+    escape_xml, resolve_line_and_arrow, resolve_shape, CoreSyntaxFunctions, FlowDirection, SyntaxConfigFile,
+};
+use crate::theme::{Style, Theme};
+use enum_as_inner::EnumAsInner;
+use usvg::fontdb::{Database, Family, Query};
+
+/// The font size of the labels.
+const FONT_SIZE: f64 = 14.0;
+/// The fonts of the labels, unless the theme sets the `fontFamily` variable.
+const FONT_FAMILY: &str = "\"trebuchet ms\", verdana, arial, sans-serif";
+/// The space between the label & the border of a node.
+const NODE_PADDING: f64 = 12.0;
+/// The space between the border of a group & its content.
+const GROUP_PADDING: f64 = 16.0;
+/// The height of the title of a group.
+const GROUP_HEADER: f64 = 24.0;
+/// The space around the diagram.
+const MARGIN: f64 = 8.0;
+
+/// An enum representation of either a [NodeConfig] or an [EdgeConfig].
+#[derive(EnumAsInner, Debug)]
+pub enum ObjectConfig<'a> {
+    NodeConfig(NodeConfig<'a>),
+    EdgeConfig(EdgeConfig),
+}
+
+/// A struct representing the possible attributes for a [Node].
+#[derive(Debug)]
+pub struct NodeConfig<'a> {
+    /// The ID of the node
+    pub id: &'a str,
+    /// The text to be displayed within the node
+    pub label: &'a str,
+    /// The class of the node (e.g. `Public-View`), set as the class of its element
+    pub class: String,
+    /// The shape of the node
+    pub shape: Shape,
+    /// The fill, border & text colours of the node
+    pub style: Style,
+}
+
+/// A struct representing the possible attributes for a [Connection].
+#[derive(Debug)]
+pub struct EdgeConfig {
+    /// The enum representation of the type of line you want
+    pub line_type: LineType,
+    /// The enum representation of the type of arrow you want
+    pub arrow_type: ArrowType,
+    /// The enum representation of the direction you want the arrows to point
+    pub arrow_direction: ArrowDirection,
+    /// An optional text to be displayed on the line
+    pub label: Option<String>,
+    /// The colour, width & dash pattern of the line
+    pub style: Style,
+}
+
+/// A node waiting to be laid out.
+struct Vertex {
+    id: String,
+    label: String,
+    class: String,
+    shape: Shape,
+    style: Style,
+    /// The index of the innermost group the node was declared in
+    group: Option<usize>,
+}
+
+/// A group waiting to be laid out.
+struct Container {
+    id: String,
+    title: String,
+    /// The index of the enclosing group
+    parent: Option<usize>,
+}
+
+/// Measures the labels with the fonts [usvg] renders them with, so that the nodes fit their labels.
+pub struct TextMeasure {
+    fonts: Database,
+}
+
+/// The fonts the generic sans-serif family falls back to when its default, Arial, isn't installed.
+const SANS_SERIF_FALLBACKS: [&str; 5] = ["DejaVu Sans", "Liberation Sans", "Noto Sans", "Helvetica", "FreeSans"];
+
+/// Loads the fonts of the system into a font database, pointing the generic sans-serif family at an installed font.
+///
+/// Renderers loading their fonts this way set the text in the same font the labels were measured with.
+///
+/// # Arguments
+///
+/// * `fonts` - The font database to load the fonts into
+pub fn load_system_fonts(fonts: &mut Database) {
+    fonts.load_system_fonts();
+    let installed = |family: &str| fonts.faces().iter().any(|face| face.family == family);
+    if installed("Arial") {
+        return;
+    }
+    let fallback = SANS_SERIF_FALLBACKS
+        .iter()
+        .find(|family| installed(family))
+        .map(|family| family.to_string())
+        .or_else(|| fonts.faces().first().map(|face| face.family.clone()));
+    if let Some(family) = fallback {
+        fonts.set_sans_serif_family(family);
+    }
+}
+
+impl TextMeasure {
+    /// Loads the fonts of the system.
+    pub fn system() -> TextMeasure {
+        let mut fonts = Database::new();
+        load_system_fonts(&mut fonts);
+        TextMeasure { fonts }
+    }
+
+    /// Returns the width of a text, estimated from the font size if none of the fonts is installed.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to measure
+    /// * `font_family` - The CSS font family list the text is set in
+    /// * `font_size` - The font size of the text
+    pub fn width(
+        &self,
+        text: &str,
+        font_family: &str,
+        font_size: f64,
+    ) -> f64 {
+        let names: Vec<String> = font_family
+            .split(',')
+            .map(|name| name.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+            .collect();
+        let mut families: Vec<Family> = names
+            .iter()
+            .map(|name| match name.as_str() {
+                "serif" => Family::Serif,
+                "sans-serif" => Family::SansSerif,
+                "monospace" => Family::Monospace,
+                name => Family::Name(name),
+            })
+            .collect();
+        families.push(Family::SansSerif);
+
+        self.fonts
+            .query(&Query {
+                families: &families,
+                ..Query::default()
+            })
+            .and_then(|id| {
+                self.fonts.with_face_data(id, |data, index| {
+                    let face = ttf_parser::Face::from_slice(data, index).ok()?;
+                    let advance: f64 = text
+                        .chars()
+                        .map(|c| {
+                            face.glyph_index(c)
+                                .and_then(|glyph| face.glyph_hor_advance(glyph))
+                                .unwrap_or(face.units_per_em() / 2) as f64
+                        })
+                        .sum();
+                    Some(advance * font_size / face.units_per_em() as f64)
+                })
+            })
+            .flatten()
+            .unwrap_or_else(|| text.chars().count() as f64 * font_size * 0.6)
+    }
+}
+
+/// This is the root struct for an SVG image rendered without any external tool.
+///
+/// The nodes, groups & edges are collected & laid out with [layout] when the schema is returned, then drawn with the shapes &
+/// colours of the theme. The labels are measured with the fonts installed on the system, see [TextMeasure].
+pub struct Svg {
+    /// `true` if the layers are laid out from left to right rather than from top to bottom
+    horizontal: bool,
+    /// `true` if the layers are laid out against the reading direction (i.e. bottom to top or right to left)
+    reversed: bool,
+    vertices: Vec<Vertex>,
+    containers: Vec<Container>,
+    /// The groups currently open
+    open_containers: Vec<usize>,
+    /// The source vertex, target vertex & attributes of every edge
+    edges: Vec<(usize, usize, EdgeConfig)>,
+    /// The ID, URL & tooltip of the clickable nodes
+    links: Vec<(String, String, Option<String>)>,
+    /// The vertex last added, the source of the next edge
+    last_vertex: Option<usize>,
+    /// The edge waiting for its target node
+    pending_edge: Option<EdgeConfig>,
+    /// The theme applied when the schema is returned
+    theme: Option<Theme>,
+}
+
+/// Returns the points of a polygon as an SVG attribute value.
+///
+/// # Arguments
+///
+/// * `points` - The corners of the polygon
+fn polygon_points(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the point where the line from the centre of a node towards another point leaves the node.
+///
+/// # Arguments
+///
+/// * `centre` - The centre of the node
+/// * `size` - The width & height of the node
+/// * `round` - `true` if the node is an ellipse rather than a rectangle
+/// * `towards` - The point the line heads to
+fn clip_to_border(
+    centre: (f64, f64),
+    size: (f64, f64),
+    round: bool,
+    towards: (f64, f64),
+) -> (f64, f64) {
+    let (dx, dy) = (towards.0 - centre.0, towards.1 - centre.1);
+    if dx == 0.0 && dy == 0.0 {
+        return centre;
+    }
+    let (half_width, half_height) = (size.0 / 2.0, size.1 / 2.0);
+    let scale = if round {
+        1.0 / ((dx / half_width).powi(2) + (dy / half_height).powi(2)).sqrt()
+    } else {
+        (half_width / dx.abs()).min(half_height / dy.abs())
+    };
+    (centre.0 + dx * scale, centre.1 + dy * scale)
+}
+
+/// Returns the path data of a smooth curve through a list of points.
+///
+/// # Arguments
+///
+/// * `points` - The points the curve passes through
+fn curve_through(points: &[(f64, f64)]) -> String {
+    let mut path = format!("M{:.1},{:.1}", points[0].0, points[0].1);
+    for index in 1..points.len() {
+        // Catmull-Rom tangents, turned into the control points of cubic Bézier segments
+        let previous = points[index.saturating_sub(2)];
+        let start = points[index - 1];
+        let end = points[index];
+        let next = points[(index + 1).min(points.len() - 1)];
+        let control_a = (start.0 + (end.0 - previous.0) / 6.0, start.1 + (end.1 - previous.1) / 6.0);
+        let control_b = (end.0 - (next.0 - start.0) / 6.0, end.1 - (next.1 - start.1) / 6.0);
+        path.push_str(&format!(
+            " C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}",
+            control_a.0, control_a.1, control_b.0, control_b.1, end.0, end.1
+        ));
+    }
+    path
+}
+
+impl Svg {
+    /// Returns the value of a theme variable, or the default value if the theme doesn't set it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the Mermaid.js theme variable
+    /// * `default` - The value Mermaid.js defaults to
+    fn variable(
+        &self,
+        name: &str,
+        default: &str,
+    ) -> String {
+        self.theme
+            .as_ref()
+            .and_then(|theme| theme.variables.get(name))
+            .cloned()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// Returns the size of a node, large enough for its label.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertex` - The node
+    /// * `text_measure` - The fonts the label is measured with
+    /// * `font_family` - The fonts of the label
+    fn vertex_size(
+        vertex: &Vertex,
+        text_measure: &TextMeasure,
+        font_family: &str,
+    ) -> (f64, f64) {
+        let width = text_measure.width(&vertex.label, font_family, FONT_SIZE) + 2.0 * NODE_PADDING;
+        let height = FONT_SIZE * 1.2 + 2.0 * NODE_PADDING;
+        match vertex.shape {
+            Shape::Circle => (width, width),
+            Shape::DoubleCircle => (width + 10.0, width + 10.0),
+            Shape::Rhombus => (width + height, height * 1.6),
+            Shape::Hexagon
+            | Shape::Parallelogram
+            | Shape::ParallelogramAlt
+            | Shape::Trapezoid
+            | Shape::TrapezoidAlt => (width + height / 2.0, height),
+            Shape::Flag => (width + height / 4.0, height),
+            Shape::Subroutine => (width + 16.0, height),
+            Shape::Cylinder => (width, height + 16.0),
+            Shape::Rectangle | Shape::Rounded | Shape::Stadium => (width, height),
+        }
+    }
+
+    /// Returns the SVG elements drawing the outline of a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` - The shape of the node
+    /// * `centre` - The centre of the node
+    /// * `size` - The width & height of the node
+    /// * `paint` - The fill & stroke attributes of the outline
+    fn outline(
+        shape: Shape,
+        centre: (f64, f64),
+        size: (f64, f64),
+        paint: &str,
+    ) -> String {
+        let (cx, cy) = centre;
+        let (width, height) = size;
+        let (x0, y0, x1, y1) = (cx - width / 2.0, cy - height / 2.0, cx + width / 2.0, cy + height / 2.0);
+        let inset = height / 4.0;
+        let polygon = |points: &[(f64, f64)]| format!("<polygon points=\"{}\"{}/>", polygon_points(points), paint);
+        let rect = |radius: f64| {
+            format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"{:.1}\"{}/>",
+                x0, y0, width, height, radius, paint
+            )
+        };
+
+        match shape {
+            Shape::Rectangle => rect(0.0),
+            Shape::Rounded => rect(5.0),
+            Shape::Stadium => rect(height / 2.0),
+            Shape::Circle => format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"{}/>", cx, cy, width / 2.0, paint),
+            Shape::DoubleCircle => format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"{}/><circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"{}/>",
+                cx,
+                cy,
+                width / 2.0,
+                paint,
+                cx,
+                cy,
+                width / 2.0 - 5.0,
+                paint
+            ),
+            Shape::Hexagon => polygon(&[(x0 + inset, y0), (x1 - inset, y0), (x1, cy), (x1 - inset, y1), (x0 + inset, y1), (x0, cy)]),
+            Shape::Flag => polygon(&[(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0 + inset, cy)]),
+            Shape::Subroutine => format!(
+                "{}<path d=\"M{:.1},{:.1} V{:.1} M{:.1},{:.1} V{:.1}\"{} fill=\"none\"/>",
+                rect(0.0),
+                x0 + 8.0,
+                y0,
+                y1,
+                x1 - 8.0,
+                y0,
+                y1,
+                paint
+            ),
+            Shape::Cylinder => format!(
+                "<path d=\"M{:.1},{:.1} V{:.1} A{:.1},8 0 0 0 {:.1},{:.1} V{:.1} Z\"{}/><ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"{:.1}\" ry=\"8\"{}/>",
+                x0,
+                y0 + 8.0,
+                y1 - 8.0,
+                width / 2.0,
+                x1,
+                y1 - 8.0,
+                y0 + 8.0,
+                paint,
+                cx,
+                y0 + 8.0,
+                width / 2.0,
+                paint
+            ),
+            Shape::Rhombus => polygon(&[(cx, y0), (x1, cy), (cx, y1), (x0, cy)]),
+            Shape::Parallelogram => polygon(&[(x0 + inset, y0), (x1, y0), (x1 - inset, y1), (x0, y1)]),
+            Shape::ParallelogramAlt => polygon(&[(x0, y0), (x1 - inset, y0), (x1, y1), (x0 + inset, y1)]),
+            Shape::Trapezoid => polygon(&[(x0 + inset, y0), (x1 - inset, y0), (x1, y1), (x0, y1)]),
+            Shape::TrapezoidAlt => polygon(&[(x0, y0), (x1, y0), (x1 - inset, y1), (x0 + inset, y1)]),
+        }
+    }
+
+    /// Returns the path of groups enclosing a group, outermost first & including the group itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The innermost group
+    fn group_path(
+        &self,
+        group: Option<usize>,
+    ) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut current = group;
+        while let Some(index) = current {
+            path.insert(0, index);
+            current = self.containers[index].parent;
+        }
+        path
+    }
+
+    /// Returns the ID of the marker drawing an arrow head, pushing its definition if it's the first of its kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `markers` - The definitions of the markers
+    /// * `arrow_type` - The kind of arrow head
+    /// * `color` - The colour of the arrow head
+    /// * `start` - `true` if the arrow head is at the start of the line, pointing backwards
+    fn marker(
+        markers: &mut Vec<(String, String)>,
+        arrow_type: ArrowType,
+        color: &str,
+        start: bool,
+    ) -> String {
+        let key = format!("{}-{}-{}", arrow_type.as_ref(), color, start);
+        if let Some(index) = markers.iter().position(|(marker_key, _)| *marker_key == key) {
+            return format!("marker-{}", index);
+        }
+
+        let id = format!("marker-{}", markers.len());
+        let (head, reference) = match (arrow_type, start) {
+            (ArrowType::Standard, false) => ("<path d=\"M0,0 L10,5 L0,10 Z\"".to_string(), 9.0),
+            (ArrowType::Standard, true) => ("<path d=\"M10,0 L0,5 L10,10 Z\"".to_string(), 1.0),
+            (ArrowType::X, _) => (
+                "<path d=\"M1,1 L9,9 M9,1 L1,9\" stroke-width=\"2\" stroke=\"".to_string() + color + "\"",
+                5.0,
+            ),
+            (ArrowType::O, _) => ("<circle cx=\"5\" cy=\"5\" r=\"4\"".to_string(), if start { 1.0 } else { 9.0 }),
+        };
+        markers.push((
+            key,
+            format!(
+                "<marker id=\"{}\" viewBox=\"0 0 10 10\" refX=\"{}\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\">{} fill=\"{}\"/></marker>",
+                id, reference, head, color
+            ),
+        ));
+        id
+    }
+}
+
+impl CoreSyntaxFunctions for Svg {
+    fn new(direction: FlowDirection) -> Self {
+        let (horizontal, reversed) = match direction {
+            FlowDirection::TB | FlowDirection::TD => (false, false),
+            FlowDirection::BT => (false, true),
+            FlowDirection::LR => (true, false),
+            FlowDirection::BL => (true, true),
+        };
+
+        Svg {
+            horizontal,
+            reversed,
+            vertices: Vec::new(),
+            containers: Vec::new(),
+            open_containers: Vec::new(),
+            edges: Vec::new(),
+            links: Vec::new(),
+            last_vertex: None,
+            pending_edge: None,
+            theme: None,
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        node_config: SyntaxConfigFile,
+    ) {
+        let node_config: NodeConfig = node_config
+            .into_svg()
+            .unwrap()
+            .into_node_config()
+            .unwrap();
+
+        // Keep the node the first time it's added, so it lands in the group it was first added in
+        let index = match self
+            .vertices
+            .iter()
+            .position(|vertex| vertex.id == node_config.id)
+        {
+            Some(index) => index,
+            None => {
+                self.vertices.push(Vertex {
+                    id: node_config.id.to_string(),
+                    label: node_config.label.to_string(),
+                    class: node_config.class,
+                    shape: node_config.shape,
+                    style: node_config.style,
+                    group: self.open_containers.last().copied(),
+                });
+                self.vertices.len() - 1
+            }
+        };
+
+        // If an edge is waiting for its target, this node is it
+        match (self.pending_edge.take(), self.last_vertex.take()) {
+            (Some(edge_config), Some(source)) => self.edges.push((source, index, edge_config)),
+            _ => self.last_vertex = Some(index),
+        }
+    }
+
+    fn add_connection(
+        &mut self,
+        connection_config: SyntaxConfigFile,
+    ) {
+        // Unwrap the `SyntaxConfigFile` into the needed `EdgeConfig`
+        let edge_config: EdgeConfig = connection_config
+            .into_svg()
+            .unwrap()
+            .into_edge_config()
+            .unwrap();
+
+        // Keep the edge until its target node is added
+        self.pending_edge = Some(edge_config);
+    }
+
+    fn add_linebreak(
+        &mut self,
+        _num_of_indents: Option<u8>,
+    ) {
+        // The elements are laid out when the schema is returned, so a linebreak only ends a chain of nodes
+        self.last_vertex = None;
+        self.pending_edge = None;
+    }
+
+    fn add_group_start(
+        &mut self,
+        id: &str,
+        title: &str,
+    ) {
+        self.containers.push(Container {
+            id: id.to_string(),
+            title: title.to_string(),
+            parent: self.open_containers.last().copied(),
+        });
+        self.open_containers.push(self.containers.len() - 1);
+    }
+
+    fn add_group_end(&mut self) {
+        self.open_containers.pop();
+    }
+
+    fn add_click(
+        &mut self,
+        id: &str,
+        url: &str,
+        tooltip: Option<&str>,
+    ) {
+        self.links
+            .push((id.to_string(), url.to_string(), tooltip.map(str::to_string)));
+    }
+
+    fn set_theme(
+        &mut self,
+        theme: Theme,
+    ) {
+        self.theme = Some(theme);
+    }
+
+    fn build_node_config<'a>(
+        &self,
+        node: &'a Node,
+        id: Option<&'a str>,
+    ) -> SyntaxConfigFile<'a> {
+        SyntaxConfigFile::Svg(ObjectConfig::NodeConfig(NodeConfig {
+            // If an ID was passed use it, else use the Node's name
+            id: id.unwrap_or(&node.name),
+            label: node.label(),
+            class: format!("{}-{}", node.scope.as_ref(), node.action.as_ref()),
+            shape: resolve_shape(node, self.theme.as_ref()),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.node_style(&node.scope, &node.action))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn build_connection_config<'a>(
+        &self,
+        connection: &'a Connection,
+        _extra_length_num: Option<u8>,
+    ) -> SyntaxConfigFile<'a> {
+        let (line_type, arrow_type, arrow_direction) =
+            resolve_line_and_arrow(&connection.connection_type);
+
+        SyntaxConfigFile::Svg(ObjectConfig::EdgeConfig(EdgeConfig {
+            line_type,
+            arrow_type,
+            arrow_direction,
+            label: connection.label.clone(),
+            style: self
+                .theme
+                .as_ref()
+                .map(|theme| theme.edge_style(&connection.connection_type))
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn return_schema(&self) -> String {
+        let text_measure = &TextMeasure::system();
+        let font_family = self.variable("fontFamily", FONT_FAMILY);
+        let line_color = self.variable("lineColor", "#333333");
+        let text_color = self.variable("primaryTextColor", "#333333");
+
+        // Lay the nodes out, leaving room for the borders & titles of the groups between them
+        let sizes: Vec<(f64, f64)> = self
+            .vertices
+            .iter()
+            .map(|vertex| Svg::vertex_size(vertex, text_measure, &font_family))
+            .collect();
+        let graph = LayoutGraph {
+            sizes: sizes.clone(),
+            group_paths: self.vertices.iter().map(|vertex| self.group_path(vertex.group)).collect(),
+            edges: self.edges.iter().map(|(source, target, _)| (*source, *target)).collect(),
+        };
+        let spacing = Spacing {
+            node_gap: 40.0,
+            layer_gap: 50.0,
+            group_padding: GROUP_PADDING,
+            group_header: GROUP_HEADER,
+        };
+        let mut layout = layout(&graph, &spacing, self.horizontal, self.reversed);
+        let mut container_bounds = layout.group_bounds.clone();
+        container_bounds.resize(self.containers.len(), None);
+
+        // Move the diagram next to the origin
+        let mut extents: Vec<(f64, f64, f64, f64)> = layout
+            .centres
+            .iter()
+            .zip(&sizes)
+            .map(|((cx, cy), (width, height))| (cx - width / 2.0, cy - height / 2.0, cx + width / 2.0, cy + height / 2.0))
+            .collect();
+        extents.extend(container_bounds.iter().flatten());
+        extents.extend(layout.routes.iter().flatten().map(|(x, y)| (*x, *y, *x, *y)));
+        let left = extents.iter().map(|e| e.0).fold(0.0, f64::min) - MARGIN;
+        let top = extents.iter().map(|e| e.1).fold(0.0, f64::min) - MARGIN;
+        let width = extents.iter().map(|e| e.2).fold(0.0, f64::max) + MARGIN - left;
+        let height = extents.iter().map(|e| e.3).fold(0.0, f64::max) + MARGIN - top;
+        for centre in layout.centres.iter_mut().chain(layout.routes.iter_mut().flatten()) {
+            centre.0 -= left;
+            centre.1 -= top;
+        }
+        for bounds in container_bounds.iter_mut().flatten() {
+            *bounds = (bounds.0 - left, bounds.1 - top, bounds.2 - left, bounds.3 - top);
+        }
+
+        let mut schema = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\" font-family=\"{}\" font-size=\"{}\">\n",
+            width.ceil(),
+            height.ceil(),
+            width.ceil(),
+            height.ceil(),
+            escape_xml(&font_family),
+            FONT_SIZE
+        );
+        if let Some(background) = self.theme.as_ref().and_then(|theme| theme.variables.get("background")) {
+            schema.push_str(&format!(
+                "\t<rect class=\"background\" width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+                escape_xml(background)
+            ));
+        }
+
+        // Push the groups, outermost first so the nested groups are drawn over them
+        for (index, container) in self.containers.iter().enumerate() {
+            if let Some((x0, y0, x1, y1)) = container_bounds[index] {
+                schema.push_str(&format!(
+                    "\t<g class=\"cluster\" id=\"{}\">\n\t\t<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"{}\"/>\n\t\t<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>\n\t</g>\n",
+                    escape_xml(&container.id),
+                    x0,
+                    y0,
+                    x1 - x0,
+                    y1 - y0,
+                    self.variable("clusterBkg", "#ffffde"),
+                    self.variable("clusterBorder", "#aaaa33"),
+                    (x0 + x1) / 2.0,
+                    y0 + GROUP_HEADER / 2.0 + 4.0,
+                    text_color,
+                    escape_xml(&container.title)
+                ));
+            }
+        }
+
+        // Push the edges, from the border of their source to the border of their target
+        let mut markers: Vec<(String, String)> = Vec::new();
+        let mut edges = String::new();
+        for ((source, target, edge_config), route) in self.edges.iter().zip(&layout.routes) {
+            if let LineType::Invisible = edge_config.line_type {
+                continue;
+            }
+            let round = |vertex: usize| matches!(self.vertices[vertex].shape, Shape::Circle | Shape::DoubleCircle);
+            let mut points = route.clone();
+            if source == target {
+                // A self loop circles around the corner of its node
+                let (cx, cy) = layout.centres[*source];
+                let (width, height) = sizes[*source];
+                points = vec![
+                    (cx + width / 2.0, cy),
+                    (cx + width / 2.0 + 20.0, cy - height / 2.0 - 10.0),
+                    (cx, cy - height / 2.0),
+                ];
+            } else {
+                let last = points.len() - 1;
+                points[0] = clip_to_border(layout.centres[*source], sizes[*source], round(*source), points[1]);
+                points[last] = clip_to_border(layout.centres[*target], sizes[*target], round(*target), points[last - 1]);
+            }
+
+            let color = edge_config.style.stroke.clone().unwrap_or_else(|| line_color.clone());
+            let mut attributes = format!(" stroke=\"{}\"", escape_xml(&color));
+            match (&edge_config.style.stroke_width, edge_config.line_type) {
+                (Some(stroke_width), _) => attributes.push_str(&format!(" stroke-width=\"{}\"", stroke_width.trim_end_matches("px"))),
+                (None, LineType::Thick) => attributes.push_str(" stroke-width=\"3.5\""),
+                (None, _) => attributes.push_str(" stroke-width=\"1.5\""),
+            }
+            match (&edge_config.style.stroke_dasharray, edge_config.line_type) {
+                (Some(dasharray), _) => attributes.push_str(&format!(" stroke-dasharray=\"{}\"", escape_xml(dasharray))),
+                (None, LineType::Dashed) => attributes.push_str(" stroke-dasharray=\"3\""),
+                (None, _) => {}
+            }
+            let (start, end) = match edge_config.arrow_direction {
+                ArrowDirection::BiDirectional => (true, true),
+                ArrowDirection::Left => (true, false),
+                ArrowDirection::Right => (false, true),
+                ArrowDirection::None => (false, false),
+            };
+            if start {
+                let marker = Svg::marker(&mut markers, edge_config.arrow_type, &color, true);
+                attributes.push_str(&format!(" marker-start=\"url(#{})\"", marker));
+            }
+            if end {
+                let marker = Svg::marker(&mut markers, edge_config.arrow_type, &color, false);
+                attributes.push_str(&format!(" marker-end=\"url(#{})\"", marker));
+            }
+            edges.push_str(&format!(
+                "\t<path class=\"edge\" data-source=\"{}\" data-target=\"{}\" d=\"{}\" fill=\"none\"{}/>\n",
+                escape_xml(&self.vertices[*source].id),
+                escape_xml(&self.vertices[*target].id),
+                curve_through(&points),
+                attributes
+            ));
+
+            // Push the label over the middle of the edge, on a background keeping it readable
+            if let Some(label) = &edge_config.label {
+                let middle = if points.len() % 2 == 1 {
+                    points[points.len() / 2]
+                } else {
+                    let (a, b) = (points[points.len() / 2 - 1], points[points.len() / 2]);
+                    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+                };
+                let label_width = text_measure.width(label, &font_family, FONT_SIZE - 2.0) + 8.0;
+                edges.push_str(&format!(
+                    "\t<g class=\"edge-label\">\n\t\t<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" opacity=\"0.85\"/>\n\t\t<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\" font-size=\"{}\" fill=\"{}\">{}</text>\n\t</g>\n",
+                    middle.0 - label_width / 2.0,
+                    middle.1 - FONT_SIZE / 2.0 - 2.0,
+                    label_width,
+                    FONT_SIZE + 4.0,
+                    self.variable("background", "#ffffff"),
+                    middle.0,
+                    middle.1,
+                    FONT_SIZE - 2.0,
+                    text_color,
+                    escape_xml(label)
+                ));
+            }
+        }
+        if !markers.is_empty() {
+            schema.push_str("\t<defs>\n");
+            for (_, marker) in &markers {
+                schema.push_str(&format!("\t\t{}\n", marker));
+            }
+            schema.push_str("\t</defs>\n");
+        }
+        schema.push_str(&edges);
+
+        // Push the nodes over the edges, wrapped in a link if they're clickable
+        for (index, vertex) in self.vertices.iter().enumerate() {
+            let style = &vertex.style;
+            let mut paint = format!(
+                " fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"",
+                escape_xml(style.fill.as_deref().unwrap_or("#ECECFF")),
+                escape_xml(style.stroke.as_deref().unwrap_or("#9370DB")),
+                style.stroke_width.as_deref().unwrap_or("1").trim_end_matches("px")
+            );
+            if let Some(dasharray) = &style.stroke_dasharray {
+                paint.push_str(&format!(" stroke-dasharray=\"{}\"", escape_xml(dasharray)));
+            }
+            let centre = layout.centres[index];
+            let mut node = format!(
+                "\t<g class=\"node {}\" id=\"{}\">\n\t\t{}\n\t\t<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>\n\t</g>\n",
+                escape_xml(&vertex.class),
+                escape_xml(&vertex.id),
+                Svg::outline(vertex.shape, centre, sizes[index], &paint),
+                centre.0,
+                centre.1,
+                escape_xml(style.color.as_deref().unwrap_or(&text_color)),
+                escape_xml(&vertex.label)
+            );
+            if let Some((_, url, tooltip)) = self.links.iter().find(|(id, _, _)| *id == vertex.id) {
+                let title = tooltip
+                    .as_ref()
+                    .map(|tooltip| format!("<title>{}</title>", escape_xml(tooltip)))
+                    .unwrap_or_default();
+                node = format!(
+                    "\t<a xlink:href=\"{}\" href=\"{}\" target=\"_blank\">{}\n{}\t</a>\n",
+                    escape_xml(url),
+                    escape_xml(url),
+                    title,
+                    node
+                );
+            }
+            schema.push_str(&node);
+        }
+
+        schema.push_str("</svg>\n");
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::test_node;
+    use crate::objects::connection::ConnectionType;
+    use crate::objects::group::Group;
+    use crate::objects::node::{ActionType, ScopeType};
+
+    #[test]
+    fn it_measures_text_with_the_system_fonts() {
+        let text_measure = TextMeasure::system();
+
+        let short = text_measure.width("fn", FONT_FAMILY, FONT_SIZE);
+        let long = text_measure.width("fn fn fn", FONT_FAMILY, FONT_SIZE);
+        assert!(short > 0.0);
+        assert!(long > short * 3.0);
+    }
+
+    #[test]
+    fn it_renders_a_laid_out_image() {
+        let mut svg = Svg::new(FlowDirection::TD);
+        svg.set_theme(Theme::builtin("dark").unwrap());
+
+        Group {
+            id: "module_a".to_string(),
+            title: "a".to_string(),
+            nodes: vec![test_node("get_balance", ScopeType::Public, ActionType::View, vec![])],
+            groups: vec![],
+        }
+        .traverse(&mut svg, 1);
+        svg.add_linebreak(None);
+        test_node(
+            "withdraw",
+            ScopeType::Public,
+            ActionType::Mutation,
+            vec![Connection {
+                connection_type: ConnectionType::DirectConnection,
+                node: test_node("get_balance", ScopeType::Public, ActionType::View, vec![]),
+                label: Some("calls".to_string()),
+                extra_length_num: None,
+            }],
+        )
+        .traverse(&mut svg);
+        svg.add_click("withdraw", "https://example.com/lib.rs#L1", Some("lib.rs"));
+
+        let schema = svg.return_schema();
+        assert!(schema.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(schema.contains("\t<rect class=\"background\" width=\"100%\" height=\"100%\" fill=\"#1E1E1E\"/>\n"));
+        assert!(schema.contains("\t<g class=\"cluster\" id=\"module_a\">\n\t\t<rect "));
+        assert!(schema.contains("fill=\"#2A2A2A\" stroke=\"#5A5A5A\"/>"));
+        assert!(schema.contains("\t<g class=\"node Public-View\" id=\"get_balance\">\n\t\t<circle "));
+        assert!(schema.contains("\t<a xlink:href=\"https://example.com/lib.rs#L1\" href=\"https://example.com/lib.rs#L1\" target=\"_blank\"><title>lib.rs</title>\n\t<g class=\"node Public-Mutation\" id=\"withdraw\">\n\t\t<polygon "));
+        assert!(schema.contains("\" fill=\"none\" stroke=\"#C8C8C8\" stroke-width=\"1.5\" marker-end=\"url(#marker-0)\"/>\n"));
+        assert!(schema.contains("\t<path class=\"edge\" data-source=\"withdraw\" data-target=\"get_balance\" d=\""));
+        assert!(schema.contains(">calls</text>"));
+
+        // The image must be valid for usvg, which rasterizes it
+        let tree = usvg::Tree::from_str(&schema, &usvg::Options::default().to_ref()).unwrap();
+        assert!(tree.svg_node().size.width() > 100.0);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::objects::connection::ConnectionType;
+use crate::objects::node::{ActionType, ScopeType};
+use crate::syntax::flow_chart::Shape;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+use strum::IntoEnumIterator;
+
+/// The names of the themes shipped with the crate, usable wherever a theme file path is accepted.
+pub const BUILTIN_THEMES: [&str; 4] = ["light", "dark", "high-contrast", "print"];
+
+/// A set of CSS properties applied to a node (via `classDef`) or a connection (via `linkStyle`).
+///
+/// Unset properties are inherited when styles are layered on top of each other.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Style {
+    /// The background colour of a node
+    pub fill: Option<String>,
+    /// The colour of the border of a node or of a connection's line
+    pub stroke: Option<String>,
+    /// The width of the border or line (e.g. `2px`)
+    pub stroke_width: Option<String>,
+    /// The dash pattern of the border or line (e.g. `4 4`)
+    pub stroke_dasharray: Option<String>,
+    /// The colour of the text
+    pub color: Option<String>,
+}
+
+impl Style {
+    /// Returns a new style with the properties set in `other` taking precedence over the ones of `self`.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The style layered on top
+    pub fn merge(
+        &self,
+        other: &Style,
+    ) -> Style {
+        Style {
+            fill: other.fill.clone().or_else(|| self.fill.clone()),
+            stroke: other.stroke.clone().or_else(|| self.stroke.clone()),
+            stroke_width: other
+                .stroke_width
+                .clone()
+                .or_else(|| self.stroke_width.clone()),
+            stroke_dasharray: other
+                .stroke_dasharray
+                .clone()
+                .or_else(|| self.stroke_dasharray.clone()),
+            color: other.color.clone().or_else(|| self.color.clone()),
+        }
+    }
+
+    /// Returns the style as a comma separated list of CSS properties (e.g. `fill:#12A5F1,stroke:#333`), `None` if no property is set.
+    pub fn to_css(&self) -> Option<String> {
+        let properties: Vec<String> = [
+            ("fill", &self.fill),
+            ("stroke", &self.stroke),
+            ("stroke-width", &self.stroke_width),
+            ("stroke-dasharray", &self.stroke_dasharray),
+            ("color", &self.color),
+        ]
+        .iter()
+        .filter_map(|(property, value)| {
+            value
+                .as_ref()
+                .map(|value| format!("{}:{}", property, value))
+        })
+        .collect();
+
+        if properties.is_empty() {
+            None
+        } else {
+            Some(properties.join(","))
+        }
+    }
+}
+
+/// The shapes of the nodes, keyed the same way as the styles of a [Theme].
+///
+/// The shape for a node's class wins over the one for its scope, which wins over the one for its action.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ShapeMap {
+    /// The shapes keyed by [ScopeType] (e.g. `External`)
+    pub scopes: BTreeMap<String, Shape>,
+    /// The shapes keyed by [ActionType] (e.g. `Mutation`)
+    pub actions: BTreeMap<String, Shape>,
+    /// The shapes keyed by class name (e.g. `Public-View`)
+    pub classes: BTreeMap<String, Shape>,
+}
+
+/// The colours & line styles used to render a diagram, loaded from a TOML or JSON file.
+///
+/// The style of a node is resolved by layering `node`, the style of its [ScopeType], the style of its [ActionType] & finally the
+/// style of its class (e.g. `Public-View`), so every combination is styled even if the theme only lists a few of them.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Theme {
+    /// The name of the theme
+    pub name: String,
+    /// The [Mermaid.js theme](https://mermaid-js.github.io/mermaid/#/theming) the variables are applied to (e.g. `base`, `dark`)
+    pub mermaid_theme: Option<String>,
+    /// The Mermaid.js theme variables (e.g. `primaryColor`, `lineColor`)
+    pub variables: BTreeMap<String, String>,
+    /// The style shared by every node
+    pub node: Style,
+    /// The styles applied on top of `node`, keyed by [ScopeType] (e.g. `Public`)
+    pub scopes: BTreeMap<String, Style>,
+    /// The styles applied on top of the scope styles, keyed by [ActionType] (e.g. `View`)
+    pub actions: BTreeMap<String, Style>,
+    /// The styles applied last, keyed by class name (e.g. `Public-View`)
+    pub classes: BTreeMap<String, Style>,
+    /// The styles of the connections, keyed by [ConnectionType] (e.g. `Emission`)
+    pub edges: BTreeMap<String, Style>,
+    /// The shapes of the nodes, the syntax's default shapes are used where none is set
+    pub shapes: ShapeMap,
+}
+
+impl Theme {
+    /// Returns the built-in theme with the supplied name, `None` if there is no such theme.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of [BUILTIN_THEMES]
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let source = match name {
+            "light" => include_str!("../themes/light.toml"),
+            "dark" => include_str!("../themes/dark.toml"),
+            "high-contrast" => include_str!("../themes/high-contrast.toml"),
+            "print" => include_str!("../themes/print.toml"),
+            _ => return None,
+        };
+        Some(toml::from_str(source).expect("Built-in themes are valid"))
+    }
+
+    /// Reads a theme from a JSON file if the path ends in `.json`, from a TOML file otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the theme file
+    pub fn from_file(path: &Path) -> Result<Theme, Error> {
+        let source = fs::read_to_string(path)?;
+        if path.extension() == Some("json".as_ref()) {
+            serde_json::from_str(&source).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        } else {
+            toml::from_str(&source).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+    }
+
+    /// Returns the built-in theme with the supplied name, or reads the theme file at that path.
+    ///
+    /// # Arguments
+    ///
+    /// * `name_or_path` - One of [BUILTIN_THEMES] or the path of a theme file
+    pub fn load(name_or_path: &str) -> Result<Theme, Error> {
+        match Theme::builtin(name_or_path) {
+            Some(theme) => Ok(theme),
+            None => Theme::from_file(Path::new(name_or_path)).map_err(|e| match e.kind() {
+                ErrorKind::NotFound => Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "{} is neither a built-in theme ({}) nor a theme file",
+                        name_or_path,
+                        BUILTIN_THEMES.join(", ")
+                    ),
+                ),
+                _ => e,
+            }),
+        }
+    }
+
+    /// Resolves the style of a node with the supplied scope & action.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - The scope of the node
+    /// * `action` - The action of the node
+    pub fn node_style(
+        &self,
+        scope: &ScopeType,
+        action: &ActionType,
+    ) -> Style {
+        let class = format!("{}-{}", scope.as_ref(), action.as_ref());
+        let empty = Style::default();
+
+        self.node
+            .merge(self.scopes.get(scope.as_ref()).unwrap_or(&empty))
+            .merge(self.actions.get(action.as_ref()).unwrap_or(&empty))
+            .merge(self.classes.get(&class).unwrap_or(&empty))
+    }
+
+    /// Returns the style of the supplied kind of connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection_type` - The kind of connection
+    pub fn edge_style(
+        &self,
+        connection_type: &ConnectionType,
+    ) -> Style {
+        self.edges
+            .get(connection_type.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the class name & CSS of every [ScopeType] & [ActionType] combination that has a style.
+    pub fn class_styles(&self) -> Vec<(String, String)> {
+        ScopeType::iter()
+            .flat_map(|scope| {
+                ActionType::iter().filter_map(move |action| {
+                    self.node_style(&scope, &action).to_css().map(|css| {
+                        (format!("{}-{}", scope.as_ref(), action.as_ref()), css)
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the [Mermaid.js directive](https://mermaid-js.github.io/mermaid/#/directives) selecting the theme & its variables,
+    /// `None` if the theme doesn't set either.
+    pub fn init_directive(&self) -> Option<String> {
+        if self.mermaid_theme.is_none() && self.variables.is_empty() {
+            return None;
+        }
+
+        let mut init = serde_json::Map::new();
+        if let Some(mermaid_theme) = &self.mermaid_theme {
+            init.insert("theme".to_string(), mermaid_theme.clone().into());
+        }
+        if !self.variables.is_empty() {
+            init.insert(
+                "themeVariables".to_string(),
+                serde_json::to_value(&self.variables).unwrap(),
+            );
+        }
+
+        Some(format!(
+            "%%{{init: {}}}%%",
+            serde_json::Value::Object(init)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_loads_every_builtin_theme() {
+        for name in BUILTIN_THEMES {
+            let theme = Theme::builtin(name).unwrap();
+
+            assert_eq!(theme.name, name);
+            // Every combination & connection kind has to be styled
+            assert_eq!(
+                theme.class_styles().len(),
+                ScopeType::iter().count() * ActionType::iter().count()
+            );
+            for connection_type in ConnectionType::iter() {
+                assert!(theme.edge_style(&connection_type).to_css().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn it_layers_scope_action_and_class_styles() {
+        let theme: Theme = toml::from_str(
+            r##"
+            [node]
+            stroke = "#333"
+            [scopes.Private]
+            fill = "#858585"
+            [actions.Event]
+            fill = "#FFDF80"
+            stroke-dasharray = "4 4"
+            [classes.Private-View]
+            stroke-width = "2px"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            theme
+                .node_style(&ScopeType::Private, &ActionType::Event)
+                .to_css(),
+            Some("fill:#FFDF80,stroke:#333,stroke-dasharray:4 4".to_string())
+        );
+        assert_eq!(
+            theme
+                .node_style(&ScopeType::Private, &ActionType::View)
+                .to_css(),
+            Some("fill:#858585,stroke:#333,stroke-width:2px".to_string())
+        );
+    }
+
+    #[test]
+    fn it_builds_the_init_directive() {
+        let theme: Theme = serde_json::from_str(
+            r##"{"mermaid_theme": "base", "variables": {"lineColor": "#000"}}"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            theme.init_directive(),
+            Some(r##"%%{init: {"theme":"base","themeVariables":{"lineColor":"#000"}}}%%"##.to_string())
+        );
+        assert_eq!(Theme::default().init_directive(), None);
+    }
+}
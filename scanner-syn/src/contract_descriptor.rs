@@ -3,7 +3,12 @@ use std::io::Read;
 use std::iter::IntoIterator;
 
 
-use std::{fs::File, path::Path};
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
 use syn::{Item, ItemStruct};
 
 use crate::core_impl::*;
@@ -39,16 +44,56 @@ pub struct FunctionInfo {
     pub is_out_of_contract_scope: bool,
     /// Whether method is part of `NearEvent` trait
     pub is_event: bool,
+    /// The type the method is implemented for, e.g. `Contract`
+    pub impl_type: Option<String>,
+    /// The trait the method is implemented as part of, e.g. `FungibleTokenCore`
+    pub trait_name: Option<String>,
+    /// The source file of the function, relative to the scanned crate root
+    pub file_path: Option<PathBuf>,
+    /// The first & last line of the function in its source file
+    pub line_span: Option<(usize, usize)>,
+    /// The signature of the function, e.g. `fn add(&mut self, amount: u64) -> u64`
+    pub signature: Option<String>,
+    /// The doc comment of the function, the text of its `///` lines
+    pub docs: Option<String>,
+    /// The arguments of the function, the receiver left out
+    pub arguments: Vec<ArgumentInfo>,
+    /// The type the function returns, if it returns a value
+    pub return_type: Option<String>,
+    /// The serializer of the value the function returns, `json` or `borsh`, if it returns a value
+    pub result_serializer: Option<String>,
+    /// The module path of the function inside the crate, e.g. `["some_fancy_impl", "helpers"]`
+    pub module_path: Vec<String>,
+    /// The `#[ext_contract]` module the method belongs to, set for cross-contract calls
+    pub external_contract: Option<String>,
+    /// Whether this is a `Promise::transfer` of $NEAR
+    pub is_transfer: bool,
+    /// The receiver the function is called on, e.g. `self.token`, set when this is an inner call made as a method call
+    pub receiver: Option<String>,
+    /// The number of call sites, set when this is an inner call of another function
+    pub call_count: usize,
     ///functions are being called by this function
     pub inner_calls: Option<Vec<FunctionInfo>>,
 }
+///Argument of a function scanned by ContractDescriptor
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct ArgumentInfo {
+    /// The name of the argument
+    pub name: String,
+    /// The type of the argument, e.g. `near_sdk::json_types::U128`
+    pub ty: String,
+    /// The serializer of the argument, `json` or `borsh`
+    pub serializer: String,
+}
 ///Contract information from the code scanned by ContractDescriptor
 pub struct ContractInfo {
     pub contract_metadata: Vec<ContractDescriptorMeta>,
 }
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ContractDescriptorMeta {
     pub fns: Vec<FunctionInfo>,
+    /// Methods of the external contracts described with `#[ext_contract]`
+    pub ext_contract_fns: Vec<FunctionInfo>,
     pub connections: Option<Vec<FunctionInfo>>,
     pub tokens: Option<TokenStream>,
 }
@@ -100,6 +145,47 @@ impl ToTokens for FunctionInfo {
         self.to_token_stream()
     }
 }
+///Metadata of the files scanned earlier, re-used for the files whose modification time didn't change since
+#[derive(Default, Debug)]
+pub struct ScanCache {
+    /// The modification time & metadata of every scanned file, by path
+    files: HashMap<PathBuf, (SystemTime, ContractDescriptorMeta)>,
+    /// The number of files parsed by the last scan
+    pub scanned: usize,
+    /// The number of files re-used from the cache by the last scan
+    pub reused: usize,
+}
+
+///Globs choosing the source files of a crate that are scanned, matched against their path relative to the crate root
+#[derive(Default, Debug, Clone)]
+pub struct SourceFilter {
+    /// Only the files matching one of these are scanned, every file if empty, e.g. `src/**/*.rs`
+    pub include: Vec<glob::Pattern>,
+    /// The files matching one of these are skipped, e.g. `src/tests/**`
+    pub exclude: Vec<glob::Pattern>,
+}
+
+impl SourceFilter {
+    ///Compiles the include & exclude globs
+    pub fn new(include: &[String], exclude: &[String]) -> Result<SourceFilter, glob::PatternError> {
+        let compile = |globs: &[String]| globs.iter().map(|glob| glob::Pattern::new(glob)).collect::<Result<Vec<_>, _>>();
+        Ok(SourceFilter {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+    ///Whether a file is scanned, given its path relative to the crate root
+    pub fn is_scanned(&self, relative_path: &Path) -> bool {
+        // `*` stays inside a directory, only `**` crosses into subdirectories
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let matches = |pattern: &glob::Pattern| pattern.matches_path_with(relative_path, options);
+        (self.include.is_empty() || self.include.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
 ///Trait near smart contracts descriptor
 pub trait ContractDescriptor {
     ///Gets the contract information inside the current crate
@@ -109,21 +195,95 @@ pub trait ContractDescriptor {
 }
 
 ///Default Near contract descriptor
-pub struct DefaultContractDescriptor;
+#[derive(Default)]
+pub struct DefaultContractDescriptor {
+    ///The source files scanned by [DefaultContractDescriptor::get_contract_info_for_crate_cached]
+    pub source_filter: SourceFilter,
+}
 
 ///Implementation of Near contract descriptor
 impl DefaultContractDescriptor {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+    ///Creates a descriptor scanning only the source files let through by the filter
+    pub fn with_source_filter(source_filter: SourceFilter) -> Self {
+        Self { source_filter }
     }
     pub fn version()-> String{
         String::from("0.0.1")
     }
+    ///Gets the contract information inside a crate, re-using the metadata of the files unchanged since they were cached
+    pub fn get_contract_info_for_crate_cached(&self, root: Option<&str>, cache: &mut ScanCache) -> ContractInfo {
+        let mut contract_metadata: Vec<ContractDescriptorMeta> = vec![];
+        let mut fns: Vec<FunctionInfo> = vec![];
+        let mut ext_contract_fns: Vec<FunctionInfo> = vec![];
+        cache.scanned = 0;
+        cache.reused = 0;
+        // Walk into every dir to find every `rs` file
+        let root_path=root.unwrap_or(".");
+        for entry in WalkDir::new(root_path).into_iter().filter_map(|e| {
+            let dir = e.unwrap().clone();
+            if !dir.path().to_str().unwrap().contains("test") {
+                return Some(dir);
+            }
+            None
+        }) {
+            let relative_path = entry
+                .path()
+                .strip_prefix(root_path)
+                .unwrap_or_else(|_| entry.path());
+            if entry.path().extension().map(|s| s == "rs").unwrap_or(false) && self.source_filter.is_scanned(relative_path) {
+                //println!("\n{}", entry.path().display());
+                let modified = entry.metadata().ok().and_then(|metadata| metadata.modified().ok());
+                let cached = match (modified, cache.files.get(entry.path())) {
+                    (Some(modified), Some((cached_modified, metadata))) if modified == *cached_modified => Some(metadata.clone()),
+                    _ => None,
+                };
+                let mut metadata = match cached {
+                    Some(metadata) => {
+                        cache.reused += 1;
+                        metadata
+                    }
+                    None => {
+                        let metadata = self.get_tokens_from_file_path(entry.path());
+                        cache.scanned += 1;
+                        if let Some(modified) = modified {
+                            cache.files.insert(entry.path().to_path_buf(), (modified, metadata.clone()));
+                        }
+                        metadata
+                    }
+                };
+                let file_module_path = self.module_path_from_file(relative_path);
+                for f_info in metadata.fns.iter_mut() {
+                    f_info.file_path = Some(relative_path.to_path_buf());
+                    f_info.module_path =
+                        [file_module_path.clone(), f_info.module_path.clone()].concat();
+                }
+                //println!("\n{:?}", metadata.connections);
+                let scoped_fns = metadata.fns.clone();
+                fns.extend(scoped_fns);
+                ext_contract_fns.extend(metadata.ext_contract_fns.clone());
+                contract_metadata.push(metadata);
+            }
+        }
+
+        let resolved = contract_metadata
+            .into_iter()
+            .map(|m| self.resolve_call_hierarchy(m, fns.clone(), &ext_contract_fns))
+            .collect();
+
+        //println!("\n{:?}", resolved);
+        ContractInfo {
+            contract_metadata: resolved,
+        }
+    }
     fn get_inner_calls(
         &self,
         fn_name: String,
         connections: Vec<FunctionInfo>,
         fns: Vec<FunctionInfo>,
+        ext_contract_fns: &[FunctionInfo],
     ) -> Option<Vec<FunctionInfo>> {
         let mut def=fn_name;
         if def=="def_ault"{
@@ -135,27 +295,75 @@ impl DefaultContractDescriptor {
             .find(|el| def == el.name)
             .unwrap();
 
-        let mut fn_iter = fns.into_iter();
-
-        let inner_calls = con_info
-            .inner_calls
-            .unwrap()
-            .into_iter()
-            .filter_map(|ic| -> Option<FunctionInfo> {
-                fn_iter.find(|f| f.name == ic.name && !f.is_payable && !f.is_init)
-            })
-            .collect::<Vec<_>>();
+        let mut inner_calls: Vec<FunctionInfo> = vec![];
+        for ic in con_info.inner_calls.unwrap_or_default() {
+            // Only a `transfer` called on a `Promise::new(..)` chain sends $NEAR & only the methods called on `self` are the
+            // contract's own, e.g. `self.token.transfer(..)` is a method of the token
+            let on_self = matches!(ic.receiver.as_deref(), None | Some("self"));
+            let resolved = if ic.is_transfer {
+                Some(FunctionInfo {
+                    name: ic.name.clone(),
+                    is_transfer: true,
+                    is_out_of_contract_scope: true,
+                    ..Default::default()
+                })
+            } else {
+                fns.iter()
+                    .filter(|_| on_self)
+                    .find(|f| f.name == ic.name && !f.is_payable && !f.is_init)
+                    .or_else(|| ext_contract_fns.iter().find(|f| f.name == ic.name))
+                    .cloned()
+            };
+            if let Some(resolved) = resolved {
+                // Every call site of an already resolved function only increases its count
+                if let Some(known) = inner_calls.iter_mut().find(|f| {
+                    f.name == resolved.name
+                        && f.is_transfer == resolved.is_transfer
+                        && f.external_contract == resolved.external_contract
+                }) {
+                    known.call_count += 1;
+                } else {
+                    inner_calls.push(FunctionInfo {
+                        call_count: 1,
+                        ..resolved
+                    });
+                }
+            }
+        }
 
-        if inner_calls.len() > 0 {
+        if !inner_calls.is_empty() {
             Some(inner_calls)
         } else {
             None
         }
     }
+    /// Derives the module path of a source file from its location relative to the crate root,
+    /// e.g. `src/some_fancy_impl/helpers.rs` becomes `["some_fancy_impl", "helpers"]`.
+    fn module_path_from_file(&self, relative_path: &Path) -> Vec<String> {
+        let mut module_path: Vec<String> = relative_path
+            .with_extension("")
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => part.to_str().map(String::from),
+                _ => None,
+            })
+            .collect();
+        if module_path.first().map(|part| part == "src").unwrap_or(false) {
+            module_path.remove(0);
+        }
+        if matches!(
+            module_path.last().map(String::as_str),
+            Some("lib") | Some("main") | Some("mod")
+        ) {
+            module_path.pop();
+        }
+        module_path
+    }
     fn resolve_call_hierarchy(
         &self,
         metadata: ContractDescriptorMeta,
         fns: Vec<FunctionInfo>,
+        ext_contract_fns: &[FunctionInfo],
     ) -> ContractDescriptorMeta {
         let iiter = fns;
         let connections = metadata.connections.unwrap();
@@ -168,6 +376,7 @@ impl DefaultContractDescriptor {
                     f_info.name.clone(),
                     connections.clone(),
                     iiter.clone(),
+                    ext_contract_fns,
                 ),
                 ..f_info.clone()
             })
@@ -175,6 +384,7 @@ impl DefaultContractDescriptor {
 
         ContractDescriptorMeta {
             fns: result,
+            ext_contract_fns: metadata.ext_contract_fns,
             connections: None,
             tokens: None,
         }
@@ -186,8 +396,10 @@ impl DefaultContractDescriptor {
             visitor.visit_file(&input);
             let connections = visitor.get_connections();
             let fns = visitor.generate_metadata_method().unwrap();
+            let ext_contract_fns = visitor.get_ext_contract_fns();
             syn::Result::Ok(ContractDescriptorMeta {
                 fns,
+                ext_contract_fns,
                 connections: Some(connections),
                 tokens: None,
             })
@@ -204,36 +416,7 @@ impl DefaultContractDescriptor {
 ///Implement contract descriptor trait for DefaultContractDescriptor
 impl ContractDescriptor for DefaultContractDescriptor {
     fn get_contract_info_for_crate(&self,root:Option<&str>) -> ContractInfo {
-        let mut contract_metadata: Vec<ContractDescriptorMeta> = vec![];
-        let mut fns: Vec<FunctionInfo> = vec![];
-        // Walk into every dir to find every `rs` file
-        let root_path=root.unwrap_or(".");
-        for entry in WalkDir::new(root_path).into_iter().filter_map(|e| {
-            let dir = e.unwrap().clone();
-            if !dir.path().to_str().unwrap().contains("test") {
-                return Some(dir);
-            }
-            None
-        }) {
-            if entry.path().extension().map(|s| s == "rs").unwrap_or(false) {
-                //println!("\n{}", entry.path().display());
-                let metadata = self.get_tokens_from_file_path(entry.path());
-                //println!("\n{:?}", metadata.connections);
-                let scoped_fns = metadata.fns.clone();
-                fns.extend(scoped_fns);
-                contract_metadata.push(metadata);
-            }
-        }
-
-        let resolved = contract_metadata
-            .into_iter()
-            .map(|m| self.resolve_call_hierarchy(m, fns.clone()))
-            .collect();
-
-        //println!("\n{:?}", resolved);
-        ContractInfo {
-            contract_metadata: resolved,
-        }
+        self.get_contract_info_for_crate_cached(root, &mut ScanCache::default())
     }
 
     fn get_tokens_from_file_path(&self, file_path: &Path) -> ContractDescriptorMeta {
@@ -248,3 +431,83 @@ impl ContractDescriptor for DefaultContractDescriptor {
         self.metadata(syntax.to_token_stream()).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn it_reuses_the_metadata_of_unchanged_files() {
+        let root = std::env::temp_dir().join(format!("scanner-cache-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub struct Contract;\nimpl Contract { pub fn a(&self) { self.b() } fn b(&self) {} }").unwrap();
+        fs::write(root.join("src/other.rs"), "pub fn c() {}").unwrap();
+        let desc = DefaultContractDescriptor::new();
+        let mut cache = ScanCache::default();
+
+        let first = desc.get_contract_info_for_crate_cached(root.to_str(), &mut cache);
+        assert_eq!((cache.scanned, cache.reused), (2, 0));
+
+        let source = fs::File::options().write(true).open(root.join("src/other.rs")).unwrap();
+        source.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        let second = desc.get_contract_info_for_crate_cached(root.to_str(), &mut cache);
+        assert_eq!((cache.scanned, cache.reused), (1, 1));
+
+        let names = |info: &ContractInfo| -> Vec<String> {
+            let mut names: Vec<String> = info
+                .contract_metadata
+                .iter()
+                .flat_map(|metadata| metadata.fns.iter().map(|f| f.name.clone()))
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&first), names(&second));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_only_scans_the_files_let_through_by_the_source_filter() {
+        let filter = SourceFilter::new(&["src/**/*.rs".to_string()], &["src/generated/*.rs".to_string()]).unwrap();
+
+        assert!(filter.is_scanned(Path::new("src/lib.rs")));
+        assert!(filter.is_scanned(Path::new("src/some_fancy_impl/helpers.rs")));
+        assert!(!filter.is_scanned(Path::new("src/generated/abi.rs")));
+        assert!(!filter.is_scanned(Path::new("build.rs")));
+        assert!(SourceFilter::default().is_scanned(Path::new("build.rs")));
+    }
+
+    #[test]
+    fn it_only_marks_the_promise_transfers() {
+        let root = std::env::temp_dir().join(format!("scanner-transfer-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub struct Contract;
+            #[near_bindgen]
+            impl Contract {
+                pub fn withdraw(&mut self) {
+                    Promise::new(self.owner.clone()).transfer(1);
+                    self.token.transfer(2);
+                    self.transfer(3);
+                }
+                fn transfer(&mut self, amount: u128) {}
+            }",
+        )
+        .unwrap();
+
+        let info = DefaultContractDescriptor::new().get_contract_info_for_crate(root.to_str());
+        let withdraw = info.contract_metadata[0].fns.iter().find(|f| f.name == "withdraw").unwrap();
+        let inner_calls = withdraw.inner_calls.as_ref().unwrap();
+        assert_eq!(inner_calls.len(), 2);
+        assert!(inner_calls[0].is_transfer && inner_calls[0].is_out_of_contract_scope);
+        assert_eq!(inner_calls[0].call_count, 1);
+        // Only the calls on `self` are resolved to the contract's own method, not the one on `self.token`
+        assert_eq!(inner_calls[1].name, "transfer");
+        assert!(!inner_calls[1].is_transfer && !inner_calls[1].is_out_of_contract_scope);
+        assert_eq!(inner_calls[1].call_count, 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
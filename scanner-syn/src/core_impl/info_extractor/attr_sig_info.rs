@@ -1,8 +1,9 @@
 use super::{ArgInfo, BindgenArgType, InitAttr, MethodType, SerializerAttr, SerializerType};
+use crate::contract_descriptor::ArgumentInfo;
 use proc_macro2::Span;
 use quote::ToTokens;
 use syn::spanned::Spanned;
-use syn::{Attribute, Error, FnArg, Ident, Receiver, ReturnType, Signature};
+use syn::{Attribute, Error, FnArg, Ident, Lit, Meta, Receiver, ReturnType, Signature};
 
 /// Information extracted from method attributes and signature.
 pub struct AttrSigInfo {
@@ -174,6 +175,61 @@ impl AttrSigInfo {
         Ok(result)
     }
 
+    /// The signature of the method as it reads in the source, e.g. `fn add(&mut self, amount: u64) -> u64`.
+    pub fn signature(&self) -> String {
+        tidy_tokens(&self.original_sig)
+    }
+
+    /// The arguments of the method, each with its type as it reads in the source.
+    pub fn arguments(&self) -> Vec<ArgumentInfo> {
+        self.args
+            .iter()
+            .map(|arg| ArgumentInfo {
+                name: arg.ident.to_string(),
+                ty: tidy_tokens(&arg.original.ty),
+                serializer: serializer_name(&arg.serializer_ty).to_string(),
+            })
+            .collect()
+    }
+
+    /// The serializer of the value the method returns, `json` or `borsh`, if it returns a value.
+    pub fn result_serializer_name(&self) -> Option<String> {
+        match &self.returns {
+            ReturnType::Default => None,
+            ReturnType::Type(..) => Some(serializer_name(&self.result_serializer).to_string()),
+        }
+    }
+
+    /// The type the method returns as it reads in the source, if it returns a value.
+    pub fn return_type(&self) -> Option<String> {
+        match &self.returns {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some(tidy_tokens(ty)),
+        }
+    }
+
+    /// The doc comment of the method, the text of its `///` lines without the leading space, if it has any.
+    pub fn docs(&self) -> Option<String> {
+        let lines: Vec<String> = self
+            .non_bindgen_attrs
+            .iter()
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::NameValue(meta)) if meta.path.is_ident("doc") => match meta.lit {
+                    Lit::Str(doc) => Some(doc.value()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+            .collect();
+        let docs = lines.join("\n").trim().to_string();
+        if docs.is_empty() {
+            None
+        } else {
+            Some(docs)
+        }
+    }
+
     /// Only get args that correspond to `env::input()`.
     pub fn input_args(&self) -> impl Iterator<Item = &ArgInfo> {
         self.args
@@ -181,3 +237,36 @@ impl AttrSigInfo {
             .filter(|arg| matches!(arg.bindgen_ty, BindgenArgType::Regular))
     }
 }
+
+/// Prints tokens the way Rust code is written, rather than space separated.
+fn tidy_tokens<T: ToTokens>(tokens: &T) -> String {
+    [
+        (" :: ", "::"),
+        (":: ", "::"),
+        (" : ", ": "),
+        (" (", "("),
+        ("( ", "("),
+        (" )", ")"),
+        (" ,", ","),
+        ("& ", "&"),
+        (" < ", "<"),
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        ("[ ", "["),
+        (" ]", "]"),
+        (" ;", ";"),
+    ]
+    .iter()
+    .fold(tokens.to_token_stream().to_string(), |printed, (from, to)| {
+        printed.replace(from, to)
+    })
+}
+
+/// The name of a serializer as its attribute spells it, e.g. `#[serializer(borsh)]`.
+fn serializer_name(serializer: &SerializerType) -> &'static str {
+    match serializer {
+        SerializerType::JSON => "json",
+        SerializerType::Borsh => "borsh",
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core_impl::info_extractor::AttrSigInfo;
+use syn::spanned::Spanned;
 use syn::{ImplItemMethod, Type, Visibility};
 
 /// Information extracted from `ImplItemMethod`.
@@ -13,6 +14,8 @@ pub struct ImplItemMethodInfo {
     pub has_near_sdk_attr: bool,
     /// The type of the contract struct.
     pub struct_type: Type,
+    /// The first and last line of the method in its source file.
+    pub line_span: (usize, usize),
 }
 
 impl ImplItemMethodInfo {
@@ -23,6 +26,8 @@ impl ImplItemMethodInfo {
         has_near_sdk_attr: bool,
         struct_type: Type,
     ) -> syn::Result<Self> {
+        let span = original.span();
+        let line_span = (span.start().line, span.end().line);
         let ImplItemMethod { attrs, sig, .. } = original;
         // TODO:
         // let mut functions_called = vec![];
@@ -35,6 +40,7 @@ impl ImplItemMethodInfo {
             is_trait_impl,
             has_near_sdk_attr,
             struct_type,
+            line_span,
         })
     }
 }
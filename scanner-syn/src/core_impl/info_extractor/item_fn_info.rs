@@ -1,18 +1,24 @@
 use crate::core_impl::info_extractor::AttrSigInfo;
+use syn::spanned::Spanned;
 use syn::ItemFn;
 
 /// Information extracted from `ItemFn`.
 pub struct ItemFnInfo {
     /// Information on the attributes and the signature of the function.
     pub attr_signature_info: AttrSigInfo,
+    /// The first and last line of the function in its source file.
+    pub line_span: (usize, usize),
 }
 
 impl ItemFnInfo {
     /// Process the function and extract important information.
     pub fn new(original: &mut ItemFn) -> syn::Result<Self> {
+        let span = original.span();
+        let line_span = (span.start().line, span.end().line);
         let x = AttrSigInfo::new(&mut original.attrs, &mut original.sig)?;
         Ok(Self {
             attr_signature_info: x,
+            line_span,
         })
     }
 }
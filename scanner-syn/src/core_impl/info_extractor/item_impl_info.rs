@@ -1,11 +1,13 @@
 use crate::ImplItemMethodInfo;
 use syn::spanned::Spanned;
-use syn::{Error, ImplItem, ItemImpl, Type};
+use syn::{Error, ImplItem, ItemImpl, Path, Type};
 
 /// Information extracted from `impl` section.
 pub struct ItemImplInfo {
     /// Whether this is a trait implementation.
     pub is_trait_impl: bool,
+    /// The path of the trait being implemented, if any.
+    pub trait_path: Option<Path>,
     /// Whether `impl` section decorated with `#[near_bindgen]`
     pub has_near_sdk_attr: bool,
     /// The type for which this `impl` is written.
@@ -23,6 +25,7 @@ impl ItemImplInfo {
             ));
         }
         let is_trait_impl = original.trait_.is_some();
+        let trait_path = original.trait_.as_ref().map(|(_, path, _)| path.clone());
         let ty = (*original.self_ty.as_ref()).clone();
 
         let mut methods = vec![];
@@ -35,6 +38,7 @@ impl ItemImplInfo {
         }
         Ok(Self {
             is_trait_impl,
+            trait_path,
             has_near_sdk_attr,
             ty,
             methods,
@@ -1,11 +1,12 @@
 use crate::{
     contract_descriptor::FunctionInfo,
     core_impl::{metadata::type_is_event, AttrSigInfo},
-    BindgenArgType, ImplItemMethodInfo, InputStructType, MethodType, SerializerType,
+    BindgenArgType, ImplItemMethodInfo, InputStructType, ItemTraitInfo, MethodType,
+    SerializerType,
 };
 
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, ToTokens};
 use syn::ReturnType;
 
 impl ImplItemMethodInfo {
@@ -20,11 +21,23 @@ impl ImplItemMethodInfo {
             method_name_str=method_name_str.replace("default", "def_ault");
         }
         let is_event = type_is_event(&self.struct_type);
+        let impl_type = Some(self.struct_type.to_token_stream().to_string().replace(' ', ""));
+        let signature = Some(self.attr_signature_info.signature());
+        let docs = self.attr_signature_info.docs();
+        let arguments = self.attr_signature_info.arguments();
+        let return_type = self.attr_signature_info.return_type();
+        let result_serializer = self.attr_signature_info.result_serializer_name();
         if !is_event && !self.has_near_sdk_attr {
             let function_info = FunctionInfo {
                 name: method_name_str,
                 is_process: matches!(self.attr_signature_info.returns, ReturnType::Default),
                 is_out_of_contract_scope: true,
+                impl_type,
+                signature,
+                docs,
+                arguments,
+                return_type,
+                result_serializer,
                 ..Default::default()
             };
             return function_info;
@@ -129,11 +142,56 @@ impl ImplItemMethodInfo {
             is_out_of_contract_scope: false,
             is_event,
             is_test,
+            impl_type,
+            signature,
+            docs,
+            arguments,
+            return_type,
+            result_serializer,
             inner_calls: None,
+            ..Default::default()
         }
     }
 }
 
+impl ItemTraitInfo {
+    /// A public method that creates info about the methods of an `#[ext_contract]` trait.
+    ///
+    /// # Returns
+    ///
+    /// * The `FunctionInfo` structs of the external contract methods.
+    pub fn metadata_structs(&self) -> Vec<FunctionInfo> {
+        let contract_name = self.mod_name.to_string();
+        self.methods
+            .iter()
+            .map(|method| {
+                let sig_info = &method.attr_sig_info;
+                let is_mutable = sig_info
+                    .receiver
+                    .as_ref()
+                    .map(|receiver| receiver.mutability.is_some() && receiver.reference.is_some())
+                    .unwrap_or(false);
+                FunctionInfo {
+                    name: sig_info.ident.to_string(),
+                    is_public: true,
+                    is_payable: sig_info.is_payable,
+                    is_view: matches!(&sig_info.method_type, &MethodType::View),
+                    is_mutable,
+                    is_process: matches!(sig_info.returns, ReturnType::Default),
+                    is_out_of_contract_scope: true,
+                    external_contract: Some(contract_name.clone()),
+                    signature: Some(sig_info.signature()),
+                    docs: sig_info.docs(),
+                    arguments: sig_info.arguments(),
+                    return_type: sig_info.return_type(),
+                    result_serializer: sig_info.result_serializer_name(),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+}
+
 /// A public function that creates info about the function.
 ///
 /// # Arguments
@@ -153,6 +211,11 @@ pub fn metadata_fn_struct(sig_info: &AttrSigInfo) -> FunctionInfo {
         is_test: sig_info.is_test,
         is_payable: sig_info.is_payable,
         is_public: !sig_info.is_private,
+        signature: Some(sig_info.signature()),
+        docs: sig_info.docs(),
+        arguments: sig_info.arguments(),
+        return_type: sig_info.return_type(),
+        result_serializer: sig_info.result_serializer_name(),
         ..Default::default()
     }
 }
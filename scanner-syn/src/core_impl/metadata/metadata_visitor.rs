@@ -5,22 +5,35 @@
 use std::collections::HashMap;
 
 use crate::contract_descriptor::FunctionInfo;
-use crate::{ItemFnInfo, ItemImplInfo};
+use crate::{ItemFnInfo, ItemImplInfo, ItemTraitInfo};
 
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::visit::Visit;
-use syn::{Error, Expr, ExprCall, ExprMethodCall, Ident, ImplItemMethod, ItemFn, ItemImpl};
+use syn::{
+    Error, Expr, ExprCall, ExprMethodCall, Ident, ImplItemMethod, ItemFn, ItemImpl, ItemMod,
+    ItemTrait,
+};
 
 use super::metadata_generator::metadata_fn_struct;
 
+/// A call made by a method: the function called, whether it's a `Promise` transfer of $NEAR & the receiver of a method call.
+type Call = (TokenStream, bool, Option<String>);
+
 /// Information relevant to metadata extracted from the `impl` section decorated with `#[near_bindgen]`.
 #[derive(Default)]
 pub struct MetadataVisitor {
-    impl_item_infos: Vec<ItemImplInfo>,
-    fn_items_infos: Vec<ItemFnInfo>,
+    /// Extracted `impl` sections along with the inline module path they were found in.
+    impl_item_infos: Vec<(Vec<String>, ItemImplInfo)>,
+    /// Extracted functions along with the inline module path they were found in.
+    fn_items_infos: Vec<(Vec<String>, ItemFnInfo)>,
+    /// Traits decorated with `#[ext_contract]` describing external contracts.
+    ext_contract_infos: Vec<ItemTraitInfo>,
+    /// Names of the inline `mod` blocks enclosing the item currently visited.
+    module_path: Vec<String>,
     /// Errors that occurred while extracting the data.
-    connections: Vec<(TokenStream, Vec<TokenStream>)>,
+    /// The calls made by every method.
+    connections: Vec<(TokenStream, Vec<Call>)>,
     errors: Vec<Error>,
 }
 
@@ -41,7 +54,7 @@ impl<'ast> Visit<'ast> for MetadataVisitor {
             .iter()
             .any(|attr| attr.path.to_token_stream().to_string().as_str() == "near_bindgen");
         match ItemImplInfo::new(&mut i.clone(), has_near_sdk_attr) {
-            Ok(info) => self.impl_item_infos.push(info),
+            Ok(info) => self.impl_item_infos.push((self.module_path.clone(), info)),
             Err(err) => self.errors.push(err),
         }
         syn::visit::visit_item_impl(self, i);
@@ -58,7 +71,7 @@ impl<'ast> Visit<'ast> for MetadataVisitor {
     /// or add the errors that occurred while extracting the data.
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
         match ItemFnInfo::new(&mut i.clone()) {
-            Ok(info) => self.fn_items_infos.push(info),
+            Ok(info) => self.fn_items_infos.push((self.module_path.clone(), info)),
             Err(err) => self.errors.push(err),
         }
         self.connections
@@ -66,6 +79,39 @@ impl<'ast> Visit<'ast> for MetadataVisitor {
         syn::visit::visit_item_fn(self, i);
     }
 
+    /// A method that will visit every trait in a file.
+    /// Only traits decorated with `#[ext_contract]` are extracted, as they describe external contracts.
+    ///
+    /// # Arguments
+    ///
+    /// * `i`: The trait tree.
+    fn visit_item_trait(&mut self, i: &'ast ItemTrait) {
+        let ext_contract_attr = i
+            .attrs
+            .iter()
+            .find(|attr| attr.path.to_token_stream().to_string().as_str() == "ext_contract");
+        if let Some(attr) = ext_contract_attr {
+            let mod_name_override = attr.parse_args::<Ident>().ok();
+            match ItemTraitInfo::new(&mut i.clone(), mod_name_override) {
+                Ok(info) => self.ext_contract_infos.push(info),
+                Err(err) => self.errors.push(err),
+            }
+        }
+        syn::visit::visit_item_trait(self, i);
+    }
+
+    /// A method that will visit every inline module in a file.
+    /// It keeps track of the module path so that extracted items know where they live.
+    ///
+    /// # Arguments
+    ///
+    /// * `i`: The module tree.
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        self.module_path.push(i.ident.to_string());
+        syn::visit::visit_item_mod(self, i);
+        self.module_path.pop();
+    }
+
     fn visit_impl_item_method(&mut self, i: &'ast ImplItemMethod) {
         self.connections
             .push((i.sig.ident.to_token_stream(), vec![]));
@@ -75,17 +121,42 @@ impl<'ast> Visit<'ast> for MetadataVisitor {
     // TODO: find a way to not parse all(59) ways we can call a function
     fn visit_expr_call(&mut self, i: &'ast ExprCall) {
         let (_name, functions) = self.connections.last_mut().expect("Not stable way");
-        functions.push(i.func.to_token_stream());
+        functions.push((i.func.to_token_stream(), false, None));
         syn::visit::visit_expr_call(self, i);
     }
 
     fn visit_expr_method_call(&mut self, i: &'ast ExprMethodCall) {
         let (_name, functions) = self.connections.last_mut().expect("Not stable way");
-        functions.push(i.method.to_token_stream());
+        functions.push((
+            i.method.to_token_stream(),
+            i.method == "transfer" && is_promise_chain(&i.receiver),
+            Some(i.receiver.to_token_stream().to_string().replace(' ', "")),
+        ));
         syn::visit::visit_expr_method_call(self, i);
     }
 }
 
+/// Returns `true` if an expression is a chain of calls starting from `Promise::new(..)`, e.g.
+/// `Promise::new(account_id).function_call(..)`.
+///
+/// # Arguments
+///
+/// * `expr` - The receiver of a method call
+fn is_promise_chain(expr: &Expr) -> bool {
+    match expr {
+        Expr::MethodCall(call) => is_promise_chain(&call.receiver),
+        Expr::Paren(paren) => is_promise_chain(&paren.expr),
+        Expr::Call(call) => match &*call.func {
+            Expr::Path(path) => {
+                let segments: Vec<String> = path.path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+                segments.ends_with(&["Promise".to_string(), "new".to_string()])
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 impl MetadataVisitor {
     pub fn new() -> Self {
         Default::default()
@@ -103,14 +174,29 @@ impl MetadataVisitor {
         let mut methods: Vec<FunctionInfo> = self
             .impl_item_infos
             .iter()
-            .flat_map(|i| &i.methods)
-            .map(|m| m.metadata_struct())
+            .flat_map(|(module_path, i)| {
+                let trait_name = i
+                    .trait_path
+                    .as_ref()
+                    .and_then(|path| path.segments.last())
+                    .map(|segment| segment.ident.to_string());
+                i.methods.iter().map(move |m| FunctionInfo {
+                    trait_name: trait_name.clone(),
+                    module_path: module_path.clone(),
+                    line_span: Some(m.line_span),
+                    ..m.metadata_struct()
+                })
+            })
             .filter(|f| !f.is_test)
             .collect();
         let functions: Vec<FunctionInfo> = self
             .fn_items_infos
             .iter()
-            .map(|s| metadata_fn_struct(&s.attr_signature_info))
+            .map(|(module_path, s)| FunctionInfo {
+                module_path: module_path.clone(),
+                line_span: Some(s.line_span),
+                ..metadata_fn_struct(&s.attr_signature_info)
+            })
             .filter(|f| !f.is_test)
             .collect();
 
@@ -119,6 +205,18 @@ impl MetadataVisitor {
         Ok(methods)
     }
 
+    /// A method that returns the methods of the external contracts described in this file.
+    ///
+    /// # Returns
+    ///
+    /// * The information about every `#[ext_contract]` trait method in this file
+    pub fn get_ext_contract_fns(&self) -> Vec<FunctionInfo> {
+        self.ext_contract_infos
+            .iter()
+            .flat_map(|info| info.metadata_structs())
+            .collect()
+    }
+
     pub fn get_connections(&self) -> Vec<FunctionInfo> {
         self.connections
             .iter()
@@ -126,11 +224,13 @@ impl MetadataVisitor {
                 let ident: Ident = syn::parse2(m.to_owned()).unwrap();
                 let exps: Vec<FunctionInfo> = c
                     .iter()
-                    .filter_map(|fs| {
+                    .filter_map(|(fs, is_transfer, receiver)| {
                         let ident: Result<Ident, Error> = syn::parse2(fs.to_owned());
                         if ident.is_ok() {
                             return Some(FunctionInfo {
                                 name: ident.unwrap().to_string(),
+                                is_transfer: *is_transfer,
+                                receiver: receiver.clone(),
                                 ..Default::default()
                             });
                         }
@@ -254,4 +354,114 @@ mod tests {
             #(#actual),*
         }.to_string());
     }
+
+    #[test]
+    fn ext_contract_methods() {
+        let code = quote! {
+            #[ext_contract(ext_ft)]
+            pub trait FungibleToken {
+                fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128);
+                fn ft_balance_of(&self, account_id: AccountId) -> U128;
+            }
+        };
+
+        let file: syn::File = syn::parse2(code).unwrap();
+
+        let mut visitor = MetadataVisitor::new();
+        visitor.visit_file(&file);
+
+        let actual = visitor.get_ext_contract_fns();
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].name, "ft_transfer");
+        assert_eq!(actual[0].external_contract.as_deref(), Some("ext_ft"));
+        assert!(actual[0].is_mutable);
+        assert_eq!(actual[1].name, "ft_balance_of");
+        assert!(actual[1].is_view);
+    }
+
+    #[test]
+    fn line_spans() {
+        // Parsed from source text rather than `quote!` so the tokens carry their real locations
+        let code = "#[near_bindgen]
+impl Hello {
+    pub fn f1(&self) {
+        helper();
+    }
+}
+
+fn helper() {}
+";
+
+        let file: syn::File = syn::parse_file(code).unwrap();
+
+        let mut visitor = MetadataVisitor::new();
+        visitor.visit_file(&file);
+
+        let actual = visitor.generate_metadata_method().unwrap();
+        assert_eq!(actual[0].name, "f1");
+        assert_eq!(actual[0].line_span, Some((3, 5)));
+        assert_eq!(actual[1].name, "helper");
+        assert_eq!(actual[1].line_span, Some((8, 8)));
+    }
+
+    #[test]
+    fn signatures() {
+        let code = quote! {
+            #[near_bindgen]
+            impl Hello {
+                pub fn f1(&mut self, #[callback_unwrap] arg0: near_sdk::json_types::U128, arg1: Vec<u8>) -> Option<(u64, String)> { }
+            }
+
+            fn helper<'a, T: Clone>(value: &'a [T; 2]) { }
+        };
+
+        let file: syn::File = syn::parse2(code).unwrap();
+
+        let mut visitor = MetadataVisitor::new();
+        visitor.visit_file(&file);
+
+        let actual = visitor.generate_metadata_method().unwrap();
+        assert_eq!(
+            actual[0].signature.as_deref(),
+            Some("fn f1(&mut self, arg0: near_sdk::json_types::U128, arg1: Vec<u8>) -> Option<(u64, String)>")
+        );
+        assert_eq!(
+            actual[1].signature.as_deref(),
+            Some("fn helper<'a, T: Clone>(value: &'a [T; 2])")
+        );
+        assert_eq!(
+            actual[0].arguments.iter().map(|arg| (arg.name.as_str(), arg.ty.as_str())).collect::<Vec<_>>(),
+            vec![("arg0", "near_sdk::json_types::U128"), ("arg1", "Vec<u8>")]
+        );
+        assert_eq!(actual[0].return_type.as_deref(), Some("Option<(u64, String)>"));
+        assert_eq!(actual[0].result_serializer.as_deref(), Some("json"));
+        assert_eq!(actual[0].arguments[1].serializer, "json");
+        assert_eq!(actual[1].return_type, None);
+    }
+
+    #[test]
+    fn doc_comments() {
+        let code = "#[near_bindgen]
+impl Hello {
+    /// Adds the amount to the counter.
+    ///
+    /// Only the owner may call it.
+    pub fn f1(&self) { }
+
+    pub fn f2(&self) { }
+}
+";
+
+        let file: syn::File = syn::parse_file(code).unwrap();
+
+        let mut visitor = MetadataVisitor::new();
+        visitor.visit_file(&file);
+
+        let actual = visitor.generate_metadata_method().unwrap();
+        assert_eq!(
+            actual[0].docs.as_deref(),
+            Some("Adds the amount to the counter.\n\nOnly the owner may call it.")
+        );
+        assert_eq!(actual[1].docs, None);
+    }
 }